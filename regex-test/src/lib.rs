@@ -3,17 +3,20 @@
 pub extern crate bstr;
 
 use std::borrow::Borrow;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::Path;
+use std::time::{Duration, Instant};
 
 use anyhow::{bail, Context, Result};
 use bstr::{BStr, BString, ByteSlice, ByteVec};
 use serde::Deserialize;
+use serde_json::{json, Value};
 
 mod escape;
 
 const ENV_REGEX_TEST: &str = "REGEX_TEST";
+const ENV_REGEX_TEST_SEED: &str = "REGEX_TEST_SEED";
 
 /// A collection of regex tests.
 #[derive(Clone, Debug, Deserialize)]
@@ -21,6 +24,11 @@ pub struct RegexTests {
     tests: Vec<RegexTest>,
     #[serde(skip)]
     seen: HashSet<String>,
+    /// The last number automatically assigned as a name to a test in each
+    /// group. This ensures that loading more tests into the same group
+    /// continues numbering where it left off.
+    #[serde(skip)]
+    auto_names: HashMap<String, usize>,
 }
 
 /// A regex test describes the inputs and expected outputs of a regex match.
@@ -39,17 +47,22 @@ pub struct RegexTest {
     #[serde(rename = "match")]
     is_match: Option<bool>,
     which_matches: Option<Vec<usize>>,
+    #[serde(default = "default_true")]
+    match_which_order: bool,
     matches: Option<Vec<Match>>,
     captures: Option<Vec<Captures>>,
     match_limit: Option<usize>,
     #[serde(default = "default_true")]
     compiles: bool,
+    compile_error: Option<BString>,
     #[serde(default)]
     anchored: bool,
     #[serde(default)]
     case_insensitive: bool,
     #[serde(default)]
     unescape: bool,
+    #[serde(default)]
+    unescape_regex: bool,
     #[serde(default = "default_true")]
     unicode: bool,
     #[serde(default = "default_true")]
@@ -58,6 +71,23 @@ pub struct RegexTest {
     match_kind: MatchKind,
     #[serde(default)]
     search_kind: SearchKind,
+    pattern_flags: Option<Vec<PatternFlagsFormat>>,
+}
+
+/// The flags that apply to a single pattern in a regex test.
+///
+/// By default, every pattern in a test uses the test's global flags. A test
+/// may override them for each pattern via a `pattern_flags` array, which must
+/// have one entry per regex. Any flag omitted from an entry uses the global
+/// setting.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PatternFlags {
+    /// Whether the pattern should match without regard to case.
+    pub case_insensitive: bool,
+    /// Whether the pattern should have Unicode mode enabled.
+    pub unicode: bool,
+    /// Whether the pattern should exclusively match valid UTF-8.
+    pub utf8: bool,
 }
 
 #[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq)]
@@ -132,14 +162,49 @@ impl std::fmt::Debug for Match {
 }
 
 /// Captures represents a single group of captured matches from a regex search.
-#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+///
+/// Each capturing group may optionally have a name. Names are only used to
+/// look up groups and to report failures. In particular, two sets of
+/// captures are equal whenever their groups are equal, regardless of names.
+#[derive(Clone, Deserialize)]
 #[serde(from = "CapturesFormat")]
-pub struct Captures(Vec<Option<Match>>);
+pub struct Captures {
+    groups: Vec<Option<Match>>,
+    names: Vec<Option<String>>,
+}
+
+impl PartialEq for Captures {
+    fn eq(&self, other: &Captures) -> bool {
+        self.groups == other.groups
+    }
+}
+
+impl Eq for Captures {}
+
+impl std::fmt::Debug for Captures {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "[")?;
+        for (i, group) in self.groups.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            if let Some(name) = self.name(i) {
+                write!(f, "{}: ", name)?;
+            }
+            write!(f, "{:?}", group)?;
+        }
+        write!(f, "]")
+    }
+}
 
 impl RegexTests {
     /// Create a new empty collection of glob tests.
     pub fn new() -> RegexTests {
-        RegexTests { tests: vec![], seen: HashSet::new() }
+        RegexTests {
+            tests: vec![],
+            seen: HashSet::new(),
+            auto_names: HashMap::new(),
+        }
     }
 
     /// Loads all of the tests in the given TOML file. The group name assigned
@@ -163,41 +228,130 @@ impl RegexTests {
         Ok(())
     }
 
+    /// Loads all of the tests in every TOML file found in the given directory,
+    /// including in its subdirectories. Each file is loaded as if by
+    /// [`RegexTests::load`], so the group name of each test is the stem of
+    /// the file it came from.
+    ///
+    /// Files are loaded in lexicographic order of their paths, so the order
+    /// of the tests loaded doesn't depend on the platform. If any file fails
+    /// to load, then the error returned names that file.
+    pub fn load_dir<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
+        let mut paths = vec![];
+        find_toml_files(path.as_ref(), &mut paths)?;
+        paths.sort();
+        for path in paths {
+            self.load(&path)?;
+        }
+        Ok(())
+    }
+
     /// Load all of the TOML encoded tests in `data` into this collection.
     /// The given group name is assigned to all loaded tests.
+    ///
+    /// Tests without a name are named by number, starting at `1` in each
+    /// group. If tests are loaded into a group that already has tests, then
+    /// numbering continues from the last number assigned in that group.
     pub fn load_slice(&mut self, group_name: &str, data: &[u8]) -> Result<()> {
-        let mut index = 1;
         let mut tests: RegexTests =
             toml::from_slice(&data).context("error decoding TOML")?;
         for t in &mut tests.tests {
             t.group = group_name.to_string();
             if t.name.is_empty() {
+                let index =
+                    self.auto_names.entry(group_name.to_string()).or_insert(0);
+                *index += 1;
                 t.name = format!("{}", index);
-                index += 1;
-            }
-            t.full_name = format!("{}/{}", t.group, t.name);
-            if t.unescape {
-                t.input = BString::from(crate::escape::unescape(&t.input));
             }
-
-            t.validate().with_context(|| {
-                format!("error loading test '{}'", t.full_name())
-            })?;
-            if self.seen.contains(t.full_name()) {
-                bail!("found duplicate tests for name '{}'", t.full_name());
-            }
-            self.seen.insert(t.full_name().to_string());
+            self.prepare(t)?;
         }
         self.tests.extend(tests.tests);
         Ok(())
     }
 
+    /// Add a single test that was constructed in code, e.g., via
+    /// [`RegexTest::builder`], to this collection.
+    ///
+    /// The test is prepared and validated in the same way as tests loaded
+    /// from TOML. In particular, its input and regexes are unescaped if
+    /// requested, and an error is returned if the test is invalid or if a
+    /// test with the same full name has already been added to this
+    /// collection.
+    ///
+    /// Unlike tests loaded from TOML, the test given must have both a group
+    /// and a name.
+    pub fn push(&mut self, mut test: RegexTest) -> Result<()> {
+        if test.group.is_empty() {
+            bail!("test '{}' must have a group", test.name);
+        }
+        if test.name.is_empty() {
+            bail!("test in group '{}' must have a name", test.group);
+        }
+        self.prepare(&mut test)?;
+        self.tests.push(test);
+        Ok(())
+    }
+
+    /// Fill in the full name of the given test, unescape its input and
+    /// regexes if requested and validate it. The test's group and name must
+    /// already be set.
+    ///
+    /// This returns an error if the test is invalid or if its full name has
+    /// already been seen by this collection. Otherwise, the full name is
+    /// recorded as seen.
+    fn prepare(&mut self, t: &mut RegexTest) -> Result<()> {
+        t.full_name = format!("{}/{}", t.group, t.name);
+        if t.unescape {
+            t.input = BString::from(crate::escape::unescape(&t.input));
+        }
+        if t.unescape_regex {
+            let unescape = |re: &mut BString| {
+                *re = BString::from(crate::escape::unescape(&re[..]));
+            };
+            t.regex.iter_mut().for_each(unescape);
+            t.regexes.iter_mut().flatten().for_each(unescape);
+        }
+
+        t.validate().with_context(|| {
+            format!("error loading test '{}'", t.full_name())
+        })?;
+        if self.seen.contains(t.full_name()) {
+            bail!(
+                "found duplicate tests for name '{}' in group '{}'",
+                t.name(),
+                t.group(),
+            );
+        }
+        self.seen.insert(t.full_name().to_string());
+        Ok(())
+    }
+
     /// Return an iterator over all regex tests that have been loaded. The
     /// order of the iterator corresponds to the order in which the tests were
     /// loaded.
     pub fn iter(&self) -> RegexTestsIter {
         RegexTestsIter { it: self.tests.iter() }
     }
+
+    /// Return an iterator over all regex tests in the given group. The order
+    /// of the iterator corresponds to the order in which the tests were
+    /// loaded.
+    ///
+    /// If no tests belong to the given group, then the iterator is empty.
+    pub fn iter_group<'a>(
+        &'a self,
+        group: &'a str,
+    ) -> impl Iterator<Item = &'a RegexTest> + 'a {
+        self.iter().filter(move |t| t.group() == group)
+    }
+
+    /// Return an iterator over the distinct group names of all loaded tests.
+    /// Each group name is yielded once, in the order in which its first test
+    /// was loaded.
+    pub fn groups(&self) -> impl Iterator<Item = &str> {
+        let mut seen = HashSet::new();
+        self.tests.iter().map(|t| t.group()).filter(move |g| seen.insert(*g))
+    }
 }
 
 impl Captures {
@@ -209,11 +363,38 @@ impl Captures {
     /// `None` value should be used. (Consequently, the 0th capturing group
     /// should never be `None`.)
     pub fn new<I: IntoIterator<Item = Option<Match>>>(it: I) -> Captures {
-        Captures(it.into_iter().collect())
+        let groups: Vec<Option<Match>> = it.into_iter().collect();
+        let names = vec![None; groups.len()];
+        Captures { groups, names }
+    }
+
+    /// Return the capturing group with the given name.
+    ///
+    /// If there is no group with the given name, then `None` is returned.
+    /// Otherwise, the group is returned, which is itself `None` if it did
+    /// not participate in the match.
+    pub fn group_by_name(&self, name: &str) -> Option<Option<Match>> {
+        let i = self.names.iter().position(|n| n.as_deref() == Some(name))?;
+        Some(self.groups[i].clone())
+    }
+
+    /// Return the name of the capturing group at the given index, if it has
+    /// one.
+    fn name(&self, i: usize) -> Option<&str> {
+        self.names.get(i).and_then(|n| n.as_deref())
     }
 }
 
 impl RegexTest {
+    /// Create a builder for constructing a test in code, without writing
+    /// TOML.
+    ///
+    /// The test built can be added to a collection of tests with
+    /// [`RegexTests::push`], which also validates it.
+    pub fn builder() -> RegexTestBuilder {
+        RegexTestBuilder::new()
+    }
+
     fn test(&self, regex: &mut CompiledRegex) -> Vec<TestResult> {
         match regex.match_regex {
             None => vec![TestResult::skip()],
@@ -228,6 +409,16 @@ impl RegexTest {
             bail!("only one of 'regex' or 'regexes' can be present");
         }
 
+        if let Some(ref flags) = self.pattern_flags {
+            if flags.len() != self.regexes().len() {
+                bail!(
+                    "'pattern_flags' has {} entries, but there are {} regexes",
+                    flags.len(),
+                    self.regexes().len(),
+                );
+            }
+        }
+
         let mut match_field_count = 0;
         if self.is_match.is_some() {
             match_field_count += 1;
@@ -346,9 +537,9 @@ impl RegexTest {
         match self.regex_matches() {
             RegexMatches::YesNo(_) | RegexMatches::Which(_) => None,
             RegexMatches::AllStartEnd(matches) => Some(matches.to_vec()),
-            RegexMatches::AllCaptures(matches) => {
-                Some(matches.iter().map(|c| c.0[0].clone().unwrap()).collect())
-            }
+            RegexMatches::AllCaptures(matches) => Some(
+                matches.iter().map(|c| c.groups[0].clone().unwrap()).collect(),
+            ),
         }
     }
 
@@ -362,6 +553,28 @@ impl RegexTest {
         }
     }
 
+    /// Like `matches`, but only includes the first `match_limit` matches
+    /// when a match limit is set. This is what a regex implementation's
+    /// results are compared against.
+    fn limited_matches(&self) -> Option<Vec<Match>> {
+        let mut matches = self.matches()?;
+        if let Some(limit) = self.match_limit() {
+            matches.truncate(limit);
+        }
+        Some(matches)
+    }
+
+    /// Like `captures`, but only includes the first `match_limit` matches
+    /// when a match limit is set. This is what a regex implementation's
+    /// results are compared against.
+    fn limited_captures(&self) -> Option<Vec<Captures>> {
+        let mut captures = self.captures()?;
+        if let Some(limit) = self.match_limit() {
+            captures.truncate(limit);
+        }
+        Some(captures)
+    }
+
     fn regex_matches(&self) -> RegexMatches {
         if let Some(ref is_match) = self.is_match {
             RegexMatches::YesNo(*is_match)
@@ -385,9 +598,49 @@ impl RegexTest {
         self.match_limit
     }
 
+    /// Returns true if the order of the regexes reported by a `which` test
+    /// result must match the order of [`RegexTest::which_matches`].
+    ///
+    /// When this is disabled, the regexes reported may be in any order, and
+    /// are compared to the expected regexes as if both were sorted. This is
+    /// useful for implementations that report matching regexes in priority
+    /// order instead of ascending order.
+    ///
+    /// This is enabled by default.
+    pub fn match_which_order(&self) -> bool {
+        self.match_which_order
+    }
+
+    /// Returns true if the given regex indices are equivalent to the regexes
+    /// this test expects to match, taking `match_which_order` into account.
+    fn which_matches_eq(&self, got: &[usize]) -> bool {
+        let expected = self.which_matches();
+        if self.match_which_order {
+            return got == expected;
+        }
+        let (mut got, mut expected) = (got.to_vec(), expected.to_vec());
+        got.sort();
+        expected.sort();
+        got == expected
+    }
+
     /// Returns true if the regex(es) in this test are expected to compile.
+    ///
+    /// This always returns false when the test specifies an expected
+    /// compile error.
     pub fn compiles(&self) -> bool {
-        self.compiles
+        self.compiles && self.compile_error.is_none()
+    }
+
+    /// Returns a substring that the error produced when compiling the
+    /// regex(es) in this test is expected to contain, if any.
+    ///
+    /// The substring is searched for in the error's `Display` output and in
+    /// the `Display` output of each of its sources. This makes it possible to
+    /// test for the message of an underlying error, such as a syntax error,
+    /// that a regex implementation wraps in its own error type.
+    pub fn compile_error(&self) -> Option<&BStr> {
+        self.compile_error.as_ref().map(|e| e.as_bstr())
     }
 
     /// Whether the regex should only match at the beginning of text or not.
@@ -423,6 +676,265 @@ impl RegexTest {
     pub fn utf8(&self) -> bool {
         self.utf8
     }
+
+    /// Returns the effective flags for the pattern at index `i` in
+    /// `regexes()`.
+    ///
+    /// If this test does not specify any per-pattern flags, or if it omits
+    /// some flags for this pattern, then the corresponding global flags are
+    /// used.
+    ///
+    /// This panics if `i` is not a valid index into `regexes()`.
+    pub fn pattern_flags(&self, i: usize) -> PatternFlags {
+        assert!(
+            i < self.regexes().len(),
+            "pattern index {} out of range for {} regexes",
+            i,
+            self.regexes().len(),
+        );
+        let global = PatternFlags {
+            case_insensitive: self.case_insensitive(),
+            unicode: self.unicode(),
+            utf8: self.utf8(),
+        };
+        let flags = match self.pattern_flags {
+            None => return global,
+            Some(ref flags) => &flags[i],
+        };
+        PatternFlags {
+            case_insensitive: flags
+                .case_insensitive
+                .unwrap_or(global.case_insensitive),
+            unicode: flags.unicode.unwrap_or(global.unicode),
+            utf8: flags.utf8.unwrap_or(global.utf8),
+        }
+    }
+}
+
+/// A builder for constructing a [`RegexTest`] in code.
+///
+/// Every option has the same default as the corresponding field in the TOML
+/// format. The test built is not validated until it is added to a collection
+/// of tests with [`RegexTests::push`].
+#[derive(Clone, Debug)]
+pub struct RegexTestBuilder {
+    test: RegexTest,
+}
+
+impl RegexTestBuilder {
+    /// Create a new builder for a test with no regexes, an empty input and
+    /// no expected results.
+    pub fn new() -> RegexTestBuilder {
+        RegexTestBuilder {
+            test: RegexTest {
+                group: String::new(),
+                name: String::new(),
+                full_name: String::new(),
+                regex: None,
+                regexes: None,
+                input: BString::from(""),
+                is_match: None,
+                which_matches: None,
+                match_which_order: true,
+                matches: None,
+                captures: None,
+                match_limit: None,
+                compiles: true,
+                compile_error: None,
+                anchored: false,
+                case_insensitive: false,
+                unescape: false,
+                unescape_regex: false,
+                unicode: true,
+                utf8: true,
+                match_kind: MatchKind::default(),
+                search_kind: SearchKind::default(),
+                pattern_flags: None,
+            },
+        }
+    }
+
+    /// Return the test constructed by this builder.
+    pub fn build(&self) -> RegexTest {
+        self.test.clone()
+    }
+
+    /// Set the group name of the test.
+    pub fn group(&mut self, group: &str) -> &mut RegexTestBuilder {
+        self.test.group = group.to_string();
+        self
+    }
+
+    /// Set the name of the test.
+    pub fn name(&mut self, name: &str) -> &mut RegexTestBuilder {
+        self.test.name = name.to_string();
+        self
+    }
+
+    /// Set the single regex to match. This replaces any regexes set via
+    /// [`RegexTestBuilder::regexes`].
+    pub fn regex<B: Into<BString>>(
+        &mut self,
+        regex: B,
+    ) -> &mut RegexTestBuilder {
+        self.test.regex = Some(regex.into());
+        self.test.regexes = None;
+        self
+    }
+
+    /// Set multiple regexes to match. This replaces any regex set via
+    /// [`RegexTestBuilder::regex`].
+    pub fn regexes<I, B>(&mut self, regexes: I) -> &mut RegexTestBuilder
+    where
+        I: IntoIterator<Item = B>,
+        B: Into<BString>,
+    {
+        self.test.regexes =
+            Some(regexes.into_iter().map(Into::into).collect());
+        self.test.regex = None;
+        self
+    }
+
+    /// Set the text on which the regexes should be matched.
+    pub fn input<B: Into<BString>>(
+        &mut self,
+        input: B,
+    ) -> &mut RegexTestBuilder {
+        self.test.input = input.into();
+        self
+    }
+
+    /// Set whether the regex is expected to match. This corresponds to the
+    /// `match` field in the TOML format.
+    pub fn is_match(&mut self, yes: bool) -> &mut RegexTestBuilder {
+        self.test.is_match = Some(yes);
+        self
+    }
+
+    /// Set which of the regexes are expected to match.
+    pub fn which_matches<I: IntoIterator<Item = usize>>(
+        &mut self,
+        which: I,
+    ) -> &mut RegexTestBuilder {
+        self.test.which_matches = Some(which.into_iter().collect());
+        self
+    }
+
+    /// Set whether the order of the regexes expected to match is
+    /// significant. This is enabled by default.
+    pub fn match_which_order(&mut self, yes: bool) -> &mut RegexTestBuilder {
+        self.test.match_which_order = yes;
+        self
+    }
+
+    /// Set the matches expected to be found.
+    pub fn matches<I: IntoIterator<Item = Match>>(
+        &mut self,
+        matches: I,
+    ) -> &mut RegexTestBuilder {
+        self.test.matches = Some(matches.into_iter().collect());
+        self
+    }
+
+    /// Set the capturing groups expected to be found, one for each match.
+    pub fn captures<I: IntoIterator<Item = Captures>>(
+        &mut self,
+        captures: I,
+    ) -> &mut RegexTestBuilder {
+        self.test.captures = Some(captures.into_iter().collect());
+        self
+    }
+
+    /// Set the maximum number of matches to compare.
+    pub fn match_limit(&mut self, limit: usize) -> &mut RegexTestBuilder {
+        self.test.match_limit = Some(limit);
+        self
+    }
+
+    /// Set whether the regexes are expected to compile.
+    pub fn compiles(&mut self, yes: bool) -> &mut RegexTestBuilder {
+        self.test.compiles = yes;
+        self
+    }
+
+    /// Set a substring that the error produced when compiling the regexes is
+    /// expected to contain. This implies that the regexes are expected to
+    /// fail to compile.
+    pub fn compile_error<B: Into<BString>>(
+        &mut self,
+        substring: B,
+    ) -> &mut RegexTestBuilder {
+        self.test.compile_error = Some(substring.into());
+        self
+    }
+
+    /// Set whether the search should be anchored.
+    pub fn anchored(&mut self, yes: bool) -> &mut RegexTestBuilder {
+        self.test.anchored = yes;
+        self
+    }
+
+    /// Set whether the regexes should match case insensitively.
+    pub fn case_insensitive(&mut self, yes: bool) -> &mut RegexTestBuilder {
+        self.test.case_insensitive = yes;
+        self
+    }
+
+    /// Set whether the input should be unescaped when the test is added to
+    /// a collection of tests.
+    pub fn unescape(&mut self, yes: bool) -> &mut RegexTestBuilder {
+        self.test.unescape = yes;
+        self
+    }
+
+    /// Set whether the regexes should be unescaped when the test is added to
+    /// a collection of tests.
+    pub fn unescape_regex(&mut self, yes: bool) -> &mut RegexTestBuilder {
+        self.test.unescape_regex = yes;
+        self
+    }
+
+    /// Set whether Unicode mode is enabled.
+    pub fn unicode(&mut self, yes: bool) -> &mut RegexTestBuilder {
+        self.test.unicode = yes;
+        self
+    }
+
+    /// Set whether the regexes can only match valid UTF-8.
+    pub fn utf8(&mut self, yes: bool) -> &mut RegexTestBuilder {
+        self.test.utf8 = yes;
+        self
+    }
+
+    /// Set the match semantics required by the test.
+    pub fn match_kind(&mut self, kind: MatchKind) -> &mut RegexTestBuilder {
+        self.test.match_kind = kind;
+        self
+    }
+
+    /// Set the search semantics required by the test.
+    pub fn search_kind(&mut self, kind: SearchKind) -> &mut RegexTestBuilder {
+        self.test.search_kind = kind;
+        self
+    }
+
+    /// Set the flags for each regex, overriding the global flags. There must
+    /// be exactly one entry for each regex.
+    pub fn pattern_flags<I: IntoIterator<Item = PatternFlags>>(
+        &mut self,
+        flags: I,
+    ) -> &mut RegexTestBuilder {
+        let flags = flags
+            .into_iter()
+            .map(|f| PatternFlagsFormat {
+                case_insensitive: Some(f.case_insensitive),
+                unicode: Some(f.unicode),
+                utf8: Some(f.utf8),
+            })
+            .collect();
+        self.test.pattern_flags = Some(flags);
+        self
+    }
 }
 
 /// The result of compiling a regex.
@@ -513,6 +1025,14 @@ impl TestResult {
         }
     }
 
+    /// Like `TestResult::which`, but accepts any iterator of regex indices.
+    ///
+    /// This is useful for converting the set of matching patterns reported
+    /// by a regex implementation without collecting it into a `Vec` first.
+    pub fn which_iter<I: IntoIterator<Item = usize>>(it: I) -> TestResult {
+        TestResult::which(it.into_iter().collect())
+    }
+
     /// Create a test result containing a sequence of all matches in the
     /// test's input string.
     pub fn matches<I: IntoIterator<Item = Match>>(it: I) -> TestResult {
@@ -592,9 +1112,21 @@ impl TestResult {
 ///
 /// Whitelist and blacklist substrings are matched on the full name of each
 /// test, which typically looks like `base_file_stem/test_name`.
+///
+/// ### Randomizing test order
+///
+/// By default, `test_iter` runs tests in the order in which they are given.
+/// If the `REGEX_TEST_SEED` environment variable is set to an integer, then
+/// `test_iter` instead runs tests in a pseudo-random order determined by
+/// that seed. This is useful for finding bugs that depend on the order in
+/// which tests are run, such as bugs in reusing caches. The seed is included
+/// in the failure report so that a failing order can be reproduced.
+///
+/// Callers may also specify a seed explicitly with `TestRunner::seed`.
 #[derive(Debug)]
 pub struct TestRunner {
     include: Vec<IncludePattern>,
+    seed: Option<u64>,
     results: RegexTestResults,
 }
 
@@ -623,11 +1155,18 @@ impl TestRunner {
     ///
     /// The last substring that a test name matches takes precedent.
     ///
-    /// If there was a problem reading the environment variable, then an error
-    /// is returned.
+    /// If the `REGEX_TEST_SEED` environment variable is set, then it must
+    /// contain an integer, which is used to shuffle the order in which
+    /// `test_iter` runs tests.
+    ///
+    /// If there was a problem reading either environment variable, then an
+    /// error is returned.
     pub fn new() -> Result<TestRunner> {
-        let mut runner =
-            TestRunner { include: vec![], results: RegexTestResults::new() };
+        let mut runner = TestRunner {
+            include: vec![],
+            seed: None,
+            results: RegexTestResults::new(),
+        };
         for mut substring in read_env(ENV_REGEX_TEST)?.split(",") {
             substring = substring.trim();
             if substring.is_empty() {
@@ -639,6 +1178,17 @@ impl TestRunner {
                 runner.whitelist(substring);
             }
         }
+        let seed = read_env(ENV_REGEX_TEST_SEED)?;
+        let seed = seed.trim();
+        if !seed.is_empty() {
+            let seed = seed.parse().with_context(|| {
+                format!(
+                    "invalid integer in env var {}={:?}",
+                    ENV_REGEX_TEST_SEED, seed
+                )
+            })?;
+            runner.seed(Some(seed));
+        }
         Ok(runner)
     }
 
@@ -650,7 +1200,30 @@ impl TestRunner {
     /// If `REGEX_TEST_VERBOSE` is set to `1`, then a longer report of tests
     /// that passed, failed or skipped is printed.
     pub fn assert(&mut self) {
-        self.results.assert();
+        self.results.assert(self.seed);
+    }
+
+    /// Return the `n` slowest tests run so far, slowest first.
+    ///
+    /// Each test is reported by its full name along with the wall clock time
+    /// spent compiling its regex and running it. A test whose run produced
+    /// multiple results is reported once for each result.
+    pub fn slowest(&self, n: usize) -> Vec<(String, Duration)> {
+        let mut timings = self.results.timings();
+        timings.truncate(n);
+        timings
+    }
+
+    /// Write a machine readable report of all tests run so far as JSON.
+    ///
+    /// The report is a single object with a `tests` array. Each element
+    /// contains the test's full `name` and its `status`, which is one of
+    /// `pass`, `fail` or `skip`. Failed tests also include a `failure` object
+    /// describing the failure. Its `kind` field names the type of failure,
+    /// and where applicable, it contains `expected` and `got` fields.
+    pub fn write_json<W: std::io::Write>(&self, w: W) -> std::io::Result<()> {
+        serde_json::to_writer_pretty(w, &self.results.json())
+            .map_err(std::io::Error::from)
     }
 
     /// Whitelist the given substring.
@@ -674,7 +1247,39 @@ impl TestRunner {
         self
     }
 
+    /// Register a callback that is invoked as soon as each test result is
+    /// recorded.
+    ///
+    /// The callback receives the full name of the test along with whether
+    /// it passed, failed or was skipped. This is useful for reporting
+    /// progress while a large suite is running, since failures are otherwise
+    /// only reported once `assert` is called.
+    ///
+    /// Registering a callback replaces any callback registered previously.
+    /// It has no effect on the behavior of `assert`.
+    pub fn on_result<F: FnMut(&str, Status) + 'static>(
+        &mut self,
+        callback: F,
+    ) -> &mut TestRunner {
+        self.results.on_result = Some(ResultCallback(Box::new(callback)));
+        self
+    }
+
+    /// Set the seed used to shuffle the order in which `test_iter` runs
+    /// tests.
+    ///
+    /// When `None`, tests are run in the order in which they are given. This
+    /// overrides any seed set via the `REGEX_TEST_SEED` environment variable.
+    pub fn seed(&mut self, seed: Option<u64>) -> &mut TestRunner {
+        self.seed = seed;
+        self
+    }
+
     /// Run all of the given tests.
+    ///
+    /// If a seed has been set, then the tests are run in a pseudo-random
+    /// order determined by that seed. Otherwise, they are run in the order
+    /// given.
     pub fn test_iter<I, T>(
         &mut self,
         it: I,
@@ -690,10 +1295,18 @@ impl TestRunner {
         I: IntoIterator<Item = T>,
         T: Borrow<RegexTest>,
     {
-        for test in it {
+        let mut tests: Vec<T> = it.into_iter().collect();
+        if let Some(seed) = self.seed {
+            shuffle(&mut tests, seed);
+        }
+        for test in tests {
             let test = test.borrow();
             if self.should_skip(test) {
-                self.results.skip(test, &TestResult::skip());
+                self.results.skip(
+                    test,
+                    &TestResult::skip(),
+                    Duration::from_secs(0),
+                );
                 continue;
             }
             self.test(test, |regexes| compile(test, regexes));
@@ -720,26 +1333,39 @@ impl TestRunner {
             Box<dyn std::error::Error>,
         >,
     ) -> &mut TestRunner {
-        let mut compiled = match safe(|| compile(test.regexes())) {
+        let start = Instant::now();
+        let compiled = safe(|| compile(test.regexes()));
+        let elapsed = start.elapsed();
+        let mut compiled = match compiled {
             Err(msg) => {
                 // Regex tests should never panic. It's auto-fail if they do.
                 self.results.fail(
                     test,
                     &TestResult::none(),
                     RegexTestFailureKind::UnexpectedPanicCompile(msg),
+                    elapsed,
                 );
                 return self;
             }
             Ok(Ok(compiled)) => compiled,
             Ok(Err(err)) => {
-                if !test.compiles() {
-                    self.results.pass(test, &TestResult::none());
-                } else {
+                if test.compiles() {
                     self.results.fail(
                         test,
                         &TestResult::none(),
                         RegexTestFailureKind::CompileError { err },
+                        elapsed,
+                    );
+                } else if !compile_error_contains(&*err, test.compile_error())
+                {
+                    self.results.fail(
+                        test,
+                        &TestResult::none(),
+                        RegexTestFailureKind::WrongCompileError { err },
+                        elapsed,
                     );
+                } else {
+                    self.results.pass(test, &TestResult::none(), elapsed);
                 }
                 return self;
             }
@@ -749,16 +1375,22 @@ impl TestRunner {
                 test,
                 &TestResult::none(),
                 RegexTestFailureKind::NoCompileError,
+                elapsed,
             );
             return self;
         }
-        let results = match safe(|| test.test(&mut compiled)) {
+        let results = safe(|| test.test(&mut compiled));
+        // Each result is recorded with the total time spent compiling the
+        // regex and running the test.
+        let elapsed = start.elapsed();
+        let results = match results {
             Ok(results) => results,
             Err(msg) => {
                 self.results.fail(
                     test,
                     &TestResult::none(),
                     RegexTestFailureKind::UnexpectedPanicSearch(msg),
+                    elapsed,
                 );
                 return self;
             }
@@ -767,7 +1399,7 @@ impl TestRunner {
             match result.kind {
                 TestResultKind::None => {}
                 TestResultKind::Skip => {
-                    self.results.skip(test, result);
+                    self.results.skip(test, result, elapsed);
                 }
                 TestResultKind::Matched { ref which } => {
                     if which.is_empty() && test.is_match() {
@@ -775,25 +1407,28 @@ impl TestRunner {
                             test,
                             result,
                             RegexTestFailureKind::IsMatch,
+                            elapsed,
                         );
                     } else if !which.is_empty() && !test.is_match() {
                         self.results.fail(
                             test,
                             result,
                             RegexTestFailureKind::IsMatch,
+                            elapsed,
                         );
-                    } else if &**which != test.which_matches() {
+                    } else if !test.which_matches_eq(which) {
                         self.results.fail(
                             test,
                             result,
                             RegexTestFailureKind::Many { got: which.to_vec() },
+                            elapsed,
                         );
                     } else {
-                        self.results.pass(test, result);
+                        self.results.pass(test, result, elapsed);
                     }
                 }
                 TestResultKind::MatchedStartEnd { ref matches } => {
-                    if let Some(expected) = test.matches() {
+                    if let Some(expected) = test.limited_matches() {
                         if &expected != matches {
                             self.results.fail(
                                 test,
@@ -801,22 +1436,24 @@ impl TestRunner {
                                 RegexTestFailureKind::StartEnd {
                                     got: matches.clone(),
                                 },
+                                elapsed,
                             );
                         } else {
-                            self.results.pass(test, result);
+                            self.results.pass(test, result, elapsed);
                         }
                     } else if test.is_match() != !matches.is_empty() {
                         self.results.fail(
                             test,
                             result,
                             RegexTestFailureKind::IsMatch,
+                            elapsed,
                         );
                     } else {
-                        self.results.pass(test, result);
+                        self.results.pass(test, result, elapsed);
                     }
                 }
                 TestResultKind::MatchedCaptures { ref matches } => {
-                    if let Some(expected) = test.captures() {
+                    if let Some(expected) = test.limited_captures() {
                         if &expected != matches {
                             self.results.fail(
                                 test,
@@ -824,18 +1461,20 @@ impl TestRunner {
                                 RegexTestFailureKind::Captures {
                                     got: matches.clone(),
                                 },
+                                elapsed,
                             );
                         } else {
-                            self.results.pass(test, result);
+                            self.results.pass(test, result, elapsed);
                         }
                     } else if test.is_match() != !matches.is_empty() {
                         self.results.fail(
                             test,
                             result,
                             RegexTestFailureKind::IsMatch,
+                            elapsed,
                         );
                     } else {
-                        self.results.pass(test, result);
+                        self.results.pass(test, result, elapsed);
                     }
                 }
             }
@@ -862,6 +1501,18 @@ impl TestRunner {
     }
 }
 
+/// The status of a single test result, as reported to a callback registered
+/// via `TestRunner::on_result`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Status {
+    /// The test passed.
+    Pass,
+    /// The test failed.
+    Fail,
+    /// The test was skipped.
+    Skip,
+}
+
 /// A collection of test results, corresponding to passed, skipped and failed
 /// tests.
 #[derive(Debug)]
@@ -869,6 +1520,17 @@ struct RegexTestResults {
     pass: Vec<RegexTestResult>,
     fail: Vec<RegexTestFailure>,
     skip: Vec<RegexTestResult>,
+    /// A callback invoked with each result as it is recorded, if present.
+    on_result: Option<ResultCallback>,
+}
+
+/// A callback registered via `TestRunner::on_result`.
+struct ResultCallback(Box<dyn FnMut(&str, Status)>);
+
+impl std::fmt::Debug for ResultCallback {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("ResultCallback(...)")
+    }
 }
 
 /// A test that passed or skipped, along with its specific result.
@@ -876,6 +1538,8 @@ struct RegexTestResults {
 struct RegexTestResult {
     test: RegexTest,
     result: TestResult,
+    /// The wall clock time spent compiling and running the test.
+    elapsed: Duration,
 }
 
 /// A test that failed along with the reason why.
@@ -884,6 +1548,8 @@ struct RegexTestFailure {
     test: RegexTest,
     result: TestResult,
     kind: RegexTestFailureKind,
+    /// The wall clock time spent compiling and running the test.
+    elapsed: Duration,
 }
 
 /// Describes the nature of the failed test.
@@ -909,6 +1575,9 @@ enum RegexTestFailureKind {
     /// This occurs when the test expected the regex to compile successfully,
     /// but it failed to compile.
     CompileError { err: Box<dyn std::error::Error> },
+    /// This occurs when the test expected the regex to fail to compile with
+    /// a specific error message, but it failed with a different error.
+    WrongCompileError { err: Box<dyn std::error::Error> },
     /// This occurs when the test result is incompatible with the output that
     /// the test expects. For example, this can occur if the test expects a
     /// sequence of matches, but the test result reported is only a binary
@@ -929,14 +1598,42 @@ enum RegexTestFailureKind {
 
 impl RegexTestResults {
     fn new() -> RegexTestResults {
-        RegexTestResults { pass: vec![], fail: vec![], skip: vec![] }
+        RegexTestResults {
+            pass: vec![],
+            fail: vec![],
+            skip: vec![],
+            on_result: None,
+        }
+    }
+
+    /// Invoke the result callback, if one is registered, with the given
+    /// status and the full name of the most recently recorded result with
+    /// that status.
+    fn notify(&mut self, status: Status) {
+        let callback = match self.on_result {
+            None => return,
+            Some(ResultCallback(ref mut callback)) => callback,
+        };
+        let name = match status {
+            Status::Pass => self.pass.last().unwrap().full_name(),
+            Status::Fail => self.fail.last().unwrap().full_name(),
+            Status::Skip => self.skip.last().unwrap().full_name(),
+        };
+        callback(&name, status);
     }
 
-    fn pass(&mut self, test: &RegexTest, result: &TestResult) {
+    fn pass(
+        &mut self,
+        test: &RegexTest,
+        result: &TestResult,
+        elapsed: Duration,
+    ) {
         self.pass.push(RegexTestResult {
             test: test.clone(),
             result: result.clone(),
+            elapsed,
         });
+        self.notify(Status::Pass);
     }
 
     fn fail(
@@ -944,22 +1641,46 @@ impl RegexTestResults {
         test: &RegexTest,
         result: &TestResult,
         kind: RegexTestFailureKind,
+        elapsed: Duration,
     ) {
         self.fail.push(RegexTestFailure {
             test: test.clone(),
             result: result.clone(),
             kind,
+            elapsed,
         });
+        self.notify(Status::Fail);
     }
 
-    fn skip(&mut self, test: &RegexTest, result: &TestResult) {
+    fn skip(
+        &mut self,
+        test: &RegexTest,
+        result: &TestResult,
+        elapsed: Duration,
+    ) {
         self.skip.push(RegexTestResult {
             test: test.clone(),
             result: result.clone(),
+            elapsed,
         });
+        self.notify(Status::Skip);
+    }
+
+    /// Return the full name and elapsed time of every recorded test result,
+    /// sorted from slowest to fastest.
+    fn timings(&self) -> Vec<(String, Duration)> {
+        let mut timings: Vec<(String, Duration)> = self
+            .pass
+            .iter()
+            .chain(self.skip.iter())
+            .map(|t| (t.full_name(), t.elapsed))
+            .chain(self.fail.iter().map(|t| (t.full_name(), t.elapsed)))
+            .collect();
+        timings.sort_by(|(_, d1), (_, d2)| d2.cmp(d1));
+        timings
     }
 
-    fn assert(&self) {
+    fn assert(&self, seed: Option<u64>) {
         if read_env("REGEX_TEST_VERBOSE").map_or(false, |s| s == "1") {
             self.verbose();
         }
@@ -972,8 +1693,16 @@ impl RegexTestResults {
             .map(|f| f.to_string())
             .collect::<Vec<String>>()
             .join("\n\n");
+        let seed = match seed {
+            None => String::new(),
+            Some(seed) => format!(
+                "Tests were run in a shuffled order. Set {}={} to \n\
+                 reproduce it.\n\n",
+                ENV_REGEX_TEST_SEED, seed,
+            ),
+        };
         panic!(
-            "found {} failures:\n{}\n{}\n{}\n\n\
+            "found {} failures:\n{}\n{}\n{}\n\n{}\
              Set the REGEX_TEST environment variable to filter tests, \n\
              e.g., REGEX_TEST=foo,-foo2 runs every test whose name contains \n\
              foo but not foo2\n\n",
@@ -981,6 +1710,7 @@ impl RegexTestResults {
             "~".repeat(79),
             failures.trim(),
             "~".repeat(79),
+            seed,
         )
     }
 
@@ -1001,8 +1731,30 @@ impl RegexTestResults {
             self.skip.len(),
             self.fail.len()
         );
+        println!("\ntimings, slowest first:");
+        for (name, elapsed) in self.timings() {
+            println!("{:?}: {}", elapsed, name);
+        }
         println!("{}", "~".repeat(79));
     }
+
+    fn json(&self) -> Value {
+        let mut tests = vec![];
+        for t in &self.skip {
+            tests.push(json!({"name": t.full_name(), "status": "skip"}));
+        }
+        for t in &self.pass {
+            tests.push(json!({"name": t.full_name(), "status": "pass"}));
+        }
+        for t in &self.fail {
+            tests.push(json!({
+                "name": t.full_name(),
+                "status": "fail",
+                "failure": t.kind.json(&t.test),
+            }));
+        }
+        json!({ "tests": tests })
+    }
 }
 
 impl RegexTestResult {
@@ -1060,28 +1812,37 @@ impl RegexTestFailureKind {
             RegexTestFailureKind::Many { ref got } => {
                 write!(
                     buf,
-                    "expected regexes {:?} to match, but found {:?}",
+                    "expected regexes {:?} to match ({}), but found {:?}",
                     test.which_matches(),
+                    if test.match_which_order() {
+                        "in order"
+                    } else {
+                        "in any order"
+                    },
                     got
                 )?;
             }
             RegexTestFailureKind::StartEnd { ref got } => {
+                let expected = test.limited_matches().unwrap();
                 write!(
                     buf,
                     "did not find expected matches\n\
                      expected: {:?}\n     \
                      got: {:?}",
-                    test.matches().unwrap(),
-                    got,
+                    expected, got,
                 )?;
+                write_first_difference(&mut buf, &expected, got)?;
             }
             RegexTestFailureKind::Captures { ref got } => {
+                let expected = test.limited_captures().unwrap();
                 write!(
                     buf,
-                    "expected to find {:?} captures, but got {:?}",
-                    todo!(),
-                    got,
+                    "did not find expected captures\n\
+                     expected: {:?}\n     \
+                     got: {:?}",
+                    expected, got,
                 )?;
+                write_first_captures_difference(&mut buf, &expected, got)?;
             }
             RegexTestFailureKind::NoCompileError => {
                 write!(buf, "expected regex to NOT compile, but it did")?;
@@ -1089,7 +1850,15 @@ impl RegexTestFailureKind {
             RegexTestFailureKind::CompileError { ref err } => {
                 write!(buf, "expected regex to compile, failed: {}", err)?;
             }
-            RegexTestFailureKind::Incompatible { expected, got } => {
+            RegexTestFailureKind::WrongCompileError { ref err } => {
+                write!(
+                    buf,
+                    "expected compile error containing {:?}, but got: {}",
+                    test.compile_error().unwrap(),
+                    error_chain(&**err),
+                )?;
+            }
+            RegexTestFailureKind::Incompatible { expected, got } => {
                 write!(buf, "expected {} results, but got {}", expected, got)?;
             }
             RegexTestFailureKind::UnexpectedPanicCompile(ref msg) => {
@@ -1103,6 +1872,192 @@ impl RegexTestFailureKind {
     }
 }
 
+impl RegexTestFailureKind {
+    fn json(&self, test: &RegexTest) -> Value {
+        match *self {
+            RegexTestFailureKind::IsMatch => json!({
+                "kind": "is-match",
+                "expected": test.is_match(),
+                "got": !test.is_match(),
+            }),
+            RegexTestFailureKind::Many { ref got } => json!({
+                "kind": "many",
+                "expected": test.which_matches(),
+                "ordered": test.match_which_order(),
+                "got": got,
+            }),
+            RegexTestFailureKind::StartEnd { ref got } => json!({
+                "kind": "start-end",
+                "expected": test.limited_matches().map(|ms| matches_json(&ms)),
+                "got": matches_json(got),
+            }),
+            RegexTestFailureKind::Captures { ref got } => json!({
+                "kind": "captures",
+                "expected": test.limited_captures().map(|cs| captures_json(&cs)),
+                "got": captures_json(got),
+            }),
+            RegexTestFailureKind::NoCompileError => json!({
+                "kind": "no-compile-error",
+            }),
+            RegexTestFailureKind::CompileError { ref err } => json!({
+                "kind": "compile-error",
+                "message": err.to_string(),
+            }),
+            RegexTestFailureKind::WrongCompileError { ref err } => json!({
+                "kind": "wrong-compile-error",
+                "expected": test.compile_error().map(|e| e.to_string()),
+                "got": error_chain(&**err),
+            }),
+            RegexTestFailureKind::Incompatible { expected, got } => json!({
+                "kind": "incompatible",
+                "expected": expected,
+                "got": got,
+            }),
+            RegexTestFailureKind::UnexpectedPanicCompile(ref msg) => json!({
+                "kind": "unexpected-panic-compile",
+                "message": msg,
+            }),
+            RegexTestFailureKind::UnexpectedPanicSearch(ref msg) => json!({
+                "kind": "unexpected-panic-search",
+                "message": msg,
+            }),
+        }
+    }
+}
+
+/// Convert the given matches to a JSON array of `{id, start, end}` objects.
+fn matches_json(matches: &[Match]) -> Value {
+    matches.iter().map(match_json).collect()
+}
+
+/// Convert the given captures to a JSON array, where each element is an
+/// array of groups. Groups that did not participate in a match are `null`.
+fn captures_json(captures: &[Captures]) -> Value {
+    captures
+        .iter()
+        .map(|caps| {
+            caps.groups
+                .iter()
+                .map(|m| m.as_ref().map_or(Value::Null, match_json))
+        })
+        .map(|groups| groups.collect::<Value>())
+        .collect()
+}
+
+fn match_json(m: &Match) -> Value {
+    json!({"id": m.id, "start": m.start, "end": m.end})
+}
+
+/// The number of matches to show on either side of the first difference
+/// between expected and actual matches.
+const DIFF_CONTEXT: usize = 2;
+
+/// Write a description of the first index at which the given expected and
+/// actual matches differ, along with a few surrounding entries from each
+/// sequence. If the sequences are equal, then nothing is written.
+fn write_first_difference(
+    buf: &mut String,
+    expected: &[Match],
+    got: &[Match],
+) -> std::fmt::Result {
+    use std::fmt::Write;
+
+    let i = match (0..std::cmp::max(expected.len(), got.len()))
+        .find(|&i| expected.get(i) != got.get(i))
+    {
+        None => return Ok(()),
+        Some(i) => i,
+    };
+    write!(
+        buf,
+        "\nfirst difference at match {}: expected {}, got {}",
+        i,
+        DiffMatch(expected.get(i)),
+        DiffMatch(got.get(i)),
+    )?;
+
+    let start = i.saturating_sub(DIFF_CONTEXT);
+    let end = i + DIFF_CONTEXT + 1;
+    for (label, matches) in &[("expected", expected), ("got", got)] {
+        write!(buf, "\n{:>12}:", format!("{} near", label))?;
+        if start > 0 {
+            write!(buf, " ...")?;
+        }
+        for m in matches.iter().take(end).skip(start) {
+            write!(buf, " {}", DiffMatch(Some(m)))?;
+        }
+        if matches.len() > end {
+            write!(buf, " ...")?;
+        }
+    }
+    Ok(())
+}
+
+/// Write a description of the first capturing group at which the given
+/// expected and actual captures differ. The group is identified by its name
+/// when the expected captures name it, and by its index otherwise. If the
+/// sequences are equal, then nothing is written.
+fn write_first_captures_difference(
+    buf: &mut String,
+    expected: &[Captures],
+    got: &[Captures],
+) -> std::fmt::Result {
+    use std::fmt::Write;
+
+    let i = match (0..std::cmp::max(expected.len(), got.len()))
+        .find(|&i| expected.get(i) != got.get(i))
+    {
+        None => return Ok(()),
+        Some(i) => i,
+    };
+    let (exp, got) = match (expected.get(i), got.get(i)) {
+        (Some(exp), Some(got)) => (exp, got),
+        (exp, got) => {
+            let describe = |caps: Option<&Captures>| {
+                caps.map_or("none".to_string(), |c| format!("{:?}", c))
+            };
+            return write!(
+                buf,
+                "\nfirst difference at captures {}: expected {}, got {}",
+                i,
+                describe(exp),
+                describe(got),
+            );
+        }
+    };
+    let len = std::cmp::max(exp.groups.len(), got.groups.len());
+    let j = (0..len)
+        .find(|&j| exp.groups.get(j) != got.groups.get(j))
+        .expect("unequal captures must have a differing group");
+    let group = match exp.name(j) {
+        Some(name) => format!("{:?}", name),
+        None => j.to_string(),
+    };
+    write!(
+        buf,
+        "\nfirst difference at captures {}, group {}: expected {}, got {}",
+        i,
+        group,
+        DiffMatch(exp.groups.get(j).and_then(|m| m.as_ref())),
+        DiffMatch(got.groups.get(j).and_then(|m| m.as_ref())),
+    )
+}
+
+/// A helper for compactly displaying a single match in a diff. Matches from
+/// the first regex are shown as just a range, while matches from any other
+/// regex are prefixed with their ID. A missing match is shown as `none`.
+struct DiffMatch<'a>(Option<&'a Match>);
+
+impl<'a> std::fmt::Display for DiffMatch<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.0 {
+            None => write!(f, "none"),
+            Some(m) if m.id == 0 => write!(f, "{}..{}", m.start, m.end),
+            Some(m) => write!(f, "{}:{}..{}", m.id, m.start, m.end),
+        }
+    }
+}
+
 /// An iterator over regex tests.
 #[derive(Debug)]
 pub struct RegexTestsIter<'a> {
@@ -1135,12 +2090,32 @@ impl From<MatchFormat> for Match {
     }
 }
 
+/// The flags for a single pattern as written in a test file. Omitted flags
+/// default to the test's global flags.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PatternFlagsFormat {
+    case_insensitive: Option<bool>,
+    unicode: Option<bool>,
+    utf8: Option<bool>,
+}
+
 /// Represents the actual 'captures' key format more faithfully such that
 /// Serde can deserialize it. Namely, we need a way to represent a 'None' value
 /// inside a TOML array, and TOML has no 'null' value. So we make '[]' be
 /// 'None', and we use 'MaybeMatch' to recognize it.
+///
+/// Each group may also be written as '{ name = "foo", span = [0, 4] }' to
+/// give it a name.
 #[derive(Deserialize)]
-struct CapturesFormat(Vec<MaybeMatch>);
+struct CapturesFormat(Vec<GroupFormat>);
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(untagged)]
+enum GroupFormat {
+    Unnamed(MaybeMatch),
+    Named { name: String, span: MaybeMatch },
+}
 
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
 #[serde(untagged)]
@@ -1149,22 +2124,111 @@ enum MaybeMatch {
     Some([usize; 2]),
 }
 
+impl From<MaybeMatch> for Option<Match> {
+    fn from(m: MaybeMatch) -> Option<Match> {
+        match m {
+            MaybeMatch::None(_) => None,
+            MaybeMatch::Some([start, end]) => {
+                Some(Match { id: 0, start, end })
+            }
+        }
+    }
+}
+
 impl From<CapturesFormat> for Captures {
     fn from(data: CapturesFormat) -> Captures {
-        Captures(
-            data.0
-                .into_iter()
-                .map(|m| match m {
-                    MaybeMatch::None(_) => None,
-                    MaybeMatch::Some([start, end]) => {
-                        Some(Match { id: 0, start, end })
-                    }
-                })
-                .collect(),
-        )
+        let (mut groups, mut names) = (vec![], vec![]);
+        for group in data.0 {
+            match group {
+                GroupFormat::Unnamed(m) => {
+                    groups.push(m.into());
+                    names.push(None);
+                }
+                GroupFormat::Named { name, span } => {
+                    groups.push(span.into());
+                    names.push(Some(name));
+                }
+            }
+        }
+        Captures { groups, names }
     }
 }
 
+/// Returns true if and only if the given substring is found in the `Display`
+/// output of the given error or any of its sources. If no substring is given,
+/// then this always returns true.
+fn compile_error_contains(
+    err: &dyn std::error::Error,
+    expected: Option<&BStr>,
+) -> bool {
+    let expected = match expected {
+        None => return true,
+        Some(expected) => expected,
+    };
+    let mut err = Some(err);
+    while let Some(e) = err {
+        if e.to_string().as_bytes().contains_str(expected) {
+            return true;
+        }
+        err = e.source();
+    }
+    false
+}
+
+/// Render the given error along with all of its sources, separated by `: `.
+fn error_chain(err: &dyn std::error::Error) -> String {
+    let mut msg = err.to_string();
+    let mut source = err.source();
+    while let Some(e) = source {
+        msg.push_str(": ");
+        msg.push_str(&e.to_string());
+        source = e.source();
+    }
+    msg
+}
+
+/// Shuffle the given slice in place with a pseudo-random permutation that is
+/// determined entirely by the given seed.
+///
+/// This uses a Fisher-Yates shuffle driven by the SplitMix64 generator,
+/// which is more than good enough for randomizing test order and avoids
+/// pulling in a dependency.
+fn shuffle<T>(items: &mut [T], seed: u64) {
+    let mut state = seed;
+    let mut next = || {
+        state = state.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^ (z >> 31)
+    };
+    for i in (1..items.len()).rev() {
+        let j = (next() % (i as u64 + 1)) as usize;
+        items.swap(i, j);
+    }
+}
+
+/// Add the paths of all TOML files in `dir` and its subdirectories to
+/// `paths`, in no particular order.
+fn find_toml_files(
+    dir: &Path,
+    paths: &mut Vec<std::path::PathBuf>,
+) -> Result<()> {
+    let entries = fs::read_dir(dir)
+        .with_context(|| format!("failed to read {}", dir.display()))?;
+    for entry in entries {
+        let path = entry
+            .with_context(|| format!("failed to read {}", dir.display()))?
+            .path();
+        if path.is_dir() {
+            find_toml_files(&path, paths)?;
+        } else if path.extension().map_or(false, |ext| ext == "toml") {
+            paths.push(path);
+        }
+    }
+    Ok(())
+}
+
 /// Read the environment variable given. If it doesn't exist, then return an
 /// empty string. Otherwise, check that it is valid UTF-8. If it isn't, return
 /// a useful error message.
@@ -1218,6 +2282,68 @@ fn default_true() -> bool {
 mod tests {
     use super::*;
 
+    #[test]
+    fn push() {
+        let mut tests = RegexTests::new();
+        tests
+            .push(
+                RegexTest::builder()
+                    .group("fuzz")
+                    .name("crash1")
+                    .regexes(vec!["a+", "b"])
+                    .input(r"aa\xFF")
+                    .unescape(true)
+                    .matches(vec![Match { id: 0, start: 0, end: 2 }])
+                    .utf8(false)
+                    .build(),
+            )
+            .unwrap();
+
+        let t0 = tests.iter().next().unwrap();
+        assert_eq!("fuzz", t0.group());
+        assert_eq!("crash1", t0.name());
+        assert_eq!("fuzz/crash1", t0.full_name());
+        assert_eq!(&["a+", "b"], t0.regexes());
+        assert_eq!(b"aa\xFF", t0.input().as_bytes());
+        assert_eq!(
+            Some(vec![Match { id: 0, start: 0, end: 2 }]),
+            t0.matches()
+        );
+        assert!(t0.compiles());
+        assert!(t0.unicode());
+        assert!(!t0.utf8());
+    }
+
+    #[test]
+    fn err_push() {
+        let data = r#"
+[[tests]]
+name = "crash1"
+regex = "a"
+input = "a"
+match = true
+"#;
+
+        let mut tests = RegexTests::new();
+        tests.load_slice("fuzz", data.as_bytes()).unwrap();
+
+        let mut builder = RegexTest::builder();
+        builder.group("fuzz").name("crash1").regex("a").input("a");
+        // Invalid, since no expected results are given.
+        assert!(tests.push(builder.build()).is_err());
+        builder.is_match(true);
+        // Invalid, since a test with this name was already loaded.
+        assert!(tests.push(builder.build()).is_err());
+        // A test must have a group and a name.
+        assert!(tests.push(builder.group("").build()).is_err());
+        builder.group("fuzz").name("");
+        assert!(tests.push(builder.build()).is_err());
+
+        builder.name("crash2");
+        tests.push(builder.build()).unwrap();
+        assert_eq!(2, tests.iter().count());
+    }
+
     #[test]
     fn err_no_regexes() {
         let data = r#"
@@ -1293,6 +2419,35 @@ utf8 = false
         assert!(!t0.utf8());
     }
 
+    #[test]
+    fn iter_group() {
+        let data = r#"
+[[tests]]
+name = "foo"
+regex = "a"
+input = "a"
+match = true
+
+[[tests]]
+name = "bar"
+regex = "b"
+input = "b"
+match = true
+"#;
+
+        let mut tests = RegexTests::new();
+        tests.load_slice("ascii", data.as_bytes()).unwrap();
+        tests.load_slice("unicode", data.as_bytes()).unwrap();
+
+        let names: Vec<&str> =
+            tests.iter_group("unicode").map(|t| t.full_name()).collect();
+        assert_eq!(vec!["unicode/foo", "unicode/bar"], names);
+        assert_eq!(0, tests.iter_group("uni").count());
+
+        let groups: Vec<&str> = tests.groups().collect();
+        assert_eq!(vec!["ascii", "unicode"], groups);
+    }
+
     #[test]
     fn load_which_matches() {
         let data = r#"
@@ -1318,6 +2473,191 @@ which_matches = [0, 2, 5]
         assert!(t0.utf8());
     }
 
+    #[test]
+    fn which_matches_order() {
+        let data = r#"
+[[tests]]
+name = "ordered"
+regexes = ["a", "b", "c"]
+input = "abc"
+which_matches = [0, 2]
+
+[[tests]]
+name = "unordered"
+regexes = ["a", "b", "c"]
+input = "abc"
+which_matches = [0, 2]
+match_which_order = false
+"#;
+
+        let mut tests = RegexTests::new();
+        tests.load_slice("test", data.as_bytes()).unwrap();
+        assert!(tests.tests[0].match_which_order());
+        assert!(!tests.tests[1].match_which_order());
+
+        // Reporting the regexes in a different order only passes when the
+        // test says that order doesn't matter.
+        let mut runner = TestRunner::new().unwrap();
+        runner.test_iter(tests.iter(), |_, _| {
+            Ok(CompiledRegex::compiled(|_| {
+                vec![TestResult::which(vec![2, 0])]
+            }))
+        });
+        assert_eq!(1, runner.results.pass.len());
+        assert_eq!("test/unordered", runner.results.pass[0].full_name());
+        assert_eq!(1, runner.results.fail.len());
+        let failure = &runner.results.fail[0];
+        assert_eq!("test/ordered", failure.full_name());
+        let msg = failure.kind.fmt(&failure.test).unwrap();
+        assert!(msg.contains("(in order)"), "{}", msg);
+
+        // Both modes still fail when the regexes reported are different.
+        let mut runner = TestRunner::new().unwrap();
+        runner.test_iter(tests.iter(), |_, _| {
+            Ok(CompiledRegex::compiled(|_| {
+                vec![TestResult::which(vec![1, 0])]
+            }))
+        });
+        assert!(runner.results.pass.is_empty());
+        assert_eq!(2, runner.results.fail.len());
+        let failure = &runner.results.fail[1];
+        let msg = failure.kind.fmt(&failure.test).unwrap();
+        assert!(msg.contains("(in any order)"), "{}", msg);
+    }
+
+    #[test]
+    fn unescape_regex() {
+        let data = r#"
+[[tests]]
+name = "single"
+regex = '\x00+'
+input = '\x00\x00'
+unescape = true
+unescape_regex = true
+matches = [[0, 2]]
+
+[[tests]]
+name = "many"
+regexes = ['a\x00', '\xFF']
+input = "a"
+unescape_regex = true
+which_matches = []
+
+[[tests]]
+name = "escaped"
+regex = '\x00'
+input = ""
+match = false
+"#;
+
+        let mut tests = RegexTests::new();
+        tests.load_slice("test", data.as_bytes()).unwrap();
+
+        let got = std::rc::Rc::new(std::cell::RefCell::new(vec![]));
+        let compiled = got.clone();
+        let mut runner = TestRunner::new().unwrap();
+        runner.test_iter(tests.iter(), move |_, regexes| {
+            compiled.borrow_mut().push(regexes.to_vec());
+            Ok(CompiledRegex::skip())
+        });
+        let expected: Vec<Vec<BString>> = vec![
+            vec![BString::from(&b"\x00+"[..])],
+            vec![BString::from(&b"a\x00"[..]), BString::from(&b"\xFF"[..])],
+            vec![BString::from(&br"\x00"[..])],
+        ];
+        assert_eq!(expected, got.take());
+    }
+
+    #[test]
+    fn which_iter() {
+        let got = TestResult::which_iter((0..5).filter(|i| i % 2 == 0));
+        let expected = TestResult::which(vec![0, 2, 4]);
+        assert_eq!(format!("{:?}", expected), format!("{:?}", got));
+
+        let got = TestResult::which_iter(std::iter::empty());
+        assert_eq!(
+            format!("{:?}", TestResult::no_match()),
+            format!("{:?}", got)
+        );
+    }
+
+    #[test]
+    fn auto_names_per_group() {
+        let data = r#"
+[[tests]]
+regex = "a"
+input = "a"
+match = true
+
+[[tests]]
+regex = "b"
+input = "b"
+match = true
+"#;
+
+        let mut tests = RegexTests::new();
+        tests.load_slice("test", data.as_bytes()).unwrap();
+        tests.load_slice("other", data.as_bytes()).unwrap();
+        tests.load_slice("test", data.as_bytes()).unwrap();
+        let names: Vec<&str> = tests.iter().map(|t| t.full_name()).collect();
+        assert_eq!(
+            vec!["test/1", "test/2", "other/1", "other/2", "test/3", "test/4",],
+            names,
+        );
+
+        // An explicit name that collides with an automatic one is an error.
+        let data = r#"
+[[tests]]
+name = "5"
+regex = "a"
+input = "a"
+match = true
+
+[[tests]]
+regex = "b"
+input = "b"
+match = true
+"#;
+        let err = tests.load_slice("test", data.as_bytes()).unwrap_err();
+        assert!(
+            format!("{:#}", err).contains("duplicate tests for name '5'"),
+            "{:#}",
+            err,
+        );
+    }
+
+    #[test]
+    fn load_dir() {
+        let data = r#"
+[[tests]]
+regex = "a"
+input = "a"
+match = true
+"#;
+
+        let dir = std::env::temp_dir()
+            .join(format!("regex-test-load-dir-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("nested")).unwrap();
+        fs::write(dir.join("zzz.toml"), data).unwrap();
+        fs::write(dir.join("nested").join("aaa.toml"), data).unwrap();
+        fs::write(dir.join("ignored.txt"), "not a test").unwrap();
+
+        let mut tests = RegexTests::new();
+        let result = tests.load_dir(&dir);
+        let names: Vec<String> =
+            tests.iter().map(|t| t.full_name().to_string()).collect();
+
+        // A file that fails to load is named in the error.
+        fs::write(dir.join("bad.toml"), "[[tests]]\nregex = 1\n").unwrap();
+        let err = RegexTests::new().load_dir(&dir).unwrap_err();
+        fs::remove_dir_all(&dir).unwrap();
+
+        result.unwrap();
+        assert_eq!(vec!["aaa/1", "zzz/1"], names);
+        assert!(format!("{:#}", err).contains("bad.toml"), "{:#}", err);
+    }
+
     #[test]
     fn load_matches() {
         let data = r#"
@@ -1338,8 +2678,8 @@ matches = [[0, 2], [5, 10]]
         assert_eq!(
             t0.matches(),
             Some(vec![
-                Match { start: 0, end: 2 },
-                Match { start: 5, end: 10 },
+                Match { id: 0, start: 0, end: 2 },
+                Match { id: 0, start: 5, end: 10 },
             ])
         );
         assert_eq!(t0.captures(), None);
@@ -1368,26 +2708,445 @@ captures = [
         assert_eq!(
             t0.matches(),
             Some(vec![
-                Match { start: 0, end: 15 },
-                Match { start: 20, end: 30 },
+                Match { id: 0, start: 0, end: 15 },
+                Match { id: 0, start: 20, end: 30 },
             ])
         );
         assert_eq!(
             t0.captures(),
             Some(vec![
                 Captures::new(vec![
-                    Some(Match { start: 0, end: 15 }),
-                    Some(Match { start: 5, end: 10 }),
+                    Some(Match { id: 0, start: 0, end: 15 }),
+                    Some(Match { id: 0, start: 5, end: 10 }),
                     None,
-                    Some(Match { start: 13, end: 14 }),
+                    Some(Match { id: 0, start: 13, end: 14 }),
                 ]),
                 Captures::new(vec![
-                    Some(Match { start: 20, end: 30 }),
-                    Some(Match { start: 22, end: 24 }),
-                    Some(Match { start: 25, end: 27 }),
+                    Some(Match { id: 0, start: 20, end: 30 }),
+                    Some(Match { id: 0, start: 22, end: 24 }),
+                    Some(Match { id: 0, start: 25, end: 27 }),
                     None,
                 ]),
             ])
         );
     }
+
+    #[test]
+    fn write_json_failure() {
+        let data = r#"
+[[tests]]
+name = "good"
+regex = "a"
+input = "a"
+matches = [[0, 1]]
+
+[[tests]]
+name = "bad"
+regex = "a"
+input = "a"
+matches = [[0, 1]]
+"#;
+
+        let mut tests = RegexTests::new();
+        tests.load_slice("test", data.as_bytes()).unwrap();
+
+        let mut runner = TestRunner::new().unwrap();
+        runner.test_iter(tests.iter(), |test, _| {
+            let m = if test.name() == "bad" {
+                Match { id: 0, start: 0, end: 0 }
+            } else {
+                Match { id: 0, start: 0, end: 1 }
+            };
+            Ok(CompiledRegex::compiled(move |_| {
+                vec![TestResult::matches(vec![m.clone()])]
+            }))
+        });
+
+        let mut buf = vec![];
+        runner.write_json(&mut buf).unwrap();
+        let got: Value = serde_json::from_slice(&buf).unwrap();
+        let expected = json!({
+            "tests": [
+                {"name": "test/good", "status": "pass"},
+                {
+                    "name": "test/bad",
+                    "status": "fail",
+                    "failure": {
+                        "kind": "start-end",
+                        "expected": [{"id": 0, "start": 0, "end": 1}],
+                        "got": [{"id": 0, "start": 0, "end": 0}],
+                    },
+                },
+            ],
+        });
+        assert_eq!(expected, got);
+    }
+
+    #[test]
+    fn slowest() {
+        let data = r#"
+[[tests]]
+name = "fast"
+regex = "a"
+input = "a"
+match = true
+
+[[tests]]
+name = "slow"
+regex = "a"
+input = "a"
+match = true
+"#;
+
+        let mut tests = RegexTests::new();
+        tests.load_slice("test", data.as_bytes()).unwrap();
+
+        let mut runner = TestRunner::new().unwrap();
+        runner.test_iter(tests.iter(), |test, _| {
+            if test.name() == "slow" {
+                std::thread::sleep(Duration::from_millis(20));
+            }
+            Ok(CompiledRegex::compiled(|_| vec![TestResult::matched()]))
+        });
+
+        let slowest = runner.slowest(10);
+        assert_eq!(2, slowest.len());
+        assert_eq!("test/slow", slowest[0].0);
+        assert!(slowest[0].1 >= Duration::from_millis(20));
+        assert!(slowest.windows(2).all(|w| w[0].1 >= w[1].1));
+        assert_eq!(vec![slowest[0].clone()], runner.slowest(1));
+    }
+
+    #[test]
+    fn load_pattern_flags() {
+        let data = r#"
+[[tests]]
+name = "global"
+regexes = ["a", "b"]
+input = "ab"
+match = true
+unicode = false
+
+[[tests]]
+name = "per-pattern"
+regexes = ["a", "b"]
+input = "ab"
+match = true
+case_insensitive = true
+pattern_flags = [{}, { unicode = false, utf8 = false }]
+"#;
+
+        let mut tests = RegexTests::new();
+        tests.load_slice("test", data.as_bytes()).unwrap();
+
+        let t0 = &tests.tests[0];
+        let global = PatternFlags {
+            case_insensitive: false,
+            unicode: false,
+            utf8: true,
+        };
+        assert_eq!(global, t0.pattern_flags(0));
+        assert_eq!(global, t0.pattern_flags(1));
+
+        let t1 = &tests.tests[1];
+        assert_eq!(
+            PatternFlags { case_insensitive: true, unicode: true, utf8: true },
+            t1.pattern_flags(0),
+        );
+        assert_eq!(
+            PatternFlags {
+                case_insensitive: true,
+                unicode: false,
+                utf8: false,
+            },
+            t1.pattern_flags(1),
+        );
+    }
+
+    #[test]
+    fn err_pattern_flags_mismatch() {
+        let data = r#"
+[[tests]]
+name = "foo"
+regexes = ["a", "b"]
+input = "ab"
+match = true
+pattern_flags = [{ unicode = false }]
+"#;
+
+        let mut tests = RegexTests::new();
+        assert!(tests.load_slice("test", data.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn compile_error() {
+        let data = r#"
+[[tests]]
+name = "right"
+regex = "("
+input = ""
+matches = []
+compile_error = "unclosed group"
+
+[[tests]]
+name = "wrong"
+regex = "("
+input = ""
+matches = []
+compile_error = "something else"
+"#;
+
+        let mut tests = RegexTests::new();
+        tests.load_slice("test", data.as_bytes()).unwrap();
+        assert!(!tests.tests[0].compiles());
+        assert_eq!(
+            Some("unclosed group".as_bytes().as_bstr()),
+            tests.tests[0].compile_error(),
+        );
+
+        let mut runner = TestRunner::new().unwrap();
+        runner.test_iter(tests.iter(), |_, _| {
+            // Simulate an implementation that wraps the syntax error.
+            let err = anyhow::anyhow!("error: unclosed group")
+                .context("error parsing regex");
+            Err(err.into())
+        });
+        assert_eq!(1, runner.results.pass.len());
+        assert_eq!("test/right", runner.results.pass[0].full_name());
+        assert_eq!(1, runner.results.fail.len());
+        assert_eq!("test/wrong", runner.results.fail[0].full_name());
+        match runner.results.fail[0].kind {
+            RegexTestFailureKind::WrongCompileError { .. } => {}
+            ref kind => panic!("unexpected failure kind: {:?}", kind),
+        }
+    }
+
+    #[test]
+    fn match_limit_truncates_expected() {
+        let data = r#"
+[[tests]]
+name = "matches"
+regex = "a"
+input = "aaa"
+matches = [[0, 1], [1, 2], [2, 3]]
+match_limit = 1
+
+[[tests]]
+name = "captures"
+regex = "(a)"
+input = "aaa"
+captures = [[[0, 1], [0, 1]], [[1, 2], [1, 2]], [[2, 3], [2, 3]]]
+match_limit = 1
+"#;
+
+        let mut tests = RegexTests::new();
+        tests.load_slice("test", data.as_bytes()).unwrap();
+
+        let mut runner = TestRunner::new().unwrap();
+        runner.test_iter(tests.iter(), |_, _| {
+            Ok(CompiledRegex::compiled(|test| {
+                let m = Match { id: 0, start: 0, end: 1 };
+                if test.name() == "matches" {
+                    vec![TestResult::matches(vec![m])]
+                } else {
+                    let caps = Captures::new(vec![Some(m.clone()), Some(m)]);
+                    vec![TestResult::captures(vec![caps])]
+                }
+            }))
+        });
+        assert_eq!(2, runner.results.pass.len());
+        assert!(runner.results.fail.is_empty());
+    }
+
+    #[test]
+    fn load_named_captures() {
+        let data = r#"
+[[tests]]
+name = "foo"
+regex = '(?P<year>[0-9]{4})-([0-9]{2})(?P<day>-[0-9]{2})?'
+input = "2021-04"
+captures = [
+  [[0, 7], { name = "year", span = [0, 4] }, [5, 7], { name = "day", span = [] }],
+]
+"#;
+
+        let mut tests = RegexTests::new();
+        tests.load_slice("test", data.as_bytes()).unwrap();
+
+        let caps = &tests.tests[0].captures().unwrap()[0];
+        assert_eq!(
+            Some(Some(Match { id: 0, start: 0, end: 4 })),
+            caps.group_by_name("year"),
+        );
+        assert_eq!(Some(None), caps.group_by_name("day"));
+        assert_eq!(None, caps.group_by_name("month"));
+        // Names don't participate in equality.
+        assert_eq!(
+            &Captures::new(vec![
+                Some(Match { id: 0, start: 0, end: 7 }),
+                Some(Match { id: 0, start: 0, end: 4 }),
+                Some(Match { id: 0, start: 5, end: 7 }),
+                None,
+            ]),
+            caps,
+        );
+
+        let got = Captures::new(vec![
+            Some(Match { id: 0, start: 0, end: 7 }),
+            Some(Match { id: 0, start: 0, end: 3 }),
+            Some(Match { id: 0, start: 5, end: 7 }),
+            None,
+        ]);
+        let mut buf = String::new();
+        write_first_captures_difference(&mut buf, &[caps.clone()], &[got])
+            .unwrap();
+        assert_eq!(
+            "\nfirst difference at captures 0, group \"year\": \
+             expected 0..4, got 0..3",
+            buf,
+        );
+    }
+
+    #[test]
+    fn start_end_first_difference() {
+        let m = |id, start, end| Match { id, start, end };
+
+        let expected = vec![
+            m(0, 0, 1),
+            m(0, 2, 3),
+            m(0, 4, 5),
+            m(0, 6, 7),
+            m(0, 8, 9),
+            m(0, 10, 11),
+        ];
+        let mut buf = String::new();
+        write_first_difference(&mut buf, &expected, &expected).unwrap();
+        assert_eq!("", buf);
+
+        let mut got = expected.clone();
+        got[3] = m(0, 6, 8);
+        got.push(m(0, 12, 13));
+        let mut buf = String::new();
+        write_first_difference(&mut buf, &expected, &got).unwrap();
+        assert_eq!(
+            "\nfirst difference at match 3: expected 6..7, got 6..8\
+             \nexpected near: ... 2..3 4..5 6..7 8..9 10..11\
+             \n    got near: ... 2..3 4..5 6..8 8..9 10..11 ...",
+            buf,
+        );
+
+        // A match that is missing is shown as 'none', and matches from any
+        // regex other than the first are prefixed with their ID.
+        let expected = vec![m(0, 0, 1)];
+        let got = vec![m(0, 0, 1), m(1, 2, 3)];
+        let mut buf = String::new();
+        write_first_difference(&mut buf, &expected, &got).unwrap();
+        assert_eq!(
+            "\nfirst difference at match 1: expected none, got 1:2..3\
+             \nexpected near: 0..1\
+             \n    got near: 0..1 1:2..3",
+            buf,
+        );
+    }
+
+    #[test]
+    fn on_result_callback() {
+        let data = r#"
+[[tests]]
+name = "pass"
+regex = "a"
+input = "a"
+matches = [[0, 1]]
+
+[[tests]]
+name = "fail"
+regex = "a"
+input = "a"
+matches = [[0, 1]]
+
+[[tests]]
+name = "skip"
+regex = "a"
+input = "a"
+matches = [[0, 1]]
+"#;
+
+        let mut tests = RegexTests::new();
+        tests.load_slice("test", data.as_bytes()).unwrap();
+
+        let seen: std::rc::Rc<std::cell::RefCell<Vec<(String, Status)>>> =
+            Default::default();
+        let mut runner = TestRunner::new().unwrap();
+        let callback_seen = seen.clone();
+        runner.on_result(move |name, status| {
+            callback_seen.borrow_mut().push((name.to_string(), status));
+        });
+        runner.test_iter(tests.iter(), |test, _| {
+            let m = match test.name() {
+                "skip" => return Ok(CompiledRegex::skip()),
+                "fail" => Match { id: 0, start: 0, end: 0 },
+                _ => Match { id: 0, start: 0, end: 1 },
+            };
+            Ok(CompiledRegex::compiled(move |_| {
+                vec![TestResult::matches(vec![m.clone()])]
+            }))
+        });
+        assert_eq!(
+            seen.take(),
+            vec![
+                ("test/pass".to_string(), Status::Pass),
+                ("test/fail".to_string(), Status::Fail),
+                ("test/skip".to_string(), Status::Skip),
+            ]
+        );
+    }
+
+    #[test]
+    fn seed_shuffles_deterministically() {
+        let mut data = String::new();
+        for i in 0..20 {
+            data.push_str(&format!(
+                "[[tests]]\nname = \"t{}\"\nregex = \"a\"\ninput = \"a\"\n\
+                 matches = [[0, 1]]\n\n",
+                i
+            ));
+        }
+        let mut tests = RegexTests::new();
+        tests.load_slice("test", data.as_bytes()).unwrap();
+
+        let run = |seed: Option<u64>| -> Vec<String> {
+            let order: std::rc::Rc<std::cell::RefCell<Vec<String>>> =
+                Default::default();
+            let callback_order = order.clone();
+            let mut runner = TestRunner::new().unwrap();
+            runner.seed(seed).on_result(move |name, _| {
+                callback_order.borrow_mut().push(name.to_string());
+            });
+            runner.test_iter(tests.iter(), |_, _| {
+                Ok(CompiledRegex::compiled(|_| {
+                    vec![TestResult::matches(vec![Match {
+                        id: 0,
+                        start: 0,
+                        end: 1,
+                    }])]
+                }))
+            });
+            runner.assert();
+            order.take()
+        };
+
+        let unshuffled = run(None);
+        let expected: Vec<String> =
+            (0..20).map(|i| format!("test/t{}", i)).collect();
+        assert_eq!(expected, unshuffled);
+
+        let shuffled1 = run(Some(12345));
+        let shuffled2 = run(Some(12345));
+        assert_eq!(shuffled1, shuffled2);
+        assert_ne!(unshuffled, shuffled1);
+
+        let mut sorted = shuffled1.clone();
+        sorted.sort_by_key(|name| name[6..].parse::<usize>().unwrap());
+        assert_eq!(unshuffled, sorted);
+    }
 }
@@ -54,7 +54,13 @@ use core::convert::{TryFrom, TryInto};
 #[cfg(feature = "alloc")]
 use alloc::{vec, vec::Vec};
 
-use crate::util::bytes::{self, DeserializeError, Endian, SerializeError};
+use crate::{
+    dfa::automaton::Automaton,
+    util::{
+        bytes::{self, DeserializeError, Endian, SerializeError},
+        id::StateID,
+    },
+};
 
 /// The base type used to represent a collection of accelerators.
 ///
@@ -85,8 +91,9 @@ const ACCEL_LEN: usize = 4;
 const ACCEL_CAP: usize = 8;
 
 /// Search for between 1 and 3 needle bytes in the given haystack, starting the
-/// search at the given position. If `needles` has a length other than 1-3,
-/// then this panics.
+/// search at the given position. If `needles` is empty, then no acceleration
+/// is performed and `at` is returned. If `needles` has a length greater than
+/// 3, then this panics.
 #[inline(always)]
 pub(crate) fn find_fwd(
     needles: &[u8],
@@ -98,15 +105,17 @@ pub(crate) fn find_fwd(
         1 => memchr::memchr(bs[0], &haystack[at..])?,
         2 => memchr::memchr2(bs[0], bs[1], &haystack[at..])?,
         3 => memchr::memchr3(bs[0], bs[1], bs[2], &haystack[at..])?,
-        0 => panic!("cannot find with empty needles"),
+        0 => 0,
         n => panic!("invalid needles length: {}", n),
     };
     Some(at + i)
 }
 
 /// Search for between 1 and 3 needle bytes in the given haystack in reverse,
-/// starting the search at the given position. If `needles` has a length other
-/// than 1-3, then this panics.
+/// starting the search at the given position. If `needles` is empty, then no
+/// acceleration is performed and the position immediately before `at` is
+/// returned (or `None` if `at` is `0`). If `needles` has a length greater
+/// than 3, then this panics.
 #[inline(always)]
 pub(crate) fn find_rev(
     needles: &[u8],
@@ -118,11 +127,82 @@ pub(crate) fn find_rev(
         1 => memchr::memrchr(bs[0], &haystack[..at]),
         2 => memchr::memrchr2(bs[0], bs[1], &haystack[..at]),
         3 => memchr::memrchr3(bs[0], bs[1], bs[2], &haystack[..at]),
-        0 => panic!("cannot find with empty needles"),
+        0 => at.checked_sub(1),
         n => panic!("invalid needles length: {}", n),
     }
 }
 
+/// Skip ahead in `haystack` from `at` while the given DFA state would only
+/// transition back to itself.
+///
+/// If `id` is an accelerated state, then this returns the position of the
+/// next byte at or after `at` that may cause the DFA to leave `id`. If no
+/// such byte exists, then `haystack.len()` is returned. If `id` is not an
+/// accelerated state, then `at` is returned unchanged.
+///
+/// This is useful when implementing a custom search loop on top of the
+/// [`Automaton`] trait. The search routines provided by this crate already
+/// apply acceleration, so there is no need to use this with them.
+///
+/// Note that states are rarely accelerated when Unicode mode or UTF-8 mode
+/// is enabled, since matching only valid UTF-8 generally requires more than
+/// a single DFA state. Disabling both (as in the example below) usually makes
+/// acceleration much more likely to apply.
+///
+/// # Panics
+///
+/// This panics if `at > haystack.len()`.
+///
+/// # Example
+///
+/// This example shows a simple forward search loop that uses acceleration
+/// to skip over a megabyte of filler with only a handful of transitions.
+///
+/// ```
+/// use regex_automata::{
+///     dfa::{accelerate, dense, Automaton},
+///     nfa::thompson,
+///     SyntaxConfig,
+/// };
+///
+/// let dfa = dense::Builder::new()
+///     .syntax(SyntaxConfig::new().utf8(false))
+///     .thompson(thompson::Config::new().utf8(false))
+///     .build("(?s-u).*needle")?;
+/// let mut haystack = vec![b'z'; 1 << 20];
+/// haystack.extend_from_slice(b"needle");
+///
+/// let mut state = dfa.start_state_forward(None, &haystack, 0, haystack.len());
+/// let (mut at, mut transitions) = (0, 0);
+/// while at < haystack.len() {
+///     at = accelerate(&dfa, state, &haystack, at);
+///     if at == haystack.len() {
+///         break;
+///     }
+///     state = dfa.next_state(state, haystack[at]);
+///     transitions += 1;
+///     at += 1;
+/// }
+/// state = dfa.next_eoi_state(state);
+/// assert!(dfa.is_match_state(state));
+/// assert!(transitions < 100);
+///
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[inline]
+pub fn accelerate<A: Automaton + ?Sized>(
+    dfa: &A,
+    id: StateID,
+    haystack: &[u8],
+    at: usize,
+) -> usize {
+    assert!(at <= haystack.len(), "invalid position {}", at);
+    if !dfa.is_accel_state(id) {
+        return at;
+    }
+    find_fwd(dfa.accelerator(id), haystack, at).unwrap_or(haystack.len())
+}
+
 /// Represents the accelerators for all accelerated states in a dense DFA.
 ///
 /// The `A` type parameter represents the type of the underlying bytes.
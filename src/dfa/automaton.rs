@@ -52,6 +52,8 @@ use crate::{
 /// other type of state.) Those types are:
 ///     * A dead state. A dead state means the DFA will never enter a match
 ///     state. This can be queried via the [`Automaton::is_dead_state`] method.
+///     The search routines provided by this trait stop immediately upon
+///     entering a dead state, without scanning the rest of the haystack.
 ///     * A quit state. A quit state occurs if the DFA had to stop the search
 ///     prematurely for some reason. This can be queried via the
 ///     [`Automaton::is_quit_state`] method.
@@ -879,6 +881,38 @@ pub unsafe trait Automaton {
         &[]
     }
 
+    /// Returns true if and only if this automaton only reports matches that
+    /// begin at the start of the search, i.e., it was built as an anchored
+    /// automaton.
+    ///
+    /// Search routines use this to decide whether a prefilter may be used to
+    /// skip ahead in the haystack. Since an anchored automaton can only match
+    /// at the position where the search starts, skipping ahead would report
+    /// matches that don't actually exist.
+    ///
+    /// By default, this method always returns false.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use regex_automata::dfa::{Automaton, dense};
+    ///
+    /// let dfa = dense::DFA::new("foo[0-9]+")?;
+    /// assert!(!dfa.is_anchored());
+    ///
+    /// let dfa = dense::Builder::new()
+    ///     .configure(dense::Config::new().anchored(true))
+    ///     .build("foo[0-9]+")?;
+    /// assert!(dfa.is_anchored());
+    /// // Whether a DFA is anchored is preserved by serialization.
+    /// let (bytes, pad) = dfa.to_bytes_native_endian();
+    /// assert!(dense::DFA::from_bytes(&bytes[pad..])?.0.is_anchored());
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    fn is_anchored(&self) -> bool {
+        false
+    }
+
     /// Executes a forward search and returns the end position of the first
     /// match that is found as early as possible. If no match exists, then
     /// `None` is returned.
@@ -1079,6 +1113,49 @@ pub unsafe trait Automaton {
         self.find_leftmost_fwd_at(None, None, bytes, 0, bytes.len())
     }
 
+    /// Returns an iterator over the end offsets of all non-overlapping
+    /// leftmost matches in `bytes`, using leftmost first match semantics.
+    ///
+    /// This repeatedly calls [`Automaton::find_leftmost_fwd_at`], starting
+    /// each search where the previous match ended. An empty match at the
+    /// same position as the previous match is never reported. Instead, the
+    /// search is retried at the next UTF-8 encoded codepoint (or the next
+    /// byte if [`HalfMatches::utf8`] is disabled).
+    ///
+    /// The iterator yields an error whenever a search fails, in which case
+    /// callers should stop iterating. If the start of each match is needed,
+    /// then use a [`Regex`](crate::dfa::regex::Regex) or a
+    /// [`FullMatches`](crate::dfa::FullMatches) iterator instead.
+    ///
+    /// Since this trait provides an implementation for this method by default,
+    /// it's unlikely that one will need to implement this.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use regex_automata::{
+    ///     dfa::{Automaton, sparse},
+    ///     HalfMatch,
+    /// };
+    ///
+    /// let dfa = sparse::DFA::new(r"[0-9]+")?;
+    /// let matches: Vec<HalfMatch> =
+    ///     dfa.find_leftmost_fwd_iter(b"a1b22c").collect::<Result<_, _>>()?;
+    /// assert_eq!(matches, vec![HalfMatch::must(0, 2), HalfMatch::must(0, 5)]);
+    ///
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    #[inline]
+    fn find_leftmost_fwd_iter<'a, 't>(
+        &'a self,
+        bytes: &'t [u8],
+    ) -> HalfMatches<'a, 't, Self>
+    where
+        Self: Sized,
+    {
+        HalfMatches::new(self, bytes)
+    }
+
     /// Executes a reverse search and returns the start of the position of the
     /// leftmost match that is found. If no match exists, then `None` is
     /// returned.
@@ -1223,6 +1300,125 @@ pub unsafe trait Automaton {
         self.find_overlapping_fwd_at(None, None, bytes, 0, bytes.len(), state)
     }
 
+    /// Executes an overlapping reverse search and returns the start position
+    /// of matches as they are found. If no match exists, then `None` is
+    /// returned.
+    ///
+    /// This is like [`Automaton::find_overlapping_fwd`], except it scans the
+    /// haystack backwards. It should be used with a DFA that was built from
+    /// a reversed NFA, for example, by enabling
+    /// [`nfa::thompson::Config::reverse`](crate::nfa::thompson::Config::reverse).
+    ///
+    /// Like its forward counterpart, callers must preserve the automaton's
+    /// search state from prior calls so that the implementation knows where
+    /// the last match occurred. In order to find every overlapping match, use
+    /// [`Automaton::find_overlapping_rev_at`] and set the end of each search
+    /// to the position of the previous match.
+    ///
+    /// # Errors
+    ///
+    /// This routine only errors if the search could not complete. For
+    /// DFAs generated by this crate, this only occurs in a non-default
+    /// configuration where quit bytes are used or Unicode word boundaries are
+    /// heuristically enabled.
+    ///
+    /// When a search cannot complete, callers cannot know whether a match
+    /// exists or not.
+    ///
+    /// # Example
+    ///
+    /// This example shows how to run a basic overlapping reverse search with
+    /// a [`dense::DFA`](crate::dfa::dense::DFA). As with forward overlapping
+    /// searches, the automaton should be built with `MatchKind::All`.
+    ///
+    /// ```
+    /// use regex_automata::{
+    ///     dfa::{Automaton, OverlappingState, dense},
+    ///     nfa::thompson,
+    ///     HalfMatch,
+    ///     MatchKind,
+    /// };
+    ///
+    /// let dfa = dense::Builder::new()
+    ///     .configure(dense::Config::new().match_kind(MatchKind::All))
+    ///     .thompson(thompson::Config::new().reverse(true))
+    ///     .build_many(&[r"\w+", r"\S+"])?;
+    /// let haystack = "@foo".as_bytes();
+    /// let mut state = OverlappingState::start();
+    ///
+    /// let expected = Some(HalfMatch::must(0, 3));
+    /// let got = dfa.find_overlapping_rev(haystack, &mut state)?;
+    /// assert_eq!(expected, got);
+    ///
+    /// // The second pattern also matches at the same position, so re-running
+    /// // the search will yield another match.
+    /// let expected = Some(HalfMatch::must(1, 3));
+    /// let got = dfa.find_overlapping_rev(haystack, &mut state)?;
+    /// assert_eq!(expected, got);
+    ///
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    #[inline]
+    fn find_overlapping_rev(
+        &self,
+        bytes: &[u8],
+        state: &mut OverlappingState,
+    ) -> Result<Option<HalfMatch>, MatchError> {
+        self.find_overlapping_rev_at(None, bytes, 0, bytes.len(), state)
+    }
+
+    /// Executes a forward search and returns a match only if one ends at
+    /// the end of the haystack. If no such match exists, then `None` is
+    /// returned.
+    ///
+    /// Unlike the other search routines, this does not stop when it observes
+    /// a match state. Instead, it scans the entire haystack and then follows
+    /// the DFA's end-of-input transition. A match is reported if and only if
+    /// that transition leads to a match state, in which case the offset of
+    /// the match is always `bytes.len()`.
+    ///
+    /// This is useful for checking that a pattern matches all of the
+    /// remaining input, e.g., when tokenizing. Combined with an anchored
+    /// search, this checks whether the pattern matches the entire haystack.
+    ///
+    /// Note that the result is subject to the DFA's match semantics. With
+    /// leftmost-first semantics, the DFA may stop once it has seen a match
+    /// that is preferred over all longer matches. For example, `a|ab` will
+    /// not report a match ending at the end of `ab`. Build the DFA with
+    /// [`MatchKind::All`](crate::MatchKind::All) to consider every match.
+    ///
+    /// # Errors
+    ///
+    /// This routine only errors if the search could not complete. For
+    /// DFAs generated by this crate, this only occurs in a non-default
+    /// configuration where quit bytes are used or Unicode word boundaries are
+    /// heuristically enabled.
+    ///
+    /// When a search cannot complete, callers cannot know whether a match
+    /// exists or not.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use regex_automata::{dfa::{Automaton, dense}, HalfMatch};
+    ///
+    /// let dfa = dense::DFA::new("foo")?;
+    /// // A match exists, but it does not end at the end of the haystack.
+    /// assert_eq!(None, dfa.find_fwd_anchored_end(b"foobar")?);
+    /// assert_eq!(
+    ///     Some(HalfMatch::must(0, 3)),
+    ///     dfa.find_fwd_anchored_end(b"foo")?,
+    /// );
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    #[inline]
+    fn find_fwd_anchored_end(
+        &self,
+        bytes: &[u8],
+    ) -> Result<Option<HalfMatch>, MatchError> {
+        self.find_fwd_anchored_end_at(None, bytes, 0, bytes.len())
+    }
+
     /// Executes a forward search and returns the end position of the first
     /// match that is found as early as possible. If no match exists, then
     /// `None` is returned.
@@ -1464,7 +1660,47 @@ pub unsafe trait Automaton {
         start: usize,
         end: usize,
     ) -> Result<Option<HalfMatch>, MatchError> {
-        search::find_earliest_rev(self, pattern_id, bytes, start, end)
+        search::find_earliest_rev(None, self, pattern_id, bytes, start, end)
+    }
+
+    /// This is like [`Automaton::find_earliest_rev_at`], except it also
+    /// accepts a prefilter to use during the search.
+    ///
+    /// A prefilter given to a reverse search is run via
+    /// [`Prefilter::find_rev`](crate::util::prefilter::Prefilter::find_rev),
+    /// and so it must report the positions at which matches may *end*. If
+    /// the prefilter does not support reverse searches, then it is ignored.
+    /// As with forward searches, a prefilter is never used when a
+    /// `pattern_id` is given, since such searches are always anchored.
+    /// Unlike forward searches, a prefilter is also never used when
+    /// [`Automaton::is_anchored`] returns true.
+    ///
+    /// # Errors
+    ///
+    /// This routine only errors if the search could not complete. For
+    /// DFAs generated by this crate, this only occurs in a non-default
+    /// configuration where quit bytes are used or Unicode word boundaries are
+    /// heuristically enabled.
+    ///
+    /// When a search cannot complete, callers cannot know whether a match
+    /// exists or not.
+    ///
+    /// # Panics
+    ///
+    /// This routine must panic if a `pattern_id` is given and the underlying
+    /// DFA does not support specific pattern searches.
+    ///
+    /// It must also panic if the given haystack range is not valid.
+    #[inline]
+    fn find_earliest_rev_at_with_prefilter(
+        &self,
+        pre: Option<&mut prefilter::Scanner>,
+        pattern_id: Option<PatternID>,
+        bytes: &[u8],
+        start: usize,
+        end: usize,
+    ) -> Result<Option<HalfMatch>, MatchError> {
+        search::find_earliest_rev(pre, self, pattern_id, bytes, start, end)
     }
 
     /// Executes a forward search and returns the end position of the leftmost
@@ -1536,7 +1772,47 @@ pub unsafe trait Automaton {
         start: usize,
         end: usize,
     ) -> Result<Option<HalfMatch>, MatchError> {
-        search::find_leftmost_rev(self, pattern_id, bytes, start, end)
+        search::find_leftmost_rev(None, self, pattern_id, bytes, start, end)
+    }
+
+    /// This is like [`Automaton::find_leftmost_rev_at`], except it also
+    /// accepts a prefilter to use during the search.
+    ///
+    /// A prefilter given to a reverse search is run via
+    /// [`Prefilter::find_rev`](crate::util::prefilter::Prefilter::find_rev),
+    /// and so it must report the positions at which matches may *end*. If
+    /// the prefilter does not support reverse searches, then it is ignored.
+    /// As with forward searches, a prefilter is never used when a
+    /// `pattern_id` is given, since such searches are always anchored.
+    /// Unlike forward searches, a prefilter is also never used when
+    /// [`Automaton::is_anchored`] returns true.
+    ///
+    /// # Errors
+    ///
+    /// This routine only errors if the search could not complete. For
+    /// DFAs generated by this crate, this only occurs in a non-default
+    /// configuration where quit bytes are used or Unicode word boundaries are
+    /// heuristically enabled.
+    ///
+    /// When a search cannot complete, callers cannot know whether a match
+    /// exists or not.
+    ///
+    /// # Panics
+    ///
+    /// This routine must panic if a `pattern_id` is given and the underlying
+    /// DFA does not support specific pattern searches.
+    ///
+    /// It must also panic if the given haystack range is not valid.
+    #[inline]
+    fn find_leftmost_rev_at_with_prefilter(
+        &self,
+        pre: Option<&mut prefilter::Scanner>,
+        pattern_id: Option<PatternID>,
+        bytes: &[u8],
+        start: usize,
+        end: usize,
+    ) -> Result<Option<HalfMatch>, MatchError> {
+        search::find_leftmost_rev(pre, self, pattern_id, bytes, start, end)
     }
 
     /// Executes an overlapping forward search and returns the end position of
@@ -1594,6 +1870,164 @@ pub unsafe trait Automaton {
             pre, self, pattern_id, bytes, start, end, state,
         )
     }
+
+    /// Executes an overlapping reverse search and returns the start position
+    /// of matches as they are found. If no match exists, then `None` is
+    /// returned.
+    ///
+    /// This is like [`Automaton::find_overlapping_rev`], except it provides
+    /// some additional control over how the search is executed:
+    ///
+    /// * `pattern_id` specifies a specific pattern in the DFA to run an
+    /// anchored search for. If not given, then a search for any pattern is
+    /// performed. For DFAs built by this crate, pattern specific searches
+    /// are only supported when
+    /// [`dense::Config::starts_for_each_pattern`](crate::dfa::dense::Config::starts_for_each_pattern)
+    /// is enabled.
+    /// * The search is executed over the range `start..end` of `bytes`.
+    ///
+    /// When resuming a search, callers should set `end` to the position of
+    /// the last match. If more patterns match at that position, then they
+    /// will be immediately returned. (This is tracked by the given
+    /// overlapping state.) Otherwise, the search continues backwards from
+    /// the position given.
+    ///
+    /// If for some reason you want the search to forget about its previous
+    /// state and restart the search at a particular position, then setting the
+    /// state to [`OverlappingState::start`] will accomplish that.
+    ///
+    /// # Errors
+    ///
+    /// This routine only errors if the search could not complete. For
+    /// DFAs generated by this crate, this only occurs in a non-default
+    /// configuration where quit bytes are used or Unicode word boundaries are
+    /// heuristically enabled.
+    ///
+    /// When a search cannot complete, callers cannot know whether a match
+    /// exists or not.
+    ///
+    /// # Panics
+    ///
+    /// This routine must panic if a `pattern_id` is given and the underlying
+    /// DFA does not support specific pattern searches.
+    ///
+    /// It must also panic if the given haystack range is not valid.
+    ///
+    /// # Example
+    ///
+    /// This example shows how to find the starting position of every
+    /// overlapping match by resuming each search at the previous match.
+    ///
+    /// ```
+    /// use regex_automata::{
+    ///     dfa::{Automaton, OverlappingState, dense},
+    ///     nfa::thompson,
+    ///     HalfMatch,
+    ///     MatchKind,
+    /// };
+    ///
+    /// let dfa = dense::Builder::new()
+    ///     .configure(dense::Config::new().match_kind(MatchKind::All))
+    ///     .thompson(thompson::Config::new().reverse(true))
+    ///     .build(r"[a-z]+$")?;
+    /// let haystack = "abc".as_bytes();
+    /// let mut state = OverlappingState::start();
+    ///
+    /// let mut starts = vec![];
+    /// let mut end = haystack.len();
+    /// while let Some(m) = dfa.find_overlapping_rev_at(
+    ///     None, haystack, 0, end, &mut state,
+    /// )? {
+    ///     starts.push(m.offset());
+    ///     end = m.offset();
+    /// }
+    /// assert_eq!(starts, vec![2, 1, 0]);
+    ///
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    #[inline]
+    fn find_overlapping_rev_at(
+        &self,
+        pattern_id: Option<PatternID>,
+        bytes: &[u8],
+        start: usize,
+        end: usize,
+        state: &mut OverlappingState,
+    ) -> Result<Option<HalfMatch>, MatchError> {
+        search::find_overlapping_rev(
+            self, pattern_id, bytes, start, end, state,
+        )
+    }
+
+    /// Executes a forward search and returns a match only if one ends at
+    /// `end`. If no such match exists, then `None` is returned.
+    ///
+    /// This is like [`Automaton::find_fwd_anchored_end`], except it provides
+    /// some additional control over how the search is executed:
+    ///
+    /// * `pattern_id` specifies a specific pattern in the DFA to run an
+    /// anchored search for. If not given, then a search for any pattern is
+    /// performed. For DFAs built by this crate,
+    /// [`dense::Config::starts_for_each_pattern`](crate::dfa::dense::Config::starts_for_each_pattern)
+    /// must be enabled to use this functionality.
+    /// * `start` and `end` permit searching a specific region of the haystack
+    /// `bytes`. This is useful when implementing an iterator over matches
+    /// within the same haystack, which cannot be done correctly by simply
+    /// providing a subslice of `bytes`. (Because the existence of look-around
+    /// operations such as `\b`, `^` and `$` need to take the surrounding
+    /// context into account. This cannot be done if the haystack doesn't
+    /// contain it.)
+    ///
+    /// The examples below demonstrate each of these additional parameters.
+    ///
+    /// # Errors
+    ///
+    /// This routine only errors if the search could not complete. For
+    /// DFAs generated by this crate, this only occurs in a non-default
+    /// configuration where quit bytes are used or Unicode word boundaries are
+    /// heuristically enabled.
+    ///
+    /// When a search cannot complete, callers cannot know whether a match
+    /// exists or not.
+    ///
+    /// # Panics
+    ///
+    /// This routine must panic if a `pattern_id` is given and the underlying
+    /// DFA does not support specific pattern searches.
+    ///
+    /// It must also panic if the given haystack range is not valid.
+    ///
+    /// # Example
+    ///
+    /// This example shows how to check whether each word in a haystack is
+    /// entirely made up of lowercase letters, without splitting the haystack.
+    ///
+    /// ```
+    /// use regex_automata::{dfa::{Automaton, dense}, HalfMatch};
+    ///
+    /// let dfa = dense::Builder::new()
+    ///     .configure(dense::Config::new().anchored(true))
+    ///     .build(r"[a-z]+")?;
+    /// let haystack = b"foo bAr baz";
+    ///
+    /// let got = dfa.find_fwd_anchored_end_at(None, haystack, 0, 3)?;
+    /// assert_eq!(Some(HalfMatch::must(0, 3)), got);
+    /// let got = dfa.find_fwd_anchored_end_at(None, haystack, 4, 7)?;
+    /// assert_eq!(None, got);
+    /// let got = dfa.find_fwd_anchored_end_at(None, haystack, 8, 11)?;
+    /// assert_eq!(Some(HalfMatch::must(0, 11)), got);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    #[inline]
+    fn find_fwd_anchored_end_at(
+        &self,
+        pattern_id: Option<PatternID>,
+        bytes: &[u8],
+        start: usize,
+        end: usize,
+    ) -> Result<Option<HalfMatch>, MatchError> {
+        search::find_fwd_anchored_end(self, pattern_id, bytes, start, end)
+    }
 }
 
 unsafe impl<'a, T: Automaton> Automaton for &'a T {
@@ -1688,6 +2122,11 @@ unsafe impl<'a, T: Automaton> Automaton for &'a T {
         (**self).accelerator(id)
     }
 
+    #[inline]
+    fn is_anchored(&self) -> bool {
+        (**self).is_anchored()
+    }
+
     #[inline]
     fn find_earliest_fwd(
         &self,
@@ -1729,6 +2168,23 @@ unsafe impl<'a, T: Automaton> Automaton for &'a T {
         (**self).find_overlapping_fwd(bytes, state)
     }
 
+    #[inline]
+    fn find_overlapping_rev(
+        &self,
+        bytes: &[u8],
+        state: &mut OverlappingState,
+    ) -> Result<Option<HalfMatch>, MatchError> {
+        (**self).find_overlapping_rev(bytes, state)
+    }
+
+    #[inline]
+    fn find_fwd_anchored_end(
+        &self,
+        bytes: &[u8],
+    ) -> Result<Option<HalfMatch>, MatchError> {
+        (**self).find_fwd_anchored_end(bytes)
+    }
+
     #[inline]
     fn find_earliest_fwd_at(
         &self,
@@ -1752,6 +2208,20 @@ unsafe impl<'a, T: Automaton> Automaton for &'a T {
         (**self).find_earliest_rev_at(pattern_id, bytes, start, end)
     }
 
+    #[inline]
+    fn find_earliest_rev_at_with_prefilter(
+        &self,
+        pre: Option<&mut prefilter::Scanner>,
+        pattern_id: Option<PatternID>,
+        bytes: &[u8],
+        start: usize,
+        end: usize,
+    ) -> Result<Option<HalfMatch>, MatchError> {
+        (**self).find_earliest_rev_at_with_prefilter(
+            pre, pattern_id, bytes, start, end,
+        )
+    }
+
     #[inline]
     fn find_leftmost_fwd_at(
         &self,
@@ -1775,6 +2245,20 @@ unsafe impl<'a, T: Automaton> Automaton for &'a T {
         (**self).find_leftmost_rev_at(pattern_id, bytes, start, end)
     }
 
+    #[inline]
+    fn find_leftmost_rev_at_with_prefilter(
+        &self,
+        pre: Option<&mut prefilter::Scanner>,
+        pattern_id: Option<PatternID>,
+        bytes: &[u8],
+        start: usize,
+        end: usize,
+    ) -> Result<Option<HalfMatch>, MatchError> {
+        (**self).find_leftmost_rev_at_with_prefilter(
+            pre, pattern_id, bytes, start, end,
+        )
+    }
+
     #[inline]
     fn find_overlapping_fwd_at(
         &self,
@@ -1788,6 +2272,103 @@ unsafe impl<'a, T: Automaton> Automaton for &'a T {
         (**self)
             .find_overlapping_fwd_at(pre, pattern_id, bytes, start, end, state)
     }
+
+    #[inline]
+    fn find_overlapping_rev_at(
+        &self,
+        pattern_id: Option<PatternID>,
+        bytes: &[u8],
+        start: usize,
+        end: usize,
+        state: &mut OverlappingState,
+    ) -> Result<Option<HalfMatch>, MatchError> {
+        (**self).find_overlapping_rev_at(pattern_id, bytes, start, end, state)
+    }
+
+    #[inline]
+    fn find_fwd_anchored_end_at(
+        &self,
+        pattern_id: Option<PatternID>,
+        bytes: &[u8],
+        start: usize,
+        end: usize,
+    ) -> Result<Option<HalfMatch>, MatchError> {
+        (**self).find_fwd_anchored_end_at(pattern_id, bytes, start, end)
+    }
+}
+
+/// An iterator over the end offsets of all non-overlapping leftmost matches
+/// for a particular search.
+///
+/// This iterator is created by [`Automaton::find_leftmost_fwd_iter`].
+///
+/// The lifetime variables are as follows:
+///
+/// * `'a` is the lifetime of the automaton.
+/// * `'t` is the lifetime of the text being searched.
+#[derive(Clone, Debug)]
+pub struct HalfMatches<'a, 't, A> {
+    dfa: &'a A,
+    utf8: bool,
+    text: &'t [u8],
+    last_end: usize,
+    last_match: Option<usize>,
+}
+
+impl<'a, 't, A: Automaton> HalfMatches<'a, 't, A> {
+    fn new(dfa: &'a A, text: &'t [u8]) -> HalfMatches<'a, 't, A> {
+        HalfMatches { dfa, utf8: true, text, last_end: 0, last_match: None }
+    }
+
+    /// Whether to advance by a full UTF-8 encoded codepoint after an empty
+    /// match, instead of a single byte. This is enabled by default.
+    ///
+    /// When enabled, empty matches are never reported in the middle of a
+    /// codepoint, assuming the haystack is valid UTF-8. When disabled, an
+    /// empty match may be reported at every byte offset.
+    pub fn utf8(mut self, yes: bool) -> HalfMatches<'a, 't, A> {
+        self.utf8 = yes;
+        self
+    }
+}
+
+impl<'a, 't, A: Automaton> Iterator for HalfMatches<'a, 't, A> {
+    type Item = Result<HalfMatch, MatchError>;
+
+    fn next(&mut self) -> Option<Result<HalfMatch, MatchError>> {
+        loop {
+            if self.last_end > self.text.len() {
+                return None;
+            }
+            let result = self.dfa.find_leftmost_fwd_at(
+                None,
+                None,
+                self.text,
+                self.last_end,
+                self.text.len(),
+            );
+            let m = match result {
+                Err(err) => return Some(Err(err)),
+                Ok(None) => return None,
+                Ok(Some(m)) => m,
+            };
+            // Since each search begins where the previous match ended, a
+            // match ending at that same position must be empty. We never
+            // report an empty match immediately following a match, so skip
+            // ahead and try again.
+            if Some(m.offset()) == self.last_match {
+                self.last_end = if self.utf8 {
+                    crate::util::next_utf8(self.text, m.offset())
+                } else {
+                    m.offset() + 1
+                };
+                continue;
+            }
+            self.last_end = m.offset();
+            self.last_match = Some(m.offset());
+            return Some(Ok(m));
+        }
+    }
 }
 
 /// Represents the current state of an overlapping search.
@@ -0,0 +1,309 @@
+use alloc::vec::Vec;
+
+use crate::{
+    dfa::{
+        automaton::{Automaton, OverlappingState},
+        dense, sparse,
+    },
+    util::{
+        id::{PatternID, StateID},
+        matchtypes::{HalfMatch, MatchError},
+        prefilter,
+    },
+};
+
+/// Dispatch the given expression to whichever DFA is inside a `CompactDFA`.
+macro_rules! dispatch {
+    ($compact:expr, $dfa:ident => $expr:expr) => {
+        match *$compact {
+            CompactDFA::Dense(ref $dfa) => $expr,
+            CompactDFA::Sparse(ref $dfa) => $expr,
+        }
+    };
+}
+
+/// Either a dense or a sparse DFA, whichever uses less memory.
+///
+/// This is returned by [`dense::DFA::compact`], which converts a dense DFA
+/// to a sparse DFA only when doing so reduces its memory usage. Either way, the result implements the
+/// [`Automaton`] trait, so callers may search with it without caring about
+/// which representation was chosen.
+///
+/// Note that while sparse DFAs usually use less memory than dense DFAs, they
+/// are also usually slower to search. So a `CompactDFA` is only appropriate
+/// when memory usage matters more than search speed.
+///
+/// # Example
+///
+/// ```
+/// use regex_automata::{
+///     dfa::{dense, Automaton, CompactDFA},
+///     HalfMatch,
+/// };
+///
+/// let dfa: CompactDFA = dense::DFA::new("foo[0-9]+")?.compact()?;
+///
+/// let expected = HalfMatch::must(0, 8);
+/// assert_eq!(Some(expected), dfa.find_leftmost_fwd(b"foo12345")?);
+///
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Clone, Debug)]
+pub enum CompactDFA {
+    /// A dense DFA, used when converting it to a sparse DFA would not reduce
+    /// its memory usage.
+    Dense(dense::OwnedDFA),
+    /// A sparse DFA, used when it is smaller than the corresponding dense
+    /// DFA.
+    Sparse(sparse::DFA<Vec<u8>>),
+}
+
+impl CompactDFA {
+    /// Returns true if and only if this is a sparse DFA.
+    pub fn is_sparse(&self) -> bool {
+        match *self {
+            CompactDFA::Dense(_) => false,
+            CompactDFA::Sparse(_) => true,
+        }
+    }
+
+    /// Returns the memory usage, in bytes, of the DFA inside this value.
+    pub fn memory_usage(&self) -> usize {
+        dispatch!(self, dfa => dfa.memory_usage())
+    }
+}
+
+unsafe impl Automaton for CompactDFA {
+    #[inline]
+    fn next_state(&self, current: StateID, input: u8) -> StateID {
+        dispatch!(self, dfa => dfa.next_state(current, input))
+    }
+
+    #[inline]
+    unsafe fn next_state_unchecked(
+        &self,
+        current: StateID,
+        input: u8,
+    ) -> StateID {
+        dispatch!(self, dfa => dfa.next_state_unchecked(current, input))
+    }
+
+    #[inline]
+    fn next_eoi_state(&self, current: StateID) -> StateID {
+        dispatch!(self, dfa => dfa.next_eoi_state(current))
+    }
+
+    #[inline]
+    fn start_state_forward(
+        &self,
+        pattern_id: Option<PatternID>,
+        bytes: &[u8],
+        start: usize,
+        end: usize,
+    ) -> StateID {
+        dispatch!(self, dfa => {
+            dfa.start_state_forward(pattern_id, bytes, start, end)
+        })
+    }
+
+    #[inline]
+    fn start_state_reverse(
+        &self,
+        pattern_id: Option<PatternID>,
+        bytes: &[u8],
+        start: usize,
+        end: usize,
+    ) -> StateID {
+        dispatch!(self, dfa => {
+            dfa.start_state_reverse(pattern_id, bytes, start, end)
+        })
+    }
+
+    #[inline]
+    fn is_special_state(&self, id: StateID) -> bool {
+        dispatch!(self, dfa => dfa.is_special_state(id))
+    }
+
+    #[inline]
+    fn is_dead_state(&self, id: StateID) -> bool {
+        dispatch!(self, dfa => dfa.is_dead_state(id))
+    }
+
+    #[inline]
+    fn is_quit_state(&self, id: StateID) -> bool {
+        dispatch!(self, dfa => dfa.is_quit_state(id))
+    }
+
+    #[inline]
+    fn is_match_state(&self, id: StateID) -> bool {
+        dispatch!(self, dfa => dfa.is_match_state(id))
+    }
+
+    #[inline]
+    fn is_start_state(&self, id: StateID) -> bool {
+        dispatch!(self, dfa => dfa.is_start_state(id))
+    }
+
+    #[inline]
+    fn is_accel_state(&self, id: StateID) -> bool {
+        dispatch!(self, dfa => dfa.is_accel_state(id))
+    }
+
+    #[inline]
+    fn pattern_count(&self) -> usize {
+        dispatch!(self, dfa => dfa.pattern_count())
+    }
+
+    #[inline]
+    fn match_count(&self, id: StateID) -> usize {
+        dispatch!(self, dfa => dfa.match_count(id))
+    }
+
+    #[inline]
+    fn match_pattern(&self, id: StateID, index: usize) -> PatternID {
+        dispatch!(self, dfa => dfa.match_pattern(id, index))
+    }
+
+    #[inline]
+    fn accelerator(&self, id: StateID) -> &[u8] {
+        dispatch!(self, dfa => dfa.accelerator(id))
+    }
+
+    #[inline]
+    fn is_anchored(&self) -> bool {
+        dispatch!(self, dfa => dfa.is_anchored())
+    }
+
+    // The search routines below are dispatched as a whole, which avoids
+    // matching on the representation for every byte searched.
+
+    #[inline]
+    fn find_earliest_fwd_at(
+        &self,
+        pre: Option<&mut prefilter::Scanner>,
+        pattern_id: Option<PatternID>,
+        bytes: &[u8],
+        start: usize,
+        end: usize,
+    ) -> Result<Option<HalfMatch>, MatchError> {
+        dispatch!(self, dfa => {
+            dfa.find_earliest_fwd_at(pre, pattern_id, bytes, start, end)
+        })
+    }
+
+    #[inline]
+    fn find_earliest_rev_at(
+        &self,
+        pattern_id: Option<PatternID>,
+        bytes: &[u8],
+        start: usize,
+        end: usize,
+    ) -> Result<Option<HalfMatch>, MatchError> {
+        dispatch!(self, dfa => {
+            dfa.find_earliest_rev_at(pattern_id, bytes, start, end)
+        })
+    }
+
+    #[inline]
+    fn find_earliest_rev_at_with_prefilter(
+        &self,
+        pre: Option<&mut prefilter::Scanner>,
+        pattern_id: Option<PatternID>,
+        bytes: &[u8],
+        start: usize,
+        end: usize,
+    ) -> Result<Option<HalfMatch>, MatchError> {
+        dispatch!(self, dfa => {
+            dfa.find_earliest_rev_at_with_prefilter(
+                pre, pattern_id, bytes, start, end,
+            )
+        })
+    }
+
+    #[inline]
+    fn find_leftmost_fwd_at(
+        &self,
+        pre: Option<&mut prefilter::Scanner>,
+        pattern_id: Option<PatternID>,
+        bytes: &[u8],
+        start: usize,
+        end: usize,
+    ) -> Result<Option<HalfMatch>, MatchError> {
+        dispatch!(self, dfa => {
+            dfa.find_leftmost_fwd_at(pre, pattern_id, bytes, start, end)
+        })
+    }
+
+    #[inline]
+    fn find_leftmost_rev_at(
+        &self,
+        pattern_id: Option<PatternID>,
+        bytes: &[u8],
+        start: usize,
+        end: usize,
+    ) -> Result<Option<HalfMatch>, MatchError> {
+        dispatch!(self, dfa => {
+            dfa.find_leftmost_rev_at(pattern_id, bytes, start, end)
+        })
+    }
+
+    #[inline]
+    fn find_leftmost_rev_at_with_prefilter(
+        &self,
+        pre: Option<&mut prefilter::Scanner>,
+        pattern_id: Option<PatternID>,
+        bytes: &[u8],
+        start: usize,
+        end: usize,
+    ) -> Result<Option<HalfMatch>, MatchError> {
+        dispatch!(self, dfa => {
+            dfa.find_leftmost_rev_at_with_prefilter(
+                pre, pattern_id, bytes, start, end,
+            )
+        })
+    }
+
+    #[inline]
+    fn find_overlapping_fwd_at(
+        &self,
+        pre: Option<&mut prefilter::Scanner>,
+        pattern_id: Option<PatternID>,
+        bytes: &[u8],
+        start: usize,
+        end: usize,
+        state: &mut OverlappingState,
+    ) -> Result<Option<HalfMatch>, MatchError> {
+        dispatch!(self, dfa => {
+            dfa.find_overlapping_fwd_at(
+                pre, pattern_id, bytes, start, end, state,
+            )
+        })
+    }
+
+    #[inline]
+    fn find_overlapping_rev_at(
+        &self,
+        pattern_id: Option<PatternID>,
+        bytes: &[u8],
+        start: usize,
+        end: usize,
+        state: &mut OverlappingState,
+    ) -> Result<Option<HalfMatch>, MatchError> {
+        dispatch!(self, dfa => {
+            dfa.find_overlapping_rev_at(pattern_id, bytes, start, end, state)
+        })
+    }
+
+    #[inline]
+    fn find_fwd_anchored_end_at(
+        &self,
+        pattern_id: Option<PatternID>,
+        bytes: &[u8],
+        start: usize,
+        end: usize,
+    ) -> Result<Option<HalfMatch>, MatchError> {
+        dispatch!(self, dfa => {
+            dfa.find_fwd_anchored_end_at(pattern_id, bytes, start, end)
+        })
+    }
+}
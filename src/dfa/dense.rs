@@ -22,9 +22,9 @@ use alloc::{
 use crate::{
     dfa::{
         accel::Accel, determinize, error::Error, minimize::Minimizer, sparse,
+        CompactDFA,
     },
     nfa::thompson,
-    util::alphabet::ByteSet,
     MatchKind,
 };
 use crate::{
@@ -35,7 +35,7 @@ use crate::{
         DEAD,
     },
     util::{
-        alphabet::{self, ByteClasses},
+        alphabet::{self, ByteClasses, ByteSet},
         bytes::{self, DeserializeError, Endian, SerializeError},
         id::{PatternID, StateID},
         start::Start,
@@ -49,7 +49,10 @@ const LABEL: &str = "rust-regex-automata-dfa-dense";
 /// change occurs. A change may not necessarily be a breaking change, but the
 /// version does permit good error messages in the case where a breaking change
 /// is made.
-const VERSION: u32 = 2;
+const VERSION: u32 = 3;
+
+/// The bit set in the serialized flags of a DFA when it is anchored.
+const FLAG_ANCHORED: u32 = 1 << 0;
 
 /// The configuration used for compiling a dense DFA.
 ///
@@ -78,6 +81,7 @@ pub struct Config {
     match_kind: Option<MatchKind>,
     starts_for_each_pattern: Option<bool>,
     byte_classes: Option<bool>,
+    byte_classes_from: Option<ByteClasses>,
     unicode_word_boundary: Option<bool>,
     quit: Option<ByteSet>,
     dfa_size_limit: Option<Option<usize>>,
@@ -468,6 +472,55 @@ impl Config {
         self
     }
 
+    /// Force the DFA to use the given map from bytes to equivalence classes.
+    ///
+    /// Normally, the equivalence classes of a DFA are computed from the
+    /// regex it is built from. This means that two DFAs built from different
+    /// regexes will generally use different equivalence classes. This option
+    /// permits building several DFAs that all use the same byte class map,
+    /// which in turn means they all decode input bytes identically. A map
+    /// can be extracted from an existing DFA via [`DFA::byte_classes`].
+    ///
+    /// When set, this takes precedence over [`Config::byte_classes`].
+    ///
+    /// The given map must distinguish every pair of bytes that the DFA needs
+    /// to distinguish. If it doesn't, then building the DFA returns an error.
+    /// A map that puts every byte in its own class, such as the one used when
+    /// [`Config::byte_classes`] is disabled, can always be used.
+    ///
+    /// # Example
+    ///
+    /// This example shows how to build two DFAs that share a byte class map.
+    ///
+    /// ```
+    /// use regex_automata::dfa::dense;
+    ///
+    /// let dfa1 = dense::DFA::new("[a-z]+[0-9]")?;
+    /// let classes = *dfa1.byte_classes();
+    /// let dfa2 = dense::Builder::new()
+    ///     .configure(dense::Config::new().byte_classes_from(classes))
+    ///     .build("[0-9]")?;
+    /// for b in 0..=255 {
+    ///     assert_eq!(
+    ///         dfa1.byte_classes().get(b),
+    ///         dfa2.byte_classes().get(b),
+    ///     );
+    /// }
+    ///
+    /// // A DFA for '[A-Z]' can't use this map, since it doesn't distinguish
+    /// // between uppercase letters and other bytes.
+    /// assert!(dense::Builder::new()
+    ///     .configure(dense::Config::new().byte_classes_from(classes))
+    ///     .build("[A-Z]")
+    ///     .is_err());
+    ///
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn byte_classes_from(mut self, classes: ByteClasses) -> Config {
+        self.byte_classes_from = Some(classes);
+        self
+    }
+
     /// Heuristically enable Unicode word boundaries.
     ///
     /// When set, this will attempt to implement Unicode word boundaries as if
@@ -771,6 +824,12 @@ impl Config {
         self.byte_classes.unwrap_or(true)
     }
 
+    /// Returns the byte class map that this configuration forces the DFA to
+    /// use, if one was set.
+    pub fn get_byte_classes_from(&self) -> Option<ByteClasses> {
+        self.byte_classes_from
+    }
+
     /// Returns whether this configuration has enabled heuristic Unicode word
     /// boundary support. When enabled, it is possible for a search to return
     /// an error.
@@ -824,6 +883,7 @@ impl Config {
                 .starts_for_each_pattern
                 .or(self.starts_for_each_pattern),
             byte_classes: o.byte_classes.or(self.byte_classes),
+            byte_classes_from: o.byte_classes_from.or(self.byte_classes_from),
             unicode_word_boundary: o
                 .unicode_word_boundary
                 .or(self.unicode_word_boundary),
@@ -978,28 +1038,43 @@ impl Builder {
                 quit.add(b);
             }
         }
-        let classes = if !self.config.get_byte_classes() {
-            // DFAs will always use the equivalence class map, but enabling
+        let mut set = nfa.byte_class_set().clone();
+        // It is important to distinguish any "quit" bytes from all other
+        // bytes. Otherwise, a non-quit byte may end up in the same class as a
+        // quit byte, and thus cause the DFA stop when it shouldn't.
+        if !quit.is_empty() {
+            set.add_set(&quit);
+        }
+        let classes = match self.config.get_byte_classes_from() {
+            Some(classes) => {
+                // A forced map may have been built by hand, so we need to
+                // check that it's both valid and distinguishes every byte
+                // that our own map would distinguish.
+                if !classes.is_valid() {
+                    return Err(Error::invalid_byte_classes());
+                }
+                if !classes.is_refinement_of(&set.byte_classes()) {
+                    return Err(Error::insufficient_byte_classes());
+                }
+                classes
+            }
+            // DFAs will always use the equivalence class map, but disabling
             // this option is useful for debugging. Namely, this will cause all
             // transitions to be defined over their actual bytes instead of an
             // opaque equivalence class identifier. The former is much easier
             // to grok as a human.
-            ByteClasses::singletons()
-        } else {
-            let mut set = nfa.byte_class_set().clone();
-            // It is important to distinguish any "quit" bytes from all other
-            // bytes. Otherwise, a non-quit byte may end up in the same class
-            // as a quit byte, and thus cause the DFA stop when it shouldn't.
-            if !quit.is_empty() {
-                set.add_set(&quit);
+            None if !self.config.get_byte_classes() => {
+                ByteClasses::singletons()
             }
-            set.byte_classes()
+            None => set.byte_classes(),
         };
 
         let mut dfa = DFA::initial(
             classes,
             nfa.pattern_len(),
             self.config.get_starts_for_each_pattern(),
+            quit,
+            self.config.get_anchored(),
         )?;
         determinize::Config::new()
             .anchored(self.config.get_anchored())
@@ -1160,6 +1235,16 @@ pub struct DFA<T> {
     /// transition table. See dfa/special.rs for more details on how states are
     /// arranged.
     accels: Accels<T>,
+    /// The set of bytes that cause this DFA to enter its quit state.
+    ///
+    /// This isn't needed for searching, since the transitions themselves
+    /// already encode it. It is kept around (and serialized) so that callers
+    /// can report which bytes caused a search to fail, even for DFAs that have
+    /// been deserialized.
+    quitset: ByteSet,
+    /// Whether this DFA was built with anchored start states. When it was,
+    /// search routines must never use a prefilter to skip ahead.
+    anchored: bool,
 }
 
 #[cfg(feature = "alloc")]
@@ -1243,6 +1328,100 @@ impl OwnedDFA {
         Builder::new().build_from_nfa(&nfa)
     }
 
+    /// Rebuild a dense DFA from the given sparse DFA.
+    ///
+    /// This is the inverse of [`DFA::to_sparse`]. It is useful when only the
+    /// sparse form of a DFA was kept (for example, because it is smaller
+    /// when serialized) but the faster search speed of a dense DFA is
+    /// needed. The DFA returned matches precisely the same inputs as the
+    /// sparse DFA given, including any accelerators, quit bytes and
+    /// anchored starting states for each pattern.
+    ///
+    /// This returns an error if the dense DFA would have too many states.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use regex_automata::{dfa::{Automaton, dense, sparse}, HalfMatch};
+    ///
+    /// let bytes = sparse::DFA::new("foo[0-9]+")?.to_bytes_native_endian();
+    /// let sparse: sparse::DFA<&[u8]> = sparse::DFA::from_bytes(&bytes)?.0;
+    /// let dense = dense::DFA::from_sparse(&sparse)?;
+    ///
+    /// let expected = HalfMatch::must(0, 8);
+    /// assert_eq!(Some(expected), dense.find_leftmost_fwd(b"foo12345")?);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn from_sparse<T: AsRef<[u8]>>(
+        sparse: &sparse::DFA<T>,
+    ) -> Result<OwnedDFA, Error> {
+        let classes = sparse.byte_classes().clone();
+        let mut dfa = OwnedDFA::initial(
+            classes.clone(),
+            sparse.pattern_count(),
+            sparse.has_starts_for_each_pattern(),
+            sparse.quit_bytes(),
+            sparse.is_anchored(),
+        )?;
+        // A sparse DFA is built by converting the states of a dense DFA in
+        // order, so its states are already arranged the way a dense DFA
+        // expects its special states to be arranged. Thus, we only need to
+        // add one dense state for each sparse state, in the same order. The
+        // first two states are always the dead and quit states, which the
+        // initial DFA already has.
+        let sparse_ids: Vec<StateID> = sparse.state_ids().collect();
+        for _ in 2..sparse_ids.len() {
+            dfa.add_empty_state()?;
+        }
+        let dense_ids: Vec<StateID> =
+            (0..sparse_ids.len()).map(|i| dfa.from_index(i)).collect();
+        let remap = |id: StateID| -> StateID {
+            dense_ids[sparse_ids.binary_search(&id).unwrap()]
+        };
+
+        let mut matches = BTreeMap::new();
+        let mut accels = Accels::empty();
+        for (&sparse_id, &id) in sparse_ids.iter().zip(dense_ids.iter()) {
+            for unit in classes.representatives() {
+                let next = match unit.as_u8() {
+                    Some(byte) => sparse.next_state(sparse_id, byte),
+                    None => sparse.next_eoi_state(sparse_id),
+                };
+                dfa.set_transition(id, unit, remap(next));
+            }
+            if sparse.is_match_state(sparse_id) {
+                let pids = (0..sparse.match_count(sparse_id))
+                    .map(|i| sparse.match_pattern(sparse_id, i))
+                    .collect();
+                matches.insert(id, pids);
+            }
+            // Accelerators are stored in the same order as the accelerated
+            // states they belong to. A sparse DFA built without accelerators
+            // may still have accelerated states, but their accelerators are
+            // empty, which searches treat as "no acceleration."
+            if sparse.is_accel_state(sparse_id) {
+                let mut accel = Accel::new();
+                for &byte in sparse.accelerator(sparse_id) {
+                    accel.add(byte);
+                }
+                accels.add(accel);
+            }
+        }
+        for (sparse_id, start, pid) in sparse.starts() {
+            dfa.set_start_state(start, pid, remap(sparse_id));
+        }
+        dfa.set_pattern_map(&matches)?;
+        *dfa.special_mut() = sparse.special().remap(remap);
+        dfa.accels = accels;
+        trace!(
+            "created dense DFA from sparse DFA, memory usage: {} \
+             (sparse memory usage: {})",
+            dfa.memory_usage(),
+            sparse.memory_usage(),
+        );
+        Ok(dfa)
+    }
+
     /// Create an initial DFA with the given equivalence classes, pattern count
     /// and whether anchored starting states are enabled for each pattern. An
     /// initial DFA can be further mutated via determinization.
@@ -1250,6 +1429,8 @@ impl OwnedDFA {
         classes: ByteClasses,
         pattern_count: usize,
         starts_for_each_pattern: bool,
+        quitset: ByteSet,
+        anchored: bool,
     ) -> Result<OwnedDFA, Error> {
         let start_pattern_count =
             if starts_for_each_pattern { pattern_count } else { 0 };
@@ -1259,6 +1440,8 @@ impl OwnedDFA {
             ms: MatchStates::empty(pattern_count),
             special: Special::new(),
             accels: Accels::empty(),
+            quitset,
+            anchored,
         })
     }
 }
@@ -1273,6 +1456,8 @@ impl<T: AsRef<[u32]>> DFA<T> {
             ms: self.ms.as_ref(),
             special: self.special,
             accels: self.accels(),
+            quitset: self.quitset,
+            anchored: self.anchored,
         }
     }
 
@@ -1289,6 +1474,8 @@ impl<T: AsRef<[u32]>> DFA<T> {
             ms: self.ms.to_owned(),
             special: self.special,
             accels: self.accels().to_owned(),
+            quitset: self.quitset,
+            anchored: self.anchored,
         }
     }
 
@@ -1305,6 +1492,35 @@ impl<T: AsRef<[u32]>> DFA<T> {
         self.st.patterns > 0
     }
 
+    /// Returns the set of bytes that cause this DFA to enter a quit state.
+    ///
+    /// This includes any bytes added via [`Config::quit`], along with all
+    /// non-ASCII bytes if they were added to support heuristic Unicode word
+    /// boundaries. When a search returns a [`MatchError::Quit`] error, the
+    /// byte reported is always in this set.
+    ///
+    /// The quit set is preserved by serialization, and is also carried over
+    /// when converting to a sparse DFA.
+    ///
+    /// [`MatchError::Quit`]: crate::MatchError::Quit
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use regex_automata::dfa::dense;
+    ///
+    /// let dfa = dense::Builder::new()
+    ///     .configure(dense::Config::new().quit(b'\n', true))
+    ///     .build(r"foo\w+")?;
+    /// let quit = dfa.quit_bytes();
+    /// assert!(quit.contains(b'\n'));
+    /// assert!(!quit.contains(b'f'));
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn quit_bytes(&self) -> ByteSet {
+        self.quitset
+    }
+
     /// Returns the total number of elements in the alphabet for this DFA.
     ///
     /// That is, this returns the total number of transitions that each state
@@ -1428,7 +1644,84 @@ impl<T: AsRef<[u32]>> DFA<T> {
     /// ```
     #[cfg(feature = "alloc")]
     pub fn to_sparse(&self) -> Result<sparse::DFA<Vec<u8>>, Error> {
-        sparse::DFA::from_dense(self)
+        self.to_sparse_with_config(sparse::Config::default())
+    }
+
+    /// Convert this dense DFA to a sparse DFA using the given configuration.
+    ///
+    /// This is like [`DFA::to_sparse`], except it permits controlling what
+    /// is carried over into the sparse DFA. For example, dropping
+    /// accelerators makes the sparse DFA smaller without changing its
+    /// search results.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use regex_automata::{dfa::{Automaton, dense, sparse}, HalfMatch};
+    ///
+    /// let dense = dense::DFA::new("foo[0-9]+")?;
+    /// let with = dense.to_sparse()?;
+    /// let without = dense.to_sparse_with_config(
+    ///     sparse::Config::new().accelerate(false),
+    /// )?;
+    /// assert!(without.write_to_len() <= with.write_to_len());
+    ///
+    /// let expected = HalfMatch::must(0, 8);
+    /// assert_eq!(Some(expected), without.find_leftmost_fwd(b"foo12345")?);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    #[cfg(feature = "alloc")]
+    pub fn to_sparse_with_config(
+        &self,
+        config: sparse::Config,
+    ) -> Result<sparse::DFA<Vec<u8>>, Error> {
+        sparse::DFA::from_dense(self, config)
+    }
+
+    /// Convert this dense DFA to whichever of a dense or sparse DFA uses less
+    /// memory.
+    ///
+    /// The [memory usage](sparse::DFA::memory_usage) of the sparse DFA that
+    /// [`DFA::to_sparse`] would build is computed by visiting every
+    /// transition in this DFA, which takes time proportional to the size of
+    /// this DFA's transition table but does not allocate. When the sparse DFA
+    /// would be smaller than this DFA's [memory usage](DFA::memory_usage), it
+    /// is built and returned. Otherwise, including when both would use the
+    /// same amount of memory, a copy of this dense DFA is returned, since
+    /// dense DFAs are faster to search. Either way, the result implements the
+    /// [`Automaton`] trait.
+    ///
+    /// This may return an error in the same circumstances as
+    /// [`DFA::to_sparse`].
+    ///
+    /// # Example
+    ///
+    /// Large Unicode classes tend to produce DFAs that are much smaller in
+    /// their sparse form:
+    ///
+    /// ```
+    /// use regex_automata::{dfa::{dense, Automaton}, HalfMatch};
+    ///
+    /// let dense = dense::DFA::new(r"\w{10}")?;
+    /// let dfa = dense.compact()?;
+    /// assert!(dfa.is_sparse());
+    /// assert!(dfa.memory_usage() < dense.memory_usage());
+    ///
+    /// let expected = HalfMatch::must(0, 10);
+    /// assert_eq!(Some(expected), dfa.find_leftmost_fwd(b"abcdefghij")?);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    #[cfg(feature = "alloc")]
+    pub fn compact(&self) -> Result<CompactDFA, Error> {
+        let sparse_size = sparse::DFA::from_dense_memory_usage(
+            self,
+            sparse::Config::default(),
+        );
+        if sparse_size < self.memory_usage() {
+            Ok(CompactDFA::Sparse(self.to_sparse()?))
+        } else {
+            Ok(CompactDFA::Dense(self.to_owned()))
+        }
     }
 
     /// Serialize this DFA as raw bytes to a `Vec<u8>` in little endian
@@ -1779,12 +2072,13 @@ impl<T: AsRef<[u32]>> DFA<T> {
         bytes::write_label_len(LABEL)
         + bytes::write_endianness_check_len()
         + bytes::write_version_len()
-        + size_of::<u32>() // unused, intended for future flexibility
+        + size_of::<u32>() // flags
         + self.tt.write_to_len()
         + self.st.write_to_len()
         + self.ms.write_to_len()
         + self.special.write_to_len()
         + self.accels.write_to_len()
+        + self.quitset.write_to_len()
     }
 }
 
@@ -2038,8 +2332,13 @@ impl<'a> DFA<&'a [u32]> {
         nr += bytes::read_endianness_check(&slice[nr..])?;
         nr += bytes::read_version(&slice[nr..], VERSION)?;
 
-        let _unused = bytes::try_read_u32(&slice[nr..], "unused space")?;
-        nr += size_of::<u32>();
+        // Only the lowest bit is currently used, and it indicates whether the
+        // DFA is anchored. The remaining bits are intended for future
+        // flexibility. DFAs serialized before this field was used always
+        // wrote zero, which corresponds to an unanchored DFA.
+        let (flags, nread) = bytes::try_read_u32(&slice[nr..], "flags")?;
+        nr += nread;
+        let anchored = flags & FLAG_ANCHORED != 0;
 
         let (tt, nread) = TransitionTable::from_bytes_unchecked(&slice[nr..])?;
         nr += nread;
@@ -2057,7 +2356,10 @@ impl<'a> DFA<&'a [u32]> {
         let (accels, nread) = Accels::from_bytes_unchecked(&slice[nr..])?;
         nr += nread;
 
-        Ok((DFA { tt, st, ms, special, accels }, nr))
+        let (quitset, nread) = ByteSet::from_bytes(&slice[nr..])?;
+        nr += nread;
+
+        Ok((DFA { tt, st, ms, special, accels, quitset, anchored }, nr))
     }
 
     /// The implementation of the public `write_to` serialization methods,
@@ -2079,8 +2381,8 @@ impl<'a> DFA<&'a [u32]> {
         nw += bytes::write_endianness_check::<E>(&mut dst[nw..])?;
         nw += bytes::write_version::<E>(VERSION, &mut dst[nw..])?;
         nw += {
-            // Currently unused, intended for future flexibility
-            E::write_u32(0, &mut dst[nw..]);
+            let flags = if self.anchored { FLAG_ANCHORED } else { 0 };
+            E::write_u32(flags, &mut dst[nw..]);
             size_of::<u32>()
         };
         nw += self.tt.write_to::<E>(&mut dst[nw..])?;
@@ -2088,6 +2390,7 @@ impl<'a> DFA<&'a [u32]> {
         nw += self.ms.write_to::<E>(&mut dst[nw..])?;
         nw += self.special.write_to::<E>(&mut dst[nw..])?;
         nw += self.accels.write_to::<E>(&mut dst[nw..])?;
+        nw += self.quitset.write_to(&mut dst[nw..])?;
         Ok(nw)
     }
 }
@@ -2502,7 +2805,11 @@ impl OwnedDFA {
 /// A variety of generic internal methods for accessing DFA internals.
 impl<T: AsRef<[u32]>> DFA<T> {
     /// Return the byte classes used by this DFA.
-    pub(crate) fn byte_classes(&self) -> &ByteClasses {
+    ///
+    /// The map returned can be given to
+    /// [`Config::byte_classes_from`] in order to build other DFAs that use
+    /// the same equivalence classes as this one.
+    pub fn byte_classes(&self) -> &ByteClasses {
         &self.tt.classes
     }
 
@@ -2799,6 +3106,11 @@ unsafe impl<T: AsRef<[u32]>> Automaton for DFA<T> {
         }
         self.accels.needles(self.accelerator_index(id))
     }
+
+    #[inline]
+    fn is_anchored(&self) -> bool {
+        self.anchored
+    }
 }
 
 /// The transition table portion of a dense DFA.
@@ -3593,8 +3905,19 @@ impl<T: AsRef<[u32]>> StartTable<T> {
         let index = match pattern_id {
             None => start_index,
             Some(pid) => {
+                assert!(
+                    self.patterns > 0,
+                    "attempted to search for a specific pattern \
+                     without enabling starts_for_each_pattern",
+                );
                 let pid = pid.as_usize();
-                assert!(pid < self.patterns, "invalid pattern ID {:?}", pid);
+                assert!(
+                    pid < self.patterns,
+                    "invalid pattern ID {}; regex has {} pattern{}",
+                    pid,
+                    self.patterns,
+                    if self.patterns == 1 { "" } else { "s" },
+                );
                 self.stride + (self.stride * pid) + start_index
             }
         };
@@ -9,12 +9,15 @@ use crate::{
 /// An error that occurred during the construction of a DFA.
 ///
 /// This error does not provide many introspection capabilities. There are
-/// generally only two things you can do with it:
+/// generally only a few things you can do with it:
 ///
 /// * Obtain a human readable message via its `std::fmt::Display` impl.
 /// * Access an underlying [`nfa::thompson::Error`] type from its `source`
 /// method via the `std::error::Error` trait. This error only occurs when using
 /// convenience routines for building a DFA directly from a pattern string.
+/// * Determine whether the error occurred because a pattern could not be
+/// parsed ([`Error::is_syntax_error`]) or because a configured size limit was
+/// exceeded ([`Error::is_size_limit`] and [`Error::size_limit`]).
 ///
 /// When the `std` feature is enabled, this implements the `std::error::Error`
 /// trait.
@@ -59,9 +62,41 @@ enum ErrorKind {
     /// An error that occurs if auxiliary storage (not the DFA) used during
     /// determinization got too big.
     DeterminizeExceededSizeLimit { limit: usize },
+    /// An error that occurs when the byte classes given via
+    /// [`dense::Config::byte_classes_from`](dense/struct.Config.html#method.byte_classes_from)
+    /// cannot be used. The message string describes why.
+    ByteClasses(&'static str),
 }
 
 impl Error {
+    /// Returns true if and only if this error occurred because a pattern
+    /// could not be parsed.
+    pub fn is_syntax_error(&self) -> bool {
+        match self.kind() {
+            ErrorKind::NFA(ref err) => err.is_syntax_error(),
+            _ => false,
+        }
+    }
+
+    /// Returns true if and only if this error occurred because a configured
+    /// size limit was exceeded. This includes the size limits on the NFA,
+    /// the DFA and the auxiliary storage used during determinization.
+    pub fn is_size_limit(&self) -> bool {
+        self.size_limit().is_some()
+    }
+
+    /// Returns the size limit, in bytes, that was exceeded if this error
+    /// occurred because a configured size limit was exceeded. Otherwise, this
+    /// returns `None`.
+    pub fn size_limit(&self) -> Option<usize> {
+        match *self.kind() {
+            ErrorKind::NFA(ref err) => err.size_limit(),
+            ErrorKind::DFAExceededSizeLimit { limit } => Some(limit),
+            ErrorKind::DeterminizeExceededSizeLimit { limit } => Some(limit),
+            _ => None,
+        }
+    }
+
     /// Return the kind of this error.
     fn kind(&self) -> &ErrorKind {
         &self.kind
@@ -98,6 +133,18 @@ impl Error {
     pub(crate) fn determinize_exceeded_size_limit(limit: usize) -> Error {
         Error { kind: ErrorKind::DeterminizeExceededSizeLimit { limit } }
     }
+
+    pub(crate) fn invalid_byte_classes() -> Error {
+        let msg = "the byte class map is invalid since it contains an \
+                   equivalence class greater than its alphabet length";
+        Error { kind: ErrorKind::ByteClasses(msg) }
+    }
+
+    pub(crate) fn insufficient_byte_classes() -> Error {
+        let msg = "the byte class map puts bytes that must be \
+                   distinguished by the DFA into the same equivalence class";
+        Error { kind: ErrorKind::ByteClasses(msg) }
+    }
 }
 
 #[cfg(feature = "std")]
@@ -111,6 +158,7 @@ impl std::error::Error for Error {
             ErrorKind::TooManyMatchPatternIDs => None,
             ErrorKind::DFAExceededSizeLimit { .. } => None,
             ErrorKind::DeterminizeExceededSizeLimit { .. } => None,
+            ErrorKind::ByteClasses(_) => None,
         }
     }
 }
@@ -157,6 +205,9 @@ impl core::fmt::Display for Error {
             ErrorKind::DeterminizeExceededSizeLimit { limit } => {
                 write!(f, "determinization exceeded size limit of {:?}", limit)
             }
+            ErrorKind::ByteClasses(ref msg) => {
+                write!(f, "cannot use forced byte classes: {}", msg)
+            }
         }
     }
 }
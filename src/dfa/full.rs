@@ -0,0 +1,162 @@
+use crate::{
+    dfa::automaton::Automaton,
+    util::matchtypes::{MatchError, MultiMatch},
+};
+
+/// An iterator over all non-overlapping leftmost matches, with both start
+/// and end offsets, found by pairing a forward DFA with a reverse DFA.
+///
+/// A single DFA search only reports where a match ends. To find where it
+/// starts, a second DFA compiled from the same patterns, but in reverse, is
+/// run backwards from the end of the match. This iterator packages that
+/// technique up so that it works with any pair of automata, for example, a
+/// forward dense DFA along with a reverse sparse DFA. This is the same search
+/// that a [`Regex`](crate::dfa::regex::Regex) executes, but without requiring
+/// the two DFAs to be combined into a regex first.
+///
+/// The forward DFA should be unanchored (unless only anchored matches are
+/// desired) and use leftmost-first match semantics. The reverse DFA must be
+/// built from the same patterns with
+/// [`thompson::Config::reverse`](crate::nfa::thompson::Config::reverse)
+/// enabled, and should be anchored and use [`MatchKind::All`] semantics.
+/// These are the same settings that a `Regex` uses for its DFAs. If the two
+/// DFAs do not agree, then the matches reported are unspecified, and this
+/// iterator may panic.
+///
+/// Empty matches are handled in the same way as a `Regex` with its
+/// [`utf8`](crate::dfa::regex::Config::utf8) option enabled: an empty match
+/// that immediately follows another match is skipped, and after an empty
+/// match, the next search begins at the next UTF-8 encoded codepoint. This
+/// may be disabled with [`FullMatches::utf8`].
+///
+/// This iterator yields an error whenever either DFA's search fails. When an
+/// error is yielded, callers should stop iterating.
+///
+/// [`MatchKind::All`]: crate::MatchKind::All
+///
+/// # Example
+///
+/// ```
+/// use regex_automata::{
+///     dfa::{dense, FullMatches},
+///     nfa::thompson,
+///     MatchKind, MultiMatch,
+/// };
+///
+/// let fwd = dense::DFA::new(r"foo\d+")?;
+/// let rev = dense::Builder::new()
+///     .configure(
+///         dense::Config::new().anchored(true).match_kind(MatchKind::All),
+///     )
+///     .thompson(thompson::Config::new().reverse(true))
+///     .build(r"foo\d+")?;
+///
+/// let haystack = b"xfoo123 foo9 foo";
+/// let matches: Vec<MultiMatch> = FullMatches::new(&fwd, &rev, haystack)
+///     .collect::<Result<_, _>>()?;
+/// assert_eq!(matches, vec![
+///     MultiMatch::must(0, 1, 7),
+///     MultiMatch::must(0, 8, 12),
+/// ]);
+///
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Clone, Debug)]
+pub struct FullMatches<'t, F, R> {
+    fwd: F,
+    rev: R,
+    utf8: bool,
+    text: &'t [u8],
+    last_end: usize,
+    last_match: Option<usize>,
+}
+
+impl<'t, F: Automaton, R: Automaton> FullMatches<'t, F, R> {
+    /// Create a new iterator over all matches in `text`, using `fwd` to find
+    /// the end of each match and `rev` to find its start.
+    ///
+    /// Since [`Automaton`] is implemented for references to automata, the
+    /// DFAs given may be borrowed.
+    pub fn new(fwd: F, rev: R, text: &'t [u8]) -> FullMatches<'t, F, R> {
+        FullMatches {
+            fwd,
+            rev,
+            utf8: true,
+            text,
+            last_end: 0,
+            last_match: None,
+        }
+    }
+
+    /// Whether to advance by a full UTF-8 encoded codepoint after an empty
+    /// match, instead of a single byte. This is enabled by default.
+    ///
+    /// When enabled, empty matches are never reported in the middle of a
+    /// codepoint, assuming the haystack is valid UTF-8. When disabled, an
+    /// empty match may be reported at every byte offset.
+    pub fn utf8(mut self, yes: bool) -> FullMatches<'t, F, R> {
+        self.utf8 = yes;
+        self
+    }
+
+    /// Find the leftmost match beginning the search at `start`, or `None` if
+    /// no match exists.
+    fn find_at(&self, start: usize) -> Result<Option<MultiMatch>, MatchError> {
+        let end = self.text.len();
+        let end = match self
+            .fwd
+            .find_leftmost_fwd_at(None, None, self.text, start, end)?
+        {
+            None => return Ok(None),
+            Some(end) => end,
+        };
+        // As with a Regex, the reverse search doesn't need to be told which
+        // pattern to look for, since in the leftmost case it always finds a
+        // start for the same pattern that the forward search found.
+        let start = self
+            .rev
+            .find_leftmost_rev_at(None, self.text, start, end.offset())?
+            .expect("reverse search must match if forward search does");
+        assert_eq!(
+            start.pattern(),
+            end.pattern(),
+            "forward and reverse search must match same pattern",
+        );
+        assert!(start.offset() <= end.offset());
+        Ok(Some(MultiMatch::new(end.pattern(), start.offset(), end.offset())))
+    }
+}
+
+impl<'t, F: Automaton, R: Automaton> Iterator for FullMatches<'t, F, R> {
+    type Item = Result<MultiMatch, MatchError>;
+
+    fn next(&mut self) -> Option<Result<MultiMatch, MatchError>> {
+        if self.last_end > self.text.len() {
+            return None;
+        }
+        let m = match self.find_at(self.last_end) {
+            Err(err) => return Some(Err(err)),
+            Ok(None) => return None,
+            Ok(Some(m)) => m,
+        };
+        if m.is_empty() {
+            // This is an empty match. To ensure we make progress, start
+            // the next search at the smallest possible starting position
+            // of the next match following this one.
+            self.last_end = if self.utf8 {
+                crate::util::next_utf8(self.text, m.end())
+            } else {
+                m.end() + 1
+            };
+            // Don't accept empty matches immediately following a match.
+            // Just move on to the next match.
+            if Some(m.end()) == self.last_match {
+                return self.next();
+            }
+        } else {
+            self.last_end = m.end();
+        }
+        self.last_match = Some(m.end());
+        Some(Ok(m))
+    }
+}
@@ -334,9 +334,15 @@ via [`dense::Config::minimize`], but it can increase compilation times
 dramatically.
 */
 
-pub use crate::dfa::automaton::{Automaton, OverlappingState};
+pub use crate::dfa::accel::accelerate;
+pub use crate::dfa::automaton::{Automaton, HalfMatches, OverlappingState};
+#[cfg(feature = "alloc")]
+pub use crate::dfa::compact::CompactDFA;
 #[cfg(feature = "alloc")]
 pub use crate::dfa::error::Error;
+pub use crate::dfa::full::FullMatches;
+pub use crate::dfa::quit::QuitBytes;
+pub use crate::dfa::stream::StreamSearcher;
 
 /// This is an alias for a state ID of zero. It has special significance
 /// because it always corresponds to the first state in a DFA, and the first
@@ -348,16 +354,21 @@ const DEAD: crate::util::id::StateID = crate::util::id::StateID::ZERO;
 
 mod accel;
 mod automaton;
+#[cfg(feature = "alloc")]
+mod compact;
 pub mod dense;
 #[cfg(feature = "alloc")]
 mod determinize;
 #[cfg(feature = "alloc")]
 pub(crate) mod error;
+mod full;
 #[cfg(feature = "alloc")]
 mod minimize;
+mod quit;
 pub mod regex;
 mod search;
 pub mod sparse;
 mod special;
+mod stream;
 #[cfg(feature = "transducer")]
 mod transducer;
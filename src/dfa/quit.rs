@@ -0,0 +1,209 @@
+use crate::{
+    dfa::automaton::Automaton,
+    util::{
+        alphabet::ByteSet,
+        id::{PatternID, StateID},
+    },
+};
+
+/// The sentinel state ID used to represent a quit state that was entered
+/// because of a quit byte given at search time.
+///
+/// No DFA in this crate can actually produce this ID, since doing so would
+/// require a transition table whose size exceeds the maximum state ID.
+const QUIT: StateID = StateID::MAX;
+
+/// A DFA wrapper that adds "quit" bytes at search time.
+///
+/// Quit bytes are normally baked into a DFA when it is built via
+/// [`dense::Config::quit`](crate::dfa::dense::Config::quit). Changing them
+/// then requires rebuilding the DFA, which can be costly. This wrapper
+/// instead checks every byte against a set of quit bytes while searching, and
+/// behaves as if the wrapped DFA entered a quit state whenever one of them is
+/// seen. That is, searches will return a
+/// [`MatchError::Quit`](crate::MatchError::Quit) error at the
+/// offset of the first quit byte observed, unless a match was already found
+/// before it.
+///
+/// The quit bytes given here are in addition to any quit bytes that the
+/// wrapped DFA was built with.
+///
+/// This trades a small per-byte check for the flexibility of not needing to
+/// rebuild the DFA. Moreover, since acceleration could otherwise skip over
+/// quit bytes, searches using this wrapper never use accelerated states.
+///
+/// This wrapper implements the [`Automaton`] trait, so all of its search
+/// routines are available. It can be built from any automaton, but
+/// [`sparse::DFA::with_quit_bytes`](crate::dfa::sparse::DFA::with_quit_bytes)
+/// is a convenient way of building one that borrows an existing DFA.
+///
+/// # Example
+///
+/// This example shows how to stop a search on a newline without rebuilding
+/// the DFA.
+///
+/// ```
+/// use regex_automata::{
+///     dfa::{Automaton, dense},
+///     util::alphabet::ByteSet,
+///     HalfMatch, MatchError,
+/// };
+///
+/// let dfa = dense::DFA::new("foo[a-z]+")?.to_sparse()?;
+/// let haystack = b"xyz\nfoobar";
+/// assert_eq!(
+///     Ok(Some(HalfMatch::must(0, 10))),
+///     dfa.find_leftmost_fwd(haystack),
+/// );
+///
+/// let mut quit = ByteSet::empty();
+/// quit.add(b'\n');
+/// let dfa = dfa.with_quit_bytes(quit);
+/// assert_eq!(
+///     Err(MatchError::Quit { byte: b'\n', offset: 3 }),
+///     dfa.find_leftmost_fwd(haystack),
+/// );
+///
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Clone, Debug)]
+pub struct QuitBytes<A> {
+    dfa: A,
+    quit: ByteSet,
+}
+
+impl<A: Automaton> QuitBytes<A> {
+    /// Wrap the given automaton such that searches quit whenever any of the
+    /// given bytes are seen.
+    pub fn new(dfa: A, quit: ByteSet) -> QuitBytes<A> {
+        QuitBytes { dfa, quit }
+    }
+
+    /// Return the wrapped automaton.
+    pub fn get_ref(&self) -> &A {
+        &self.dfa
+    }
+
+    /// Return the set of bytes that cause a search to quit, in addition to
+    /// any quit bytes in the wrapped automaton.
+    pub fn quit_bytes(&self) -> &ByteSet {
+        &self.quit
+    }
+
+    /// Consume this wrapper and return the wrapped automaton.
+    pub fn into_inner(self) -> A {
+        self.dfa
+    }
+}
+
+unsafe impl<A: Automaton> Automaton for QuitBytes<A> {
+    #[inline]
+    fn next_state(&self, current: StateID, input: u8) -> StateID {
+        if current == QUIT || self.quit.contains(input) {
+            return QUIT;
+        }
+        self.dfa.next_state(current, input)
+    }
+
+    #[inline]
+    unsafe fn next_state_unchecked(
+        &self,
+        current: StateID,
+        input: u8,
+    ) -> StateID {
+        if current == QUIT || self.quit.contains(input) {
+            return QUIT;
+        }
+        self.dfa.next_state_unchecked(current, input)
+    }
+
+    #[inline]
+    fn next_eoi_state(&self, current: StateID) -> StateID {
+        if current == QUIT {
+            return QUIT;
+        }
+        self.dfa.next_eoi_state(current)
+    }
+
+    #[inline]
+    fn start_state_forward(
+        &self,
+        pattern_id: Option<PatternID>,
+        bytes: &[u8],
+        start: usize,
+        end: usize,
+    ) -> StateID {
+        self.dfa.start_state_forward(pattern_id, bytes, start, end)
+    }
+
+    #[inline]
+    fn start_state_reverse(
+        &self,
+        pattern_id: Option<PatternID>,
+        bytes: &[u8],
+        start: usize,
+        end: usize,
+    ) -> StateID {
+        self.dfa.start_state_reverse(pattern_id, bytes, start, end)
+    }
+
+    #[inline]
+    fn is_special_state(&self, id: StateID) -> bool {
+        // Since we never report accelerated states, a state that is only
+        // special because it is accelerated must not be reported as special
+        // either. Otherwise, search routines would have no idea what to do
+        // with it.
+        id == QUIT
+            || self.dfa.is_dead_state(id)
+            || self.dfa.is_quit_state(id)
+            || self.dfa.is_match_state(id)
+            || self.dfa.is_start_state(id)
+    }
+
+    #[inline]
+    fn is_dead_state(&self, id: StateID) -> bool {
+        id != QUIT && self.dfa.is_dead_state(id)
+    }
+
+    #[inline]
+    fn is_quit_state(&self, id: StateID) -> bool {
+        id == QUIT || self.dfa.is_quit_state(id)
+    }
+
+    #[inline]
+    fn is_match_state(&self, id: StateID) -> bool {
+        id != QUIT && self.dfa.is_match_state(id)
+    }
+
+    #[inline]
+    fn is_start_state(&self, id: StateID) -> bool {
+        id != QUIT && self.dfa.is_start_state(id)
+    }
+
+    #[inline]
+    fn is_accel_state(&self, _id: StateID) -> bool {
+        // Acceleration skips over bytes without looking at them, which means
+        // it could skip over quit bytes. So we never accelerate.
+        false
+    }
+
+    #[inline]
+    fn pattern_count(&self) -> usize {
+        self.dfa.pattern_count()
+    }
+
+    #[inline]
+    fn match_count(&self, id: StateID) -> usize {
+        self.dfa.match_count(id)
+    }
+
+    #[inline]
+    fn match_pattern(&self, id: StateID, index: usize) -> PatternID {
+        self.dfa.match_pattern(id, index)
+    }
+
+    #[inline]
+    fn is_anchored(&self) -> bool {
+        self.dfa.is_anchored()
+    }
+}
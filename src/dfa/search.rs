@@ -149,31 +149,50 @@ fn find_fwd<A: Automaton + ?Sized>(
 
 #[inline(never)]
 pub fn find_earliest_rev<A: Automaton + ?Sized>(
+    pre: Option<&mut prefilter::Scanner>,
     dfa: &A,
     pattern_id: Option<PatternID>,
     bytes: &[u8],
     start: usize,
     end: usize,
 ) -> Result<Option<HalfMatch>, MatchError> {
-    find_rev(true, dfa, pattern_id, bytes, start, end)
+    // Searching with a pattern ID or with an anchored DFA can only find a
+    // match that ends where the search starts, so we should never use a
+    // prefilter. It could otherwise skip past that position.
+    let anchored = pattern_id.is_some() || dfa.is_anchored();
+    if pre.is_some() && !anchored {
+        find_rev(pre, true, dfa, pattern_id, bytes, start, end)
+    } else {
+        find_rev(None, true, dfa, pattern_id, bytes, start, end)
+    }
 }
 
 #[inline(never)]
 pub fn find_leftmost_rev<A: Automaton + ?Sized>(
+    pre: Option<&mut prefilter::Scanner>,
     dfa: &A,
     pattern_id: Option<PatternID>,
     bytes: &[u8],
     start: usize,
     end: usize,
 ) -> Result<Option<HalfMatch>, MatchError> {
-    find_rev(false, dfa, pattern_id, bytes, start, end)
+    // Searching with a pattern ID or with an anchored DFA can only find a
+    // match that ends where the search starts, so we should never use a
+    // prefilter. It could otherwise skip past that position.
+    let anchored = pattern_id.is_some() || dfa.is_anchored();
+    if pre.is_some() && !anchored {
+        find_rev(pre, false, dfa, pattern_id, bytes, start, end)
+    } else {
+        find_rev(None, false, dfa, pattern_id, bytes, start, end)
+    }
 }
 
 /// This is marked as `inline(always)` specifically because it supports
-/// multiple modes of searching. Namely, the 'earliest' boolean getting inlined
-/// permits eliminating a few crucial branches.
+/// multiple modes of searching. Namely, the 'pre' and 'earliest' parameters
+/// getting inlined eliminate some critical branches.
 #[inline(always)]
 fn find_rev<A: Automaton + ?Sized>(
+    mut pre: Option<&mut prefilter::Scanner>,
     earliest: bool,
     dfa: &A,
     pattern_id: Option<PatternID>,
@@ -188,6 +207,17 @@ fn find_rev<A: Automaton + ?Sized>(
     let mut state = init_rev(dfa, pattern_id, bytes, start, end)?;
     let mut last_match = None;
     let mut at = end;
+    if let Some(ref mut pre) = pre {
+        if pre.is_effective_rev() {
+            match prev_candidate(pre, bytes, start, at) {
+                None => {}
+                Some(None) => return Ok(None),
+                Some(Some(i)) => {
+                    at = i;
+                }
+            }
+        }
+    }
     while at > start {
         at -= 1;
         while at > start && dfa.next_state(state, bytes[at]) == state {
@@ -198,11 +228,25 @@ fn find_rev<A: Automaton + ?Sized>(
         state = dfa.next_state(state, byte);
         if dfa.is_special_state(state) {
             if dfa.is_start_state(state) {
-                if dfa.is_accel_state(state) {
-                    let needles = dfa.accelerator(state);
-                    at = accel::find_rev(needles, bytes, at)
-                        .map(|i| i + 1)
-                        .unwrap_or(0);
+                let mut cand = None;
+                if let Some(ref mut pre) = pre {
+                    if pre.is_effective_rev() {
+                        cand = prev_candidate(pre, bytes, start, at);
+                    }
+                }
+                match cand {
+                    Some(None) => return Ok(last_match),
+                    Some(Some(i)) => {
+                        at = i;
+                    }
+                    None => {
+                        if dfa.is_accel_state(state) {
+                            let needles = dfa.accelerator(state);
+                            at = accel::find_rev(needles, bytes, at)
+                                .map(|i| i + 1)
+                                .unwrap_or(0);
+                        }
+                    }
                 }
             } else if dfa.is_match_state(state) {
                 last_match = Some(HalfMatch {
@@ -234,7 +278,23 @@ fn find_rev<A: Automaton + ?Sized>(
             }
         }
     }
-    Ok(eoi_rev(dfa, bytes, start, state)?.or(last_match))
+    Ok(eoi_rev(dfa, bytes, start, &mut state)?.or(last_match))
+}
+
+/// Run the given prefilter in reverse over `bytes[..at]`.
+///
+/// This returns `None` when the prefilter does not support reverse searches.
+/// Otherwise, it returns the position at which the reverse search should
+/// resume, or `Some(None)` when no match can end in `bytes[start..at]`.
+#[inline(always)]
+fn prev_candidate(
+    pre: &mut prefilter::Scanner,
+    bytes: &[u8],
+    start: usize,
+    at: usize,
+) -> Option<Option<usize>> {
+    let end = pre.find_rev(bytes, at)?.into_option_rev();
+    Some(end.filter(|&i| i >= start))
 }
 
 #[inline(never)]
@@ -392,6 +452,133 @@ fn find_overlapping_fwd_imp<A: Automaton + ?Sized>(
     result
 }
 
+#[inline(never)]
+pub fn find_overlapping_rev<A: Automaton + ?Sized>(
+    dfa: &A,
+    pattern_id: Option<PatternID>,
+    bytes: &[u8],
+    start: usize,
+    mut end: usize,
+    caller_state: &mut OverlappingState,
+) -> Result<Option<HalfMatch>, MatchError> {
+    assert!(start <= end);
+    assert!(start <= bytes.len());
+    assert!(end <= bytes.len());
+
+    let mut state = match caller_state.id() {
+        None => init_rev(dfa, pattern_id, bytes, start, end)?,
+        Some(id) => {
+            if let Some(last) = caller_state.last_match() {
+                let match_count = dfa.match_count(id);
+                if last.match_index < match_count {
+                    let m = HalfMatch {
+                        pattern: dfa.match_pattern(id, last.match_index),
+                        offset: last.offset,
+                    };
+                    last.match_index += 1;
+                    return Ok(Some(m));
+                }
+            }
+
+            // This mirrors the forward case. See the comments in
+            // 'find_overlapping_fwd_imp' for why this works. The only
+            // difference is that we move 'end' backwards instead of moving
+            // 'start' forwards, since the delayed match means we consumed
+            // 'MATCH_OFFSET' bytes preceding the start of the previous match.
+            //
+            // If there are no bytes left to consume, then the previous search
+            // already followed the final transition at the start of the
+            // search, and so there can be no more matches.
+            if end < start + MATCH_OFFSET {
+                return Ok(None);
+            }
+            end -= MATCH_OFFSET;
+            id
+        }
+    };
+
+    let mut at = end;
+    while at > start {
+        at -= 1;
+        let byte = bytes[at];
+        state = dfa.next_state(state, byte);
+        if dfa.is_special_state(state) {
+            caller_state.set_id(state);
+            if dfa.is_start_state(state) {
+                if dfa.is_accel_state(state) {
+                    let needles = dfa.accelerator(state);
+                    at = accel::find_rev(needles, bytes, at)
+                        .map(|i| i + 1)
+                        .unwrap_or(0);
+                }
+            } else if dfa.is_match_state(state) {
+                let offset = at + MATCH_OFFSET;
+                caller_state
+                    .set_last_match(StateMatch { match_index: 1, offset });
+                return Ok(Some(HalfMatch {
+                    pattern: dfa.match_pattern(state, 0),
+                    offset,
+                }));
+            } else if dfa.is_accel_state(state) {
+                let needles = dfa.accelerator(state);
+                at = accel::find_rev(needles, bytes, at)
+                    .map(|i| i + 1)
+                    .unwrap_or(0);
+            } else if dfa.is_dead_state(state) {
+                return Ok(None);
+            } else {
+                debug_assert!(dfa.is_quit_state(state));
+                return Err(MatchError::Quit { byte, offset: at });
+            }
+        }
+    }
+
+    let result = eoi_rev(dfa, bytes, start, &mut state);
+    caller_state.set_id(state);
+    if let Ok(Some(ref last_match)) = result {
+        caller_state.set_last_match(StateMatch {
+            match_index: 1,
+            offset: last_match.offset(),
+        });
+    }
+    result
+}
+
+pub fn find_fwd_anchored_end<A: Automaton + ?Sized>(
+    dfa: &A,
+    pattern_id: Option<PatternID>,
+    haystack: &[u8],
+    start: usize,
+    end: usize,
+) -> Result<Option<HalfMatch>, MatchError> {
+    assert!(start <= end);
+    assert!(start <= haystack.len());
+    assert!(end <= haystack.len());
+
+    let bytes = &haystack[..end];
+    let mut state = init_fwd(dfa, pattern_id, haystack, start, end)?;
+    let mut at = start;
+    while at < end {
+        let byte = bytes[at];
+        state = dfa.next_state(state, byte);
+        at += 1;
+        // Match states are not interesting here, since only a match ending
+        // at 'end' counts. That is decided by the EOI transition below.
+        if dfa.is_special_state(state) {
+            if dfa.is_dead_state(state) {
+                return Ok(None);
+            } else if dfa.is_quit_state(state) {
+                return Err(MatchError::Quit { byte, offset: at - 1 });
+            } else if dfa.is_accel_state(state) {
+                let needles = dfa.accelerator(state);
+                at =
+                    accel::find_fwd(needles, bytes, at).unwrap_or(bytes.len());
+            }
+        }
+    }
+    eoi_fwd(dfa, haystack, end, &mut state)
+}
+
 fn init_fwd<A: Automaton + ?Sized>(
     dfa: &A,
     pattern_id: Option<PatternID>,
@@ -456,23 +643,23 @@ fn eoi_rev<A: Automaton + ?Sized>(
     dfa: &A,
     bytes: &[u8],
     start: usize,
-    state: StateID,
+    state: &mut StateID,
 ) -> Result<Option<HalfMatch>, MatchError> {
     if start > 0 {
-        let state = dfa.next_state(state, bytes[start - 1]);
-        if dfa.is_match_state(state) {
+        *state = dfa.next_state(*state, bytes[start - 1]);
+        if dfa.is_match_state(*state) {
             Ok(Some(HalfMatch {
-                pattern: dfa.match_pattern(state, 0),
+                pattern: dfa.match_pattern(*state, 0),
                 offset: start,
             }))
         } else {
             Ok(None)
         }
     } else {
-        let state = dfa.next_eoi_state(state);
-        if dfa.is_match_state(state) {
+        *state = dfa.next_eoi_state(*state);
+        if dfa.is_match_state(*state) {
             Ok(Some(HalfMatch {
-                pattern: dfa.match_pattern(state, 0),
+                pattern: dfa.match_pattern(*state, 0),
                 offset: 0,
             }))
         } else {
@@ -4,12 +4,15 @@ Types and routines specific to sparse DFAs.
 This module is the home of [`sparse::DFA`](DFA).
 
 Unlike the [`dense`](super::dense) module, this module does not contain a
-builder or configuration specific for sparse DFAs. Instead, the intended
-way to build a sparse DFA is either by using a default configuration with
-its constructor [`sparse::DFA::new`](DFA::new), or by first configuring the
-construction of a dense DFA with [`dense::Builder`](super::dense::Builder)
-and then calling [`dense::DFA::to_sparse`](super::dense::DFA::to_sparse). For
-example, this configures a sparse DFA to do an overlapping search:
+builder for sparse DFAs. Instead, the intended way to build a sparse DFA is
+either by using a default configuration with its constructor
+[`sparse::DFA::new`](DFA::new), or by first configuring the construction of
+a dense DFA with [`dense::Builder`](super::dense::Builder) and then calling
+[`dense::DFA::to_sparse`](super::dense::DFA::to_sparse). The only
+configuration specific to sparse DFAs is [`sparse::Config`](Config), which
+controls the conversion from a dense DFA when given to
+[`dense::DFA::to_sparse_with_config`](super::dense::DFA::to_sparse_with_config).
+For example, this configures a sparse DFA to do an overlapping search:
 
 ```
 use regex_automata::{
@@ -50,18 +53,19 @@ use core::{
 };
 
 #[cfg(feature = "alloc")]
-use alloc::{collections::BTreeSet, vec, vec::Vec};
+use alloc::{vec, vec::Vec};
 
 #[cfg(feature = "alloc")]
 use crate::dfa::{dense, error::Error};
 use crate::{
     dfa::{
         automaton::{fmt_state_indicator, Automaton},
+        quit::QuitBytes,
         special::Special,
         DEAD,
     },
     util::{
-        alphabet::ByteClasses,
+        alphabet::{ByteClasses, ByteSet},
         bytes::{self, DeserializeError, Endian, SerializeError},
         id::{PatternID, StateID},
         start::Start,
@@ -70,7 +74,72 @@ use crate::{
 };
 
 const LABEL: &str = "rust-regex-automata-dfa-sparse";
-const VERSION: u32 = 2;
+const VERSION: u32 = 3;
+
+/// The bit set in the serialized flags of a DFA when it is anchored.
+const FLAG_ANCHORED: u32 = 1 << 0;
+
+/// The configuration used when converting a dense DFA to a sparse DFA.
+///
+/// A sparse DFA is always built from a dense DFA, so most of the options
+/// that influence its construction live on
+/// [`dense::Config`](crate::dfa::dense::Config). This configuration only
+/// controls what is kept when a dense DFA is converted via
+/// [`dense::DFA::to_sparse_with_config`](crate::dfa::dense::DFA::to_sparse_with_config).
+///
+/// The default configuration is used by
+/// [`dense::DFA::to_sparse`](crate::dfa::dense::DFA::to_sparse).
+#[cfg(feature = "alloc")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Config {
+    accelerate: Option<bool>,
+}
+
+#[cfg(feature = "alloc")]
+impl Config {
+    /// Return a new default sparse DFA conversion configuration.
+    pub fn new() -> Config {
+        Config::default()
+    }
+
+    /// Whether to include the accelerators of the dense DFA in the sparse
+    /// DFA.
+    ///
+    /// Each accelerated state in a sparse DFA stores the bytes that leave
+    /// that state inline with its transitions. When disabled, those bytes
+    /// are dropped, which makes the sparse DFA smaller at the cost of never
+    /// accelerating a search. Searches otherwise behave identically.
+    ///
+    /// This has no effect if the dense DFA was built without acceleration.
+    /// See [`dense::Config::accelerate`](crate::dfa::dense::Config::accelerate).
+    ///
+    /// This is enabled by default.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use regex_automata::{dfa::{Automaton, dense, sparse}, HalfMatch};
+    ///
+    /// let dense = dense::DFA::new("foo[0-9]+")?;
+    /// let sparse = dense.to_sparse_with_config(
+    ///     sparse::Config::new().accelerate(false),
+    /// )?;
+    ///
+    /// let expected = HalfMatch::must(0, 8);
+    /// assert_eq!(Some(expected), sparse.find_leftmost_fwd(b"foo12345")?);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn accelerate(mut self, yes: bool) -> Config {
+        self.accelerate = Some(yes);
+        self
+    }
+
+    /// Returns whether this configuration includes accelerators in the
+    /// sparse DFA.
+    pub fn get_accelerate(&self) -> bool {
+        self.accelerate.unwrap_or(true)
+    }
+}
 
 /// A sparse deterministic finite automaton (DFA) with variable sized states.
 ///
@@ -133,6 +202,8 @@ pub struct DFA<T> {
     trans: Transitions<T>,
     starts: StartTable<T>,
     special: Special,
+    quitset: ByteSet,
+    anchored: bool,
 }
 
 #[cfg(feature = "alloc")]
@@ -236,9 +307,57 @@ impl DFA<Vec<u8>> {
         dense::DFA::never_match()?.to_sparse()
     }
 
+    /// Returns the memory usage, in bytes, of the sparse DFA that would be
+    /// built from the given dense DFA and configuration, without building it.
+    ///
+    /// This takes time proportional to the number of transitions in the dense
+    /// DFA, but does not allocate. The result is always equivalent to calling
+    /// `memory_usage` on the DFA returned by `from_dense`.
+    pub(crate) fn from_dense_memory_usage<T: AsRef<[u32]>>(
+        dfa: &dense::DFA<T>,
+        config: Config,
+    ) -> usize {
+        let trans: usize = dfa
+            .states()
+            .map(|state| DFA::encoded_state_len(dfa, &state, config))
+            .sum();
+        trans + StartTable::from_dense_dfa_len(dfa)
+    }
+
+    /// Returns the number of bytes used to encode the given state of a dense
+    /// DFA in a sparse DFA built with the given configuration. This mirrors
+    /// the encoding written by `from_dense`.
+    fn encoded_state_len<T: AsRef<[u32]>>(
+        dfa: &dense::DFA<T>,
+        state: &dense::State<'_>,
+        config: Config,
+    ) -> usize {
+        // The byte ranges of every non-EOI transition, plus a dummy EOI
+        // transition that is always present.
+        let transition_count = 1 + state
+            .sparse_transitions()
+            .filter(|&(unit, _, _)| !unit.is_eoi())
+            .count();
+        // The transition count, and a byte range and next state for each
+        // transition.
+        let mut len = 2 + transition_count * (2 + StateID::SIZE);
+        if dfa.is_match_state(state.id()) {
+            // A u32 length prefix followed by the pattern IDs.
+            let plen = dfa.match_pattern_len(state.id());
+            len += size_of::<u32>() + plen * PatternID::SIZE;
+        }
+        // The accelerator length followed by the accelerator itself.
+        len += 1;
+        if config.get_accelerate() {
+            len += dfa.accelerator(state.id()).len();
+        }
+        len
+    }
+
     /// The implementation for constructing a sparse DFA from a dense DFA.
     pub(crate) fn from_dense<T: AsRef<[u32]>>(
         dfa: &dense::DFA<T>,
+        config: Config,
     ) -> Result<DFA<Vec<u8>>, Error> {
         // In order to build the transition table, we need to be able to write
         // state identifiers for each of the "next" transitions in each state.
@@ -257,9 +376,13 @@ impl DFA<Vec<u8>> {
         // In the second pass, we fill in the transitions based on the map
         // built in the first pass.
 
-        // The capacity given here reflects a minimum. (Well, the true minimum
-        // is likely even bigger, but hopefully this saves a few reallocs.)
-        let mut sparse = Vec::with_capacity(StateID::SIZE * dfa.state_count());
+        // We know exactly how much space each state needs, so we can avoid
+        // reallocating as we go.
+        let mut sparse = Vec::with_capacity(
+            dfa.states()
+                .map(|state| DFA::encoded_state_len(dfa, &state, config))
+                .sum(),
+        );
         // This maps state indices from the dense DFA to StateIDs in the sparse
         // DFA. We build out this map on the first pass, and then use it in the
         // second pass to back-fill our transitions.
@@ -377,10 +500,21 @@ impl DFA<Vec<u8>> {
             // at most 4 bytes and at least 1 byte. The first byte is the
             // length, N. N bytes follow the length. The set of bytes that
             // follow correspond (exhaustively) to the bytes that must be seen
-            // to leave this state.
-            let accel = dfa.accelerator(state.id());
+            // to leave this state. When accelerators are disabled, every
+            // state gets a zero length, which searches treat as "no
+            // acceleration" even for states in the accelerated range.
+            let accel = if config.get_accelerate() {
+                dfa.accelerator(state.id())
+            } else {
+                &[]
+            };
             sparse.push(accel.len().try_into().unwrap());
             sparse.extend_from_slice(accel);
+            debug_assert_eq!(
+                sparse.len() - pos,
+                DFA::encoded_state_len(dfa, &state, config),
+                "encoded state length should be computed exactly",
+            );
         }
 
         let mut new = DFA {
@@ -392,6 +526,8 @@ impl DFA<Vec<u8>> {
             },
             starts: StartTable::from_dense_dfa(dfa, &remap)?,
             special: dfa.special().remap(|id| remap[dfa.to_index(id)]),
+            quitset: dfa.quit_bytes(),
+            anchored: dfa.is_anchored(),
         };
         // And here's our second pass. Iterate over all of the dense states
         // again, and update the transitions in each of the states in the
@@ -422,6 +558,8 @@ impl<T: AsRef<[u8]>> DFA<T> {
             trans: self.trans.as_ref(),
             starts: self.starts.as_ref(),
             special: self.special,
+            quitset: self.quitset,
+            anchored: self.anchored,
         }
     }
 
@@ -436,6 +574,8 @@ impl<T: AsRef<[u8]>> DFA<T> {
             trans: self.trans.to_owned(),
             starts: self.starts.to_owned(),
             special: self.special,
+            quitset: self.quitset,
+            anchored: self.anchored,
         }
     }
 
@@ -462,6 +602,75 @@ impl<T: AsRef<[u8]>> DFA<T> {
     pub fn has_starts_for_each_pattern(&self) -> bool {
         self.starts.patterns > 0
     }
+
+    /// Returns the set of bytes that cause this DFA to enter a quit state.
+    ///
+    /// This is the quit set of the dense DFA this sparse DFA was built from.
+    /// (See [`dense::Config::quit`](crate::dfa::dense::Config::quit).) It is
+    /// preserved by serialization, which makes it possible to report which
+    /// bytes can cause a [`MatchError::Quit`](crate::MatchError::Quit) error
+    /// even for a DFA that was deserialized.
+    ///
+    /// This does not include any quit bytes added at search time via
+    /// [`DFA::with_quit_bytes`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use regex_automata::dfa::{dense, sparse};
+    ///
+    /// let dfa = dense::Builder::new()
+    ///     .configure(dense::Config::new().quit(b'\n', true))
+    ///     .build(r"foo\w+")?
+    ///     .to_sparse()?;
+    /// let bytes = dfa.to_bytes_native_endian();
+    /// let dfa: sparse::DFA<&[u8]> = sparse::DFA::from_bytes(&bytes)?.0;
+    ///
+    /// let quit = dfa.quit_bytes();
+    /// assert!(quit.contains(b'\n'));
+    /// assert!(!quit.contains(b'f'));
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn quit_bytes(&self) -> ByteSet {
+        self.quitset
+    }
+
+    /// Return a wrapper around this DFA that quits its search whenever any
+    /// byte in the given set is seen.
+    ///
+    /// This permits adding quit bytes at search time, without rebuilding the
+    /// DFA. The quit bytes given are in addition to any quit bytes this DFA
+    /// was built with. See [`QuitBytes`] for more details.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use regex_automata::{
+    ///     dfa::{Automaton, sparse},
+    ///     util::alphabet::ByteSet,
+    ///     HalfMatch, MatchError,
+    /// };
+    ///
+    /// let dfa = sparse::DFA::new("[a-z]+")?;
+    /// let mut quit = ByteSet::empty();
+    /// quit.add(b'\n');
+    ///
+    /// let qdfa = dfa.with_quit_bytes(quit);
+    /// assert_eq!(
+    ///     Err(MatchError::Quit { byte: b'\n', offset: 0 }),
+    ///     qdfa.find_leftmost_fwd(b"\nabc"),
+    /// );
+    /// // Matches found before the quit byte are still reported.
+    /// assert_eq!(
+    ///     Ok(Some(HalfMatch::must(0, 3))),
+    ///     qdfa.find_leftmost_fwd(b"abc xyz\n"),
+    /// );
+    ///
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn with_quit_bytes(&self, quit: ByteSet) -> QuitBytes<&DFA<T>> {
+        QuitBytes::new(self, quit)
+    }
 }
 
 /// Routines for converting a sparse DFA to other representations, such as raw
@@ -789,13 +998,14 @@ impl<T: AsRef<[u8]>> DFA<T> {
         nw += bytes::write_endianness_check::<E>(&mut dst[nw..])?;
         nw += bytes::write_version::<E>(VERSION, &mut dst[nw..])?;
         nw += {
-            // Currently unused, intended for future flexibility
-            E::write_u32(0, &mut dst[nw..]);
+            let flags = if self.anchored { FLAG_ANCHORED } else { 0 };
+            E::write_u32(flags, &mut dst[nw..]);
             size_of::<u32>()
         };
         nw += self.trans.write_to::<E>(&mut dst[nw..])?;
         nw += self.starts.write_to::<E>(&mut dst[nw..])?;
         nw += self.special.write_to::<E>(&mut dst[nw..])?;
+        nw += self.quitset.write_to(&mut dst[nw..])?;
         Ok(nw)
     }
 
@@ -811,6 +1021,10 @@ impl<T: AsRef<[u8]>> DFA<T> {
     /// Passing a buffer smaller than the size returned by this method will
     /// result in a serialization error.
     ///
+    /// This runs in constant time. It does not visit any of the DFA's states,
+    /// since the size of every part of the serialized form is either fixed or
+    /// equal to the length of a slice that the DFA already stores.
+    ///
     /// # Example
     ///
     /// This example shows how to dynamically allocate enough room to serialize
@@ -837,10 +1051,11 @@ impl<T: AsRef<[u8]>> DFA<T> {
         bytes::write_label_len(LABEL)
         + bytes::write_endianness_check_len()
         + bytes::write_version_len()
-        + size_of::<u32>() // unused, intended for future flexibility
+        + size_of::<u32>() // flags
         + self.trans.write_to_len()
         + self.starts.write_to_len()
         + self.special.write_to_len()
+        + self.quitset.write_to_len()
     }
 }
 
@@ -1066,8 +1281,13 @@ impl<'a> DFA<&'a [u8]> {
         nr += bytes::read_endianness_check(&slice[nr..])?;
         nr += bytes::read_version(&slice[nr..], VERSION)?;
 
-        let _unused = bytes::try_read_u32(&slice[nr..], "unused space")?;
-        nr += size_of::<u32>();
+        // Only the lowest bit is currently used, and it indicates whether the
+        // DFA is anchored. The remaining bits are intended for future
+        // flexibility. DFAs serialized before this field was used always
+        // wrote zero, which corresponds to an unanchored DFA.
+        let (flags, nread) = bytes::try_read_u32(&slice[nr..], "flags")?;
+        nr += nread;
+        let anchored = flags & FLAG_ANCHORED != 0;
 
         let (trans, nread) = Transitions::from_bytes_unchecked(&slice[nr..])?;
         nr += nread;
@@ -1083,7 +1303,37 @@ impl<'a> DFA<&'a [u8]> {
             ));
         }
 
-        Ok((DFA { trans, starts, special }, nr))
+        let (quitset, nread) = ByteSet::from_bytes(&slice[nr..])?;
+        nr += nread;
+
+        Ok((DFA { trans, starts, special, quitset, anchored }, nr))
+    }
+}
+
+/// A variety of generic internal methods for accessing DFA internals.
+impl<T: AsRef<[u8]>> DFA<T> {
+    /// Return the byte classes used by this DFA.
+    pub(crate) fn byte_classes(&self) -> &ByteClasses {
+        &self.trans.classes
+    }
+
+    /// Return the info about special states.
+    pub(crate) fn special(&self) -> &Special {
+        &self.special
+    }
+
+    /// Returns an iterator over the identifiers of all states in this DFA,
+    /// in the order in which they are encoded.
+    pub(crate) fn state_ids(&self) -> impl Iterator<Item = StateID> + '_ {
+        self.trans.states().map(|state| state.id())
+    }
+
+    /// Returns an iterator over all start states in this DFA, along with
+    /// their start type and the pattern they are anchored to, if any.
+    pub(crate) fn starts(
+        &self,
+    ) -> impl Iterator<Item = (StateID, Start, Option<PatternID>)> + '_ {
+        self.starts.iter()
     }
 }
 
@@ -1216,6 +1466,11 @@ unsafe impl<T: AsRef<[u8]>> Automaton for DFA<T> {
     fn accelerator(&self, id: StateID) -> &[u8] {
         self.trans.state(id).accelerator()
     }
+
+    #[inline]
+    fn is_anchored(&self) -> bool {
+        self.anchored
+    }
 }
 
 /// The transition table portion of a sparse DFA.
@@ -1372,48 +1627,11 @@ impl<T: AsRef<[u8]>> Transitions<T> {
     fn validate(&self) -> Result<(), DeserializeError> {
         // In order to validate everything, we not only need to make sure we
         // can decode every state, but that every transition in every state
-        // points to a valid state. There are many duplicative transitions, so
-        // we record state IDs that we've verified so that we don't redo the
-        // decoding work.
-        //
-        // Except, when in no_std mode, we don't have dynamic memory allocation
-        // available to us, so we skip this optimization. It's not clear
-        // whether doing something more clever is worth it just yet. If you're
-        // profiling this code and need it to run faster, please file an issue.
+        // points to the beginning of a state. So we do this in two passes.
+        // The first decodes every state in order. The second records where
+        // each state begins and checks every transition against that record,
+        // which means no state is ever decoded more than once.
         //
-        // ---AG
-        struct Seen {
-            #[cfg(feature = "alloc")]
-            set: BTreeSet<StateID>,
-            #[cfg(not(feature = "alloc"))]
-            set: core::marker::PhantomData<StateID>,
-        }
-
-        #[cfg(feature = "alloc")]
-        impl Seen {
-            fn new() -> Seen {
-                Seen { set: BTreeSet::new() }
-            }
-            fn insert(&mut self, id: StateID) {
-                self.set.insert(id);
-            }
-            fn contains(&self, id: &StateID) -> bool {
-                self.set.contains(id)
-            }
-        }
-
-        #[cfg(not(feature = "alloc"))]
-        impl Seen {
-            fn new() -> Seen {
-                Seen { set: core::marker::PhantomData }
-            }
-            fn insert(&mut self, _id: StateID) {}
-            fn contains(&self, _id: &StateID) -> bool {
-                false
-            }
-        }
-
-        let mut verified: Seen = Seen::new();
         // We need to make sure that we decode the correct number of states.
         // Otherwise, an empty set of transitions would validate even if the
         // recorded state count is non-empty.
@@ -1423,7 +1641,6 @@ impl<T: AsRef<[u8]>> Transitions<T> {
         let mut id = DEAD;
         while id.as_usize() < self.sparse().len() {
             let state = self.try_state(id)?;
-            verified.insert(id);
             // The next ID should be the offset immediately following `state`.
             id = StateID::new(bytes::add(
                 id.as_usize(),
@@ -1434,22 +1651,76 @@ impl<T: AsRef<[u8]>> Transitions<T> {
                 DeserializeError::state_id_error(err, "next state ID offset")
             })?;
             count += 1;
-
-            // Now check that all transitions in this state are correct.
-            for i in 0..state.ntrans {
-                let to = state.next_at(i);
-                if verified.contains(&to) {
-                    continue;
-                }
-                let _ = self.try_state(to)?;
-                verified.insert(id);
-            }
         }
         if count != self.count {
             return Err(DeserializeError::generic(
                 "mismatching sparse state count",
             ));
         }
+
+        // Every state was decoded successfully above, so it's now safe to
+        // iterate over them. When we have an allocator and the table is too
+        // big for a single window of StateStarts, we record every state in a
+        // sorted list instead so that the states are only visited twice.
+        #[cfg(feature = "alloc")]
+        {
+            if self.sparse().len() > StateStarts::WINDOW_LEN {
+                let starts: Vec<StateID> =
+                    self.states().map(|state| state.id).collect();
+                return self.validate_transitions(|id| {
+                    Some(starts.binary_search(&id).is_ok())
+                });
+            }
+        }
+        // Otherwise, we check the table one window at a time. Every
+        // transition was checked to be in bounds above, so each one falls
+        // into exactly one window and is thus checked exactly once. States
+        // are visited in increasing order of their IDs, so a single cursor
+        // over the states is shared by all windows to record where each
+        // state begins.
+        //
+        // This uses a fixed amount of memory. The cost is that every
+        // transition is visited once per window, since without an allocator,
+        // there is nowhere to bucket the transitions by the window their
+        // target falls in. So this takes time proportional to the number of
+        // transitions times the number of windows. With an allocator, this
+        // path is only taken for tables that fit in one window.
+        let mut states = self.states().peekable();
+        let mut window_start = 0;
+        while window_start < self.sparse().len() {
+            let window_end = window_start + StateStarts::WINDOW_LEN;
+            let mut starts = StateStarts::new(window_start);
+            while let Some(state) = states.peek() {
+                if state.id.as_usize() >= window_end {
+                    break;
+                }
+                starts.insert(state.id);
+                states.next();
+            }
+            self.validate_transitions(|id| starts.contains(id))?;
+            window_start = window_end;
+        }
+        Ok(())
+    }
+
+    /// Validates that every transition in this table points to the beginning
+    /// of a state, as reported by `is_start`. When `is_start` returns `None`
+    /// for a transition, then that transition is skipped.
+    ///
+    /// This assumes every state in this table can be decoded.
+    fn validate_transitions<F: Fn(StateID) -> Option<bool>>(
+        &self,
+        is_start: F,
+    ) -> Result<(), DeserializeError> {
+        for state in self.states() {
+            for i in 0..state.ntrans {
+                if is_start(state.next_at(i)) == Some(false) {
+                    return Err(DeserializeError::generic(
+                        "sparse transition does not point to a state",
+                    ));
+                }
+            }
+        }
         Ok(())
     }
 
@@ -1514,17 +1785,19 @@ impl<T: AsRef<[u8]>> Transitions<T> {
     /// transitions point to valid states themselves, nor does it verify that
     /// every pattern ID is valid.
     fn try_state(&self, id: StateID) -> Result<State<'_>, DeserializeError> {
-        if id.as_usize() > self.sparse().len() {
+        // A state ID equal to the length of the transitions would produce an
+        // empty state below, so it must be rejected too.
+        if id.as_usize() >= self.sparse().len() {
             return Err(DeserializeError::generic("invalid sparse state ID"));
         }
         let mut state = &self.sparse()[id.as_usize()..];
         // Encoding format starts with a u16 that stores the total number of
         // transitions in this state.
-        let (mut ntrans, _) =
+        let (mut ntrans, nr) =
             bytes::try_read_u16_as_usize(state, "state transition count")?;
         let is_match = ((1 << 15) & ntrans) != 0;
         ntrans &= !(1 << 15);
-        state = &state[2..];
+        state = &state[nr..];
         if ntrans > 257 || ntrans == 0 {
             return Err(DeserializeError::generic("invalid transition count"));
         }
@@ -1555,11 +1828,11 @@ impl<T: AsRef<[u8]>> Transitions<T> {
         for idbytes in next.chunks(self.id_len()) {
             let (id, _) =
                 bytes::read_state_id(idbytes, "sparse state ID in try_state")?;
-            bytes::check_slice_len(
-                self.sparse(),
-                id.as_usize(),
-                "invalid sparse state ID",
-            )?;
+            if id.as_usize() >= self.sparse().len() {
+                return Err(DeserializeError::generic(
+                    "invalid sparse state ID",
+                ));
+            }
         }
 
         // If this is a match state, then read the pattern IDs for this state.
@@ -1594,9 +1867,7 @@ impl<T: AsRef<[u8]>> Transitions<T> {
         // is no bigger than 3. The length indicates the number of bytes that
         // follow, where each byte corresponds to a transition out of this
         // state.
-        if state.is_empty() {
-            return Err(DeserializeError::generic("no accelerator length"));
-        }
+        bytes::check_slice_len(state, 1, "sparse accelerator length")?;
         let (accel_len, state) = (state[0] as usize, &state[1..]);
 
         if accel_len > 3 {
@@ -1719,32 +1990,49 @@ struct StartTable<T> {
 #[cfg(feature = "alloc")]
 impl StartTable<Vec<u8>> {
     fn new(patterns: usize) -> StartTable<Vec<u8>> {
+        let len = StartTable::table_len(patterns);
+        StartTable { table: vec![0; len], stride: Start::count(), patterns }
+    }
+
+    /// Returns the length, in bytes, of the table of a start table that
+    /// encodes start states for the given number of patterns.
+    fn table_len(patterns: usize) -> usize {
         let stride = Start::count();
         // This is OK since the only way we're here is if a dense DFA could be
         // constructed successfully, which uses the same space.
-        let len = stride
-            .checked_mul(patterns)
-            .unwrap()
-            .checked_add(stride)
-            .unwrap()
-            .checked_mul(StateID::SIZE)
-            .unwrap();
-        StartTable { table: vec![0; len], stride, patterns }
+        StateID::offset(
+            stride.checked_mul(patterns).unwrap().checked_add(stride).unwrap(),
+        )
+        .unwrap()
     }
 
-    fn from_dense_dfa<T: AsRef<[u32]>>(
+    /// Returns the number of patterns for which start states are encoded
+    /// when converting the given dense DFA.
+    fn dense_start_pattern_count<T: AsRef<[u32]>>(
         dfa: &dense::DFA<T>,
-        remap: &[StateID],
-    ) -> Result<StartTable<Vec<u8>>, Error> {
+    ) -> usize {
         // Unless the DFA has start states compiled for each pattern, then
         // as far as the starting state table is concerned, there are zero
         // patterns to account for. It will instead only store starting states
         // for the entire DFA.
-        let start_pattern_count = if dfa.has_starts_for_each_pattern() {
+        if dfa.has_starts_for_each_pattern() {
             dfa.pattern_count()
         } else {
             0
-        };
+        }
+    }
+
+    /// Returns the memory usage, in bytes, of the start table that
+    /// `from_dense_dfa` would build for the given dense DFA.
+    fn from_dense_dfa_len<T: AsRef<[u32]>>(dfa: &dense::DFA<T>) -> usize {
+        StartTable::table_len(StartTable::dense_start_pattern_count(dfa))
+    }
+
+    fn from_dense_dfa<T: AsRef<[u32]>>(
+        dfa: &dense::DFA<T>,
+        remap: &[StateID],
+    ) -> Result<StartTable<Vec<u8>>, Error> {
+        let start_pattern_count = StartTable::dense_start_pattern_count(dfa);
         let mut sl = StartTable::new(start_pattern_count);
         for (old_start_id, sty, pid) in dfa.starts() {
             let new_start_id = remap[dfa.to_index(old_start_id)];
@@ -1884,8 +2172,19 @@ impl<T: AsRef<[u8]>> StartTable<T> {
         let index = match pattern_id {
             None => start_index,
             Some(pid) => {
+                assert!(
+                    self.patterns > 0,
+                    "attempted to search for a specific pattern \
+                     without enabling starts_for_each_pattern",
+                );
                 let pid = pid.as_usize();
-                assert!(pid < self.patterns, "invalid pattern ID {:?}", pid);
+                assert!(
+                    pid < self.patterns,
+                    "invalid pattern ID {}; regex has {} pattern{}",
+                    pid,
+                    self.patterns,
+                    if self.patterns == 1 { "" } else { "s" },
+                );
                 self.stride
                     .checked_mul(pid)
                     .unwrap()
@@ -1895,7 +2194,7 @@ impl<T: AsRef<[u8]>> StartTable<T> {
                     .unwrap()
             }
         };
-        let start = index * StateID::SIZE;
+        let start = StateID::offset(index).unwrap();
         // This OK since we're allowed to assume that the start table contains
         // valid StateIDs.
         bytes::read_state_id_unchecked(&self.table()[start..]).0
@@ -1950,7 +2249,7 @@ impl<T: AsMut<[u8]>> StartTable<T> {
                     .unwrap()
             }
         };
-        let start = index * StateID::SIZE;
+        let start = StateID::offset(index).unwrap();
         let end = start + StateID::SIZE;
         bytes::write_state_id::<bytes::NE>(
             id,
@@ -2007,6 +2306,59 @@ impl<'a, T> fmt::Debug for StartStateIter<'a, T> {
     }
 }
 
+/// The set of offsets at which states begin in a window of a sparse
+/// transition table.
+///
+/// This is used to check that every transition points to the beginning of a
+/// state when validating a deserialized DFA. Offsets are recorded in a fixed
+/// size bitset covering a window of the table, which doesn't require an
+/// allocator. Tables bigger than one window are checked one window at a time.
+struct StateStarts {
+    bits: [u64; StateStarts::BITSET_WORDS],
+    start: usize,
+}
+
+impl StateStarts {
+    /// The number of 64-bit words used by the bitset. This permits windows
+    /// of 16KB to be recorded while using only 2KB of stack space.
+    const BITSET_WORDS: usize = 256;
+
+    /// The number of bytes of a transition table covered by one window.
+    const WINDOW_LEN: usize = StateStarts::BITSET_WORDS * 64;
+
+    /// Create an empty set for the window of a transition table beginning at
+    /// the given offset.
+    fn new(start: usize) -> StateStarts {
+        StateStarts { bits: [0; StateStarts::BITSET_WORDS], start }
+    }
+
+    /// Returns the position of the given ID in this set's bitset, or `None`
+    /// if it falls outside of this set's window.
+    fn index(&self, id: StateID) -> Option<usize> {
+        let i = id.as_usize().checked_sub(self.start)?;
+        if i < StateStarts::WINDOW_LEN {
+            Some(i)
+        } else {
+            None
+        }
+    }
+
+    /// Record that a state begins at the given ID. IDs that fall outside of
+    /// this set's window are ignored.
+    fn insert(&mut self, id: StateID) {
+        if let Some(i) = self.index(id) {
+            self.bits[i / 64] |= 1 << (i % 64);
+        }
+    }
+
+    /// Returns true if and only if a state begins at the given ID. If the ID
+    /// falls outside of this set's window, then this returns `None`.
+    fn contains(&self, id: StateID) -> Option<bool> {
+        let i = self.index(id)?;
+        Some(self.bits[i / 64] & (1 << (i % 64)) != 0)
+    }
+}
+
 /// An iterator over all states in a sparse DFA.
 ///
 /// This iterator yields tuples, where the first element is the state ID and
@@ -2281,3 +2633,57 @@ fn binary_search_ranges(ranges: &[u8], needle: u8) -> Option<usize> {
     }
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The number of bytes used by each state built by `chain`.
+    const STATE_LEN: usize = 9;
+
+    /// Writes a chain of states into the given buffer, where each state has
+    /// only an EOI transition to the state following it, and the last state
+    /// has an EOI transition to the dead state.
+    fn chain(buf: &mut [u8]) -> usize {
+        let count = buf.len() / STATE_LEN;
+        for (i, state) in buf.chunks_exact_mut(STATE_LEN).enumerate() {
+            let next = if i + 1 == count { 0 } else { (i + 1) * STATE_LEN };
+            state[..2].copy_from_slice(&1u16.to_ne_bytes());
+            state[2..4].copy_from_slice(&[0, 0]);
+            state[4..8].copy_from_slice(&(next as u32).to_ne_bytes());
+            state[8] = 0;
+        }
+        count
+    }
+
+    fn validate(buf: &[u8], count: usize) -> Result<(), DeserializeError> {
+        let trans = Transitions {
+            sparse: buf,
+            classes: ByteClasses::empty(),
+            count,
+            patterns: 0,
+        };
+        trans.validate()
+    }
+
+    // Tests that a transition that points into the middle of a state is
+    // rejected, regardless of which window of StateStarts it falls into.
+    // This doesn't require an allocator, so that it checks the same
+    // strictness in every configuration.
+    #[test]
+    fn validate_transition_into_state() {
+        let mut buf = [0u8; 2000 * STATE_LEN];
+        assert!(buf.len() > StateStarts::WINDOW_LEN);
+        let count = chain(&mut buf);
+        assert!(validate(&buf, count).is_ok());
+
+        for &(from, to) in
+            &[(0, 1), (1900, 1), (1900, 1950 * STATE_LEN + 1), (5, 19_000)]
+        {
+            let mut corrupt = buf;
+            let pos = from * STATE_LEN + 4;
+            corrupt[pos..pos + 4].copy_from_slice(&(to as u32).to_ne_bytes());
+            assert!(validate(&corrupt, count).is_err());
+        }
+    }
+}
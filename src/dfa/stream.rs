@@ -0,0 +1,156 @@
+use crate::{
+    dfa::automaton::Automaton,
+    util::{id::StateID, matchtypes::HalfMatch, MATCH_OFFSET},
+    MatchError,
+};
+
+/// A leftmost forward search that is executed incrementally over a sequence
+/// of chunks.
+///
+/// The search routines on [`Automaton`] require the entire haystack to be
+/// available as a single contiguous slice. This helper instead permits
+/// feeding the haystack to a DFA one chunk at a time, for example, when
+/// reading fixed size blocks from a file. The current DFA state is carried
+/// across chunk boundaries, and all offsets reported are relative to the
+/// start of the first chunk.
+///
+/// The automaton is not stored inside the searcher. Instead, it must be given
+/// to every call. Callers must use the same automaton for every call on the
+/// same searcher, otherwise the results are unspecified (but safe).
+///
+/// Once a search completes, either because a match was found or because the
+/// DFA determined that no match is possible, all subsequent calls to
+/// [`StreamSearcher::feed`] and [`StreamSearcher::finish`] return `Ok(None)`.
+/// To start a new search, create a new searcher.
+///
+/// Since the haystack is never available in its entirety, this does not use
+/// prefilters or accelerated states. Moreover, the search always starts as
+/// if it were at the beginning of the haystack, so `^` will match at the
+/// start of the first chunk.
+///
+/// # Example
+///
+/// ```
+/// use regex_automata::{
+///     dfa::{dense, StreamSearcher},
+///     HalfMatch,
+/// };
+///
+/// let dfa = dense::DFA::new("foo[0-9]+")?;
+/// let mut searcher = StreamSearcher::new(&dfa);
+/// assert_eq!(None, searcher.feed(&dfa, b"xfoo1")?);
+/// assert_eq!(None, searcher.feed(&dfa, b"234")?);
+/// assert_eq!(Some(HalfMatch::must(0, 9)), searcher.feed(&dfa, b"5 bar")?);
+///
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Clone, Debug)]
+pub struct StreamSearcher {
+    /// The current state of the DFA.
+    state: StateID,
+    /// The total number of bytes fed to this searcher so far.
+    offset: usize,
+    /// The most recent match seen, if any. Matches are not reported until
+    /// the search completes, since leftmost semantics may extend them.
+    last_match: Option<HalfMatch>,
+    /// Set once the search is complete.
+    done: bool,
+}
+
+impl StreamSearcher {
+    /// Create a new searcher that begins at the forward start state of the
+    /// given automaton.
+    pub fn new<A: Automaton + ?Sized>(dfa: &A) -> StreamSearcher {
+        let state = dfa.start_state_forward(None, &[], 0, 0);
+        StreamSearcher { state, offset: 0, last_match: None, done: false }
+    }
+
+    /// Returns the total number of bytes given to this searcher so far.
+    ///
+    /// If the search completed in the middle of a chunk, then this only
+    /// includes the bytes of that chunk that were actually searched.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Returns true if and only if this search has completed. Once it has,
+    /// feeding more bytes to this searcher has no effect.
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    /// Search the next chunk of the haystack.
+    ///
+    /// This returns a match once the search has determined that no other
+    /// bytes can change it. Otherwise, `Ok(None)` is returned and the caller
+    /// should continue feeding chunks, and then call
+    /// [`StreamSearcher::finish`] once there are no more chunks.
+    ///
+    /// # Errors
+    ///
+    /// This returns an error if the automaton enters a quit state before a
+    /// match was found. The offset in the error is relative to the start of
+    /// the first chunk.
+    pub fn feed<A: Automaton + ?Sized>(
+        &mut self,
+        dfa: &A,
+        chunk: &[u8],
+    ) -> Result<Option<HalfMatch>, MatchError> {
+        if self.done {
+            return Ok(None);
+        }
+        for &byte in chunk {
+            self.state = dfa.next_state(self.state, byte);
+            self.offset += 1;
+            if dfa.is_special_state(self.state) {
+                if dfa.is_start_state(self.state) {
+                    // Since we don't use prefilters, there's nothing to do.
+                } else if dfa.is_match_state(self.state) {
+                    self.last_match = Some(HalfMatch {
+                        pattern: dfa.match_pattern(self.state, 0),
+                        offset: self.offset - MATCH_OFFSET,
+                    });
+                } else if dfa.is_accel_state(self.state) {
+                    // Since we don't use acceleration, there's nothing to do.
+                } else if dfa.is_dead_state(self.state) {
+                    self.done = true;
+                    return Ok(self.last_match.take());
+                } else {
+                    debug_assert!(dfa.is_quit_state(self.state));
+                    self.done = true;
+                    if self.last_match.is_some() {
+                        return Ok(self.last_match.take());
+                    }
+                    return Err(MatchError::Quit {
+                        byte,
+                        offset: self.offset - 1,
+                    });
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Complete the search after all chunks have been fed to this searcher.
+    ///
+    /// This follows the automaton's EOI transition, and thus may find a
+    /// match that ends at the end of the last chunk. It returns the final
+    /// match found by the search, if any.
+    pub fn finish<A: Automaton + ?Sized>(
+        &mut self,
+        dfa: &A,
+    ) -> Result<Option<HalfMatch>, MatchError> {
+        if self.done {
+            return Ok(None);
+        }
+        self.done = true;
+        self.state = dfa.next_eoi_state(self.state);
+        if dfa.is_match_state(self.state) {
+            return Ok(Some(HalfMatch {
+                pattern: dfa.match_pattern(self.state, 0),
+                offset: self.offset,
+            }));
+        }
+        Ok(self.last_match.take())
+    }
+}
@@ -9,7 +9,7 @@ This module also contains a [`hybrid::dfa::Builder`](Builder) and a
 
 use core::{borrow::Borrow, iter, mem::size_of};
 
-use alloc::{sync::Arc, vec::Vec};
+use alloc::{format, string::String, sync::Arc, vec::Vec};
 
 use crate::{
     hybrid::{
@@ -38,6 +38,15 @@ use crate::{
 /// assume that the cache can fit at least some small number of states.
 const MIN_STATES: usize = 5;
 
+/// The minimum average number of states that a lazy DFA's cache must compute
+/// between clearings for it to be considered effective.
+///
+/// When a cache is cleared after computing only a handful of states, nearly
+/// every transition taken by a search requires determinization. This is
+/// typically much slower than a fully compiled DFA or even an NFA simulation.
+/// See [`Cache::was_effective`].
+const MIN_STATES_PER_CLEAR: usize = 10 * MIN_STATES;
+
 /// A hybrid NFA/DFA (also called a "lazy DFA") for regex searching.
 ///
 /// A lazy DFA is a DFA that builds itself at search time. It otherwise has
@@ -107,11 +116,14 @@ pub struct DFA {
     stride2: usize,
     classes: ByteClasses,
     quitset: ByteSet,
+    unicode_word_quitset: ByteSet,
     anchored: bool,
     match_kind: MatchKind,
     starts_for_each_pattern: bool,
     cache_capacity: usize,
     minimum_cache_clear_count: Option<usize>,
+    minimum_bytes_per_state: Option<usize>,
+    max_total_states: Option<usize>,
 }
 
 impl DFA {
@@ -292,7 +304,9 @@ impl DFA {
     ///
     /// Resetting a cache sets its "clear count" to 0. This is relevant if the
     /// lazy DFA has been configured to "give up" after it has cleared the
-    /// cache a certain number of times.
+    /// cache a certain number of times. It also resets the statistics used
+    /// by [`Cache::was_effective`] and the count of states checked against
+    /// [`Config::max_total_states`].
     ///
     /// Any lazy state ID generated by the cache prior to resetting it is
     /// invalid after the reset.
@@ -375,6 +389,25 @@ impl DFA {
         &self.nfa
     }
 
+    /// Returns true if and only if this lazy DFA was configured to execute
+    /// anchored searches.
+    pub(crate) fn is_anchored(&self) -> bool {
+        self.anchored
+    }
+
+    /// Returns the match semantics this lazy DFA was built with.
+    pub(crate) fn match_kind(&self) -> MatchKind {
+        self.match_kind
+    }
+
+    /// Returns true if and only if this lazy DFA quits on the given byte
+    /// only because [`Config::unicode_word_boundary`] is enabled and one of
+    /// its patterns contains a Unicode word boundary. This is false for any
+    /// byte given to [`Config::quit`].
+    pub(crate) fn is_unicode_word_quit(&self, byte: u8) -> bool {
+        self.unicode_word_quitset.contains(byte)
+    }
+
     /// Returns the stride, as a base-2 exponent, required for these
     /// equivalence classes.
     ///
@@ -401,15 +434,101 @@ impl DFA {
         self.classes.alphabet_len()
     }
 
+    /// Returns the set of bytes that cause this lazy DFA to enter a quit
+    /// state.
+    ///
+    /// This includes any bytes added via [`Config::quit`]. When
+    /// [`Config::unicode_word_boundary`] is enabled and at least one of the
+    /// patterns contains a Unicode word boundary, this also includes every
+    /// non-ASCII byte. When a search returns a [`MatchError::Quit`] error,
+    /// the byte reported is always in this set, so this can be used to
+    /// explain which configured condition caused a search to quit.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use regex_automata::hybrid::dfa::DFA;
+    ///
+    /// let dfa = DFA::builder()
+    ///     .configure(DFA::config().unicode_word_boundary(true))
+    ///     .build(r"\b\w+\b")?;
+    /// let quit = dfa.quit_bytes();
+    /// assert!(quit.contains(0x80));
+    /// assert!(!quit.contains(b'a'));
+    ///
+    /// // Without a Unicode word boundary in the pattern, the option has no
+    /// // effect.
+    /// let dfa = DFA::builder()
+    ///     .configure(DFA::config().unicode_word_boundary(true))
+    ///     .build(r"\w+")?;
+    /// assert!(dfa.quit_bytes().is_empty());
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn quit_bytes(&self) -> ByteSet {
+        self.quitset
+    }
+
     /// Returns the memory usage, in bytes, of this lazy DFA.
     ///
     /// This does **not** include the stack size used up by this lazy DFA. To
     /// compute that, use `std::mem::size_of::<DFA>()`. This also does
     /// not include the size of the `Cache` used.
+    ///
+    /// This is the fixed cost of a lazy DFA, which is shared by every search
+    /// using it, regardless of how many caches (e.g., one per thread) are
+    /// created for it. The per-cache cost is reported by
+    /// [`Cache::memory_usage`]. Note that the NFA is shared via an `Arc`, so
+    /// its heap memory is included here even if it is also used elsewhere.
     pub fn memory_usage(&self) -> usize {
         // Everything else is on the stack.
         self.nfa.memory_usage()
     }
+
+    /// Returns a human readable rendering of every state currently stored
+    /// in the given cache, along with their transitions and the cached start
+    /// states.
+    ///
+    /// This is a debugging aid for inspecting what a lazy DFA has computed
+    /// so far, e.g., after a search reports an unexpected result. The format
+    /// of the output is not stable and may change in any release.
+    ///
+    /// Each state is shown with its index in the cache's transition table,
+    /// prefixed by indicators for the kind of state: `U` for the unknown
+    /// sentinel, `D` for dead, `Q` for quit, `>` for start and `*` for match.
+    /// The NFA states that make up each DFA state are shown after the index,
+    /// and its transitions are shown on the following line. Transitions that
+    /// have not been computed yet, as well as transitions to the dead state,
+    /// are omitted.
+    ///
+    /// The cache given must be used with this DFA. Otherwise, the output is
+    /// unspecified and this may panic.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use regex_automata::{hybrid::dfa::DFA, HalfMatch};
+    ///
+    /// let dfa = DFA::new("foo")?;
+    /// let mut cache = dfa.create_cache();
+    /// assert_eq!(
+    ///     Some(HalfMatch::must(0, 4)),
+    ///     dfa.find_leftmost_fwd(&mut cache, b"xfoo")?,
+    /// );
+    /// let dump = dfa.debug_cache(&cache);
+    /// assert!(dump.starts_with("hybrid::dfa::Cache("));
+    /// // The start state for a search beginning at offset 0 is shown with the
+    /// // start indicator, along with the match state entered at the end of
+    /// // the haystack.
+    /// assert!(dump.contains("> 000003:"));
+    /// assert!(dump.contains(" *000007:"));
+    /// assert!(dump.contains("START-GROUP(ALL)"));
+    /// assert!(dump.contains("Text => 000003"));
+    /// assert!(dump.contains("state count: 8"));
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn debug_cache(&self, cache: &Cache) -> String {
+        format!("{:?}", DebugCache(LazyRef::new(self, cache)))
+    }
 }
 
 impl DFA {
@@ -1007,7 +1126,53 @@ impl DFA {
         start: usize,
         end: usize,
     ) -> Result<Option<HalfMatch>, MatchError> {
-        search::find_earliest_rev(self, cache, pattern_id, bytes, start, end)
+        search::find_earliest_rev(
+            None, self, cache, pattern_id, bytes, start, end,
+        )
+    }
+
+    /// This is like [`DFA::find_earliest_rev_at`], except it also accepts a
+    /// prefilter to use during the search.
+    ///
+    /// A prefilter given to a reverse search is run via
+    /// [`Prefilter::find_rev`](crate::util::prefilter::Prefilter::find_rev),
+    /// and so it must report the positions at which matches may *end*. If
+    /// the prefilter does not support reverse searches, then it is ignored.
+    /// As with forward searches, a prefilter is never used when a
+    /// `pattern_id` is given, since such searches are always anchored.
+    /// Unlike forward searches, a prefilter is also never used when this
+    /// lazy DFA was built to only execute anchored searches.
+    ///
+    /// # Errors
+    ///
+    /// This routine only errors if the search could not complete. For
+    /// lazy DFAs generated by this crate, this only occurs in non-default
+    /// configurations where quit bytes are used, Unicode word boundaries are
+    /// heuristically enabled or limits are set on the number of times the lazy
+    /// DFA's cache may be cleared.
+    ///
+    /// When a search cannot complete, callers cannot know whether a match
+    /// exists or not.
+    ///
+    /// # Panics
+    ///
+    /// This routine panics if a `pattern_id` is given and the underlying
+    /// DFA does not support specific pattern searches.
+    ///
+    /// It also panics if the given haystack range is not valid.
+    #[inline]
+    pub fn find_earliest_rev_at_with_prefilter(
+        &self,
+        cache: &mut Cache,
+        pre: Option<&mut prefilter::Scanner>,
+        pattern_id: Option<PatternID>,
+        bytes: &[u8],
+        start: usize,
+        end: usize,
+    ) -> Result<Option<HalfMatch>, MatchError> {
+        search::find_earliest_rev(
+            pre, self, cache, pattern_id, bytes, start, end,
+        )
     }
 
     /// Executes a forward search and returns the end position of the leftmost
@@ -1085,7 +1250,53 @@ impl DFA {
         start: usize,
         end: usize,
     ) -> Result<Option<HalfMatch>, MatchError> {
-        search::find_leftmost_rev(self, cache, pattern_id, bytes, start, end)
+        search::find_leftmost_rev(
+            None, self, cache, pattern_id, bytes, start, end,
+        )
+    }
+
+    /// This is like [`DFA::find_leftmost_rev_at`], except it also accepts a
+    /// prefilter to use during the search.
+    ///
+    /// A prefilter given to a reverse search is run via
+    /// [`Prefilter::find_rev`](crate::util::prefilter::Prefilter::find_rev),
+    /// and so it must report the positions at which matches may *end*. If
+    /// the prefilter does not support reverse searches, then it is ignored.
+    /// As with forward searches, a prefilter is never used when a
+    /// `pattern_id` is given, since such searches are always anchored.
+    /// Unlike forward searches, a prefilter is also never used when this
+    /// lazy DFA was built to only execute anchored searches.
+    ///
+    /// # Errors
+    ///
+    /// This routine only errors if the search could not complete. For
+    /// lazy DFAs generated by this crate, this only occurs in non-default
+    /// configurations where quit bytes are used, Unicode word boundaries are
+    /// heuristically enabled or limits are set on the number of times the lazy
+    /// DFA's cache may be cleared.
+    ///
+    /// When a search cannot complete, callers cannot know whether a match
+    /// exists or not.
+    ///
+    /// # Panics
+    ///
+    /// This routine panics if a `pattern_id` is given and the underlying
+    /// DFA does not support specific pattern searches.
+    ///
+    /// It also panics if the given haystack range is not valid.
+    #[inline]
+    pub fn find_leftmost_rev_at_with_prefilter(
+        &self,
+        cache: &mut Cache,
+        pre: Option<&mut prefilter::Scanner>,
+        pattern_id: Option<PatternID>,
+        bytes: &[u8],
+        start: usize,
+        end: usize,
+    ) -> Result<Option<HalfMatch>, MatchError> {
+        search::find_leftmost_rev(
+            pre, self, cache, pattern_id, bytes, start, end,
+        )
     }
 
     /// Executes an overlapping forward search and returns the end position of
@@ -1610,6 +1821,82 @@ impl DFA {
         lazy.cache_start_group(pattern_id, start_type)
     }
 
+    /// Returns true if and only if every start state of this lazy DFA is the
+    /// same, regardless of where a search begins.
+    ///
+    /// This occurs precisely when the underlying NFA has no look-around
+    /// assertions. When this returns true, [`DFA::universal_start_state`]
+    /// may be used in place of [`DFA::start_state_forward`] and
+    /// [`DFA::start_state_reverse`].
+    #[inline]
+    pub fn has_universal_start_state(&self) -> bool {
+        !self.nfa.has_any_look()
+    }
+
+    /// Return the ID of the "universal" start state for this lazy DFA.
+    ///
+    /// When the underlying NFA has no look-around assertions, the start
+    /// state does not depend on the position at which a search begins. In
+    /// that case, this returns the same state as [`DFA::start_state_forward`]
+    /// (or [`DFA::start_state_reverse`] for a reverse DFA) with no pattern
+    /// ID, but without needing to inspect the haystack to determine the
+    /// starting configuration.
+    ///
+    /// Like the other start state routines, this may need to compute the
+    /// start state and add it to the cache, which may fail if the cache has
+    /// been cleared too many times.
+    ///
+    /// # Panics
+    ///
+    /// This panics if this lazy DFA does not have a universal start state.
+    /// That is, when [`DFA::has_universal_start_state`] returns false.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use regex_automata::hybrid::dfa::DFA;
+    ///
+    /// let dfa = DFA::new(r"\w")?;
+    /// let mut cache = dfa.create_cache();
+    ///
+    /// assert!(dfa.has_universal_start_state());
+    /// let sid = dfa.universal_start_state(&mut cache)?;
+    /// let haystack = b"a b";
+    /// for start in 0..=haystack.len() {
+    ///     let fwd = dfa.start_state_forward(
+    ///         &mut cache,
+    ///         None,
+    ///         haystack,
+    ///         start,
+    ///         haystack.len(),
+    ///     )?;
+    ///     assert_eq!(sid, fwd);
+    /// }
+    ///
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    #[inline]
+    pub fn universal_start_state(
+        &self,
+        cache: &mut Cache,
+    ) -> Result<LazyStateID, CacheError> {
+        assert!(
+            self.has_universal_start_state(),
+            "attempted to get a universal start state for a lazy DFA \
+             whose start state depends on look-around assertions",
+        );
+        // Any starting configuration would do, since they all produce the
+        // same state. We choose 'NonWordByte' since it is the 'main'
+        // starting configuration, which mirrors what the dense DFA does.
+        let mut lazy = Lazy::new(self, cache);
+        let start_type = Start::NonWordByte;
+        let sid = lazy.as_ref().get_cached_start_id(None, start_type);
+        if !sid.is_unknown() {
+            return Ok(sid);
+        }
+        lazy.cache_start_group(None, start_type)
+    }
+
     /// Returns the total number of patterns that match in this state.
     ///
     /// If the lazy DFA was compiled with one pattern, then this must
@@ -1809,6 +2096,20 @@ pub struct Cache {
     /// clear count is set, then the cache will return an error instead of
     /// clearing the cache if the count has been exceeded.
     clear_count: usize,
+    /// The number of non-sentinel states that have been added to this cache
+    /// since it was created or last reset. Unlike 'states', this includes
+    /// states that were dropped when the cache was cleared. When a maximum
+    /// total number of states is set, then the cache will return an error
+    /// instead of adding a new state once this reaches the maximum.
+    states_added: usize,
+    /// The progress of the search currently using this cache, if any. This
+    /// is used to compute the number of bytes searched for the
+    /// 'minimum_bytes_per_state' heuristic.
+    progress: Option<SearchProgress>,
+    /// The number of bytes searched by completed searches since the cache
+    /// was last cleared. This does not include the bytes searched by the
+    /// search in 'progress'.
+    bytes_searched: usize,
 }
 
 impl Cache {
@@ -1829,6 +2130,9 @@ impl Cache {
             state_saver: StateSaver::none(),
             memory_usage_state: 0,
             clear_count: 0,
+            states_added: 0,
+            progress: None,
+            bytes_searched: 0,
         };
         Lazy { dfa, cache: &mut cache }.init_cache();
         cache
@@ -1842,7 +2146,9 @@ impl Cache {
     ///
     /// Resetting a cache sets its "clear count" to 0. This is relevant if the
     /// lazy DFA has been configured to "give up" after it has cleared the
-    /// cache a certain number of times.
+    /// cache a certain number of times. It also resets the statistics used
+    /// by [`Cache::was_effective`] and the count of states checked against
+    /// [`Config::max_total_states`].
     ///
     /// Any lazy state ID generated by the cache prior to resetting it is
     /// invalid after the reset.
@@ -1881,6 +2187,54 @@ impl Cache {
         Lazy::new(dfa, self).reset_cache()
     }
 
+    /// Clear this cache of all states computed so far, while keeping it
+    /// bound to the given lazy DFA.
+    ///
+    /// This is useful for bounding memory usage between searches without
+    /// re-purposing the cache. No heap memory is released, but every cached
+    /// state is dropped and subsequent searches recompute the states they
+    /// need.
+    ///
+    /// The DFA given must be the one this cache is currently used with. A
+    /// cache does not keep a reference to its DFA, and the DFA is needed
+    /// to re-initialize the cache's sentinel and start states. To use the
+    /// cache with a different DFA, use [`Cache::reset`] instead.
+    ///
+    /// Unlike clearings that occur during a search, clearing a cache
+    /// explicitly does not count toward its
+    /// [clear count](Cache::clear_count). Namely, it does not bring a lazy
+    /// DFA configured with [`Config::minimum_cache_clear_count`] any closer
+    /// to giving up. Neither does it reset the count to zero, as
+    /// [`Cache::reset`] does.
+    ///
+    /// Any lazy state ID generated by the cache prior to clearing it is
+    /// invalid after the clear.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use regex_automata::{hybrid::dfa::DFA, HalfMatch};
+    ///
+    /// let dfa = DFA::new(r"\w+")?;
+    /// let mut cache = dfa.create_cache();
+    /// assert_eq!(
+    ///     Some(HalfMatch::must(0, 3)),
+    ///     dfa.find_leftmost_fwd(&mut cache, b"foo bar")?,
+    /// );
+    ///
+    /// cache.clear(&dfa);
+    /// assert_eq!(0, cache.clear_count());
+    /// assert_eq!(
+    ///     Some(HalfMatch::must(0, 3)),
+    ///     dfa.find_leftmost_fwd(&mut cache, b"foo bar")?,
+    /// );
+    ///
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn clear(&mut self, dfa: &DFA) {
+        Lazy::new(dfa, self).clear_cache_uncounted()
+    }
+
     /// Returns the total number of times this cache has been cleared since it
     /// was either created or last reset.
     ///
@@ -1891,6 +2245,111 @@ impl Cache {
         self.clear_count
     }
 
+    /// Returns true if this cache appears to have been effective for the
+    /// searches run with it since it was created or last reset.
+    ///
+    /// This is a heuristic. A cache is considered effective if it has never
+    /// been cleared during a search, or if, on average, it computed at least
+    /// some minimum number of states between clearings. A cache that is
+    /// cleared after computing only a few states is thrashing, which usually
+    /// means its capacity is too small for the regex and haystacks it is used
+    /// with. Callers may use this to decide to switch to a different regex
+    /// engine, such as a fully compiled DFA.
+    ///
+    /// Explicitly clearing a cache via [`Cache::clear`] does not influence
+    /// this heuristic. Resetting a cache via [`Cache::reset`] or
+    /// [`DFA::reset_cache`] returns it to its initial effective state.
+    ///
+    /// # Example
+    ///
+    /// This example uses the smallest possible cache to force it to thrash.
+    ///
+    /// ```
+    /// use regex_automata::hybrid::dfa::DFA;
+    ///
+    /// let dfa = DFA::builder()
+    ///     .configure(DFA::config()
+    ///         .skip_cache_capacity_check(true)
+    ///         .cache_capacity(0)
+    ///     )
+    ///     .build(r"[a-z]{50}")?;
+    /// let mut cache = dfa.create_cache();
+    /// assert!(cache.was_effective());
+    ///
+    /// let haystack = "a".repeat(100).into_bytes();
+    /// dfa.find_leftmost_fwd(&mut cache, &haystack)?;
+    /// assert!(cache.clear_count() > 0);
+    /// assert!(!cache.was_effective());
+    ///
+    /// cache.reset(&dfa);
+    /// assert_eq!(0, cache.clear_count());
+    /// assert!(cache.was_effective());
+    ///
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn was_effective(&self) -> bool {
+        if self.clear_count == 0 {
+            return true;
+        }
+        self.states_added / self.clear_count >= MIN_STATES_PER_CLEAR
+    }
+
+    /// Indicate that a search is starting at the given position.
+    ///
+    /// This, along with [`Cache::search_update`] and
+    /// [`Cache::search_finish`], is used to track the number of bytes
+    /// searched for the [`Config::minimum_bytes_per_state`] heuristic. The
+    /// search routines in this crate call these methods automatically, so
+    /// they only need to be used when implementing a custom search routine
+    /// on top of [`DFA::next_state`].
+    ///
+    /// If a previous search was started but never finished, then the bytes
+    /// it searched up to its last update are counted as searched.
+    pub fn search_start(&mut self, at: usize) {
+        if let Some(p) = self.progress.take() {
+            self.bytes_searched = self.bytes_searched.saturating_add(p.len());
+        }
+        self.progress = Some(SearchProgress { start: at, at });
+    }
+
+    /// Update the position of the search currently using this cache.
+    ///
+    /// A search should call this before any transition that may require
+    /// computing a new state, which is when the cache may be cleared. The
+    /// position may be less than the starting position, which is the case
+    /// for reverse searches.
+    ///
+    /// # Panics
+    ///
+    /// This panics if no search has been started via
+    /// [`Cache::search_start`].
+    #[inline]
+    pub fn search_update(&mut self, at: usize) {
+        let p = self.progress.as_mut().expect("no in-progress search");
+        p.at = at;
+    }
+
+    /// Indicate that the search currently using this cache has stopped at
+    /// the given position.
+    ///
+    /// # Panics
+    ///
+    /// This panics if no search has been started via
+    /// [`Cache::search_start`].
+    pub fn search_finish(&mut self, at: usize) {
+        let mut p = self.progress.take().expect("no in-progress search");
+        p.at = at;
+        self.bytes_searched = self.bytes_searched.saturating_add(p.len());
+    }
+
+    /// Returns the total number of bytes searched with this cache since it
+    /// was last cleared, including the bytes searched so far by the search
+    /// in progress.
+    pub fn search_total_len(&self) -> usize {
+        let in_progress = self.progress.as_ref().map_or(0, |p| p.len());
+        self.bytes_searched.saturating_add(in_progress)
+    }
+
     /// Returns the heap memory usage, in bytes, of this cache.
     ///
     /// This does **not** include the stack size used up by this cache. To
@@ -1912,6 +2371,27 @@ impl Cache {
     }
 }
 
+/// The progress of a search using a lazy DFA cache.
+///
+/// Positions are absolute offsets into the haystack. For forward searches,
+/// 'at' only increases from 'start'. For reverse searches, it only decreases.
+#[derive(Clone, Debug)]
+struct SearchProgress {
+    start: usize,
+    at: usize,
+}
+
+impl SearchProgress {
+    /// Returns the number of bytes searched so far.
+    fn len(&self) -> usize {
+        if self.start <= self.at {
+            self.at - self.start
+        } else {
+            self.start - self.at
+        }
+    }
+}
+
 /// A map from states to state identifiers. When using std, we use a standard
 /// hashmap, since it's a bit faster for this use case. (Other maps, like
 /// one's based on FNV, have not yet been benchmarked.)
@@ -2042,7 +2522,16 @@ impl<'i, 'c> Lazy<'i, 'c> {
         start: Start,
     ) -> Result<LazyStateID, CacheError> {
         let mut builder_matches = self.get_state_builder().into_matches();
-        determinize::set_lookbehind_from_start(&start, &mut builder_matches);
+        // When the NFA has no look-around assertions, the look-behind
+        // context is irrelevant. Leaving it out means every starting
+        // configuration maps to the same state, which is what makes
+        // 'DFA::universal_start_state' possible.
+        if self.dfa.nfa.has_any_look() {
+            determinize::set_lookbehind_from_start(
+                &start,
+                &mut builder_matches,
+            );
+        }
         self.cache.sparses.set1.clear();
         determinize::epsilon_closure(
             self.dfa.nfa.borrow(),
@@ -2084,6 +2573,18 @@ impl<'i, 'c> Lazy<'i, 'c> {
             self.put_state_builder(builder);
             return Ok(cached_id);
         }
+        if let Some(max) = self.dfa.max_total_states {
+            if self.cache.states_added >= max {
+                trace!(
+                    "lazy DFA cache has computed {} states, which meets the \
+                     maximum of {}",
+                    self.cache.states_added,
+                    max,
+                );
+                self.put_state_builder(builder);
+                return Err(CacheError::too_many_states());
+            }
+        }
         let result = self.add_state(builder.to_state(), idmap);
         self.put_state_builder(builder);
         result
@@ -2133,6 +2634,9 @@ impl<'i, 'c> Lazy<'i, 'c> {
                 self.set_transition(id, alphabet::Unit::u8(b), quit_id);
             }
         }
+        if !self.as_ref().is_sentinel(id) {
+            self.cache.states_added += 1;
+        }
         self.cache.memory_usage_state += state.memory_usage();
         self.cache.states.push(state.clone());
         self.cache.states_to_id.insert(state, id);
@@ -2175,23 +2679,36 @@ impl<'i, 'c> Lazy<'i, 'c> {
     /// Otherwise, any lazy state ID generated by the cache prior to resetting
     /// it is invalid after the reset.
     fn try_clear_cache(&mut self) -> Result<(), CacheError> {
-        // Currently, the only heuristic we use is the minimum cache clear
-        // count. If we pass that minimum, then we give up.
+        // We use two heuristics. The first is the minimum cache clear count.
+        // If we pass that minimum, then we give up.
         //
-        // It would be good to also add a heuristic based on "bytes searched
-        // per generated state," but this requires API design work. Namely,
-        // we really do not want to add a counter increment to the transition
-        // function, which implies we need to expose APIs to update the number
-        // of bytes searched by implementers of the search routines. And that
-        // doesn't seem great... But we should do it if this heuristic isn't
-        // enough. (The original lazy DFA implementation in the 'regex' crate
-        // had this heuristic, since the lazy DFA was coupled with the search
-        // routines.)
+        // The second is the number of bytes searched per cached state. We
+        // don't want to add a counter increment to the transition function,
+        // so instead, search routines report their progress via the
+        // 'search_*' methods on the cache before any transition that might
+        // need to compute a new state. (The original lazy DFA implementation
+        // in the 'regex' crate had this heuristic too, but there the lazy DFA
+        // was coupled with the search routines.)
         if let Some(min_count) = self.dfa.minimum_cache_clear_count {
             if self.cache.clear_count >= min_count {
                 return Err(CacheError::too_many_cache_clears());
             }
         }
+        if let Some(min_bytes_per) = self.dfa.minimum_bytes_per_state {
+            let len = self.cache.search_total_len();
+            let min_bytes =
+                min_bytes_per.saturating_mul(self.cache.states.len());
+            if len < min_bytes {
+                trace!(
+                    "lazy DFA cache is inefficient: searched {} bytes \
+                     with {} states, but the minimum is {} bytes per state",
+                    len,
+                    self.cache.states.len(),
+                    min_bytes_per,
+                );
+                return Err(CacheError::too_few_bytes_per_state());
+            }
+        }
         self.clear_cache();
         Ok(())
     }
@@ -2211,6 +2728,21 @@ impl<'i, 'c> Lazy<'i, 'c> {
         // size.
         self.cache.sparses.resize(self.dfa.nfa.len());
         self.cache.clear_count = 0;
+        self.cache.states_added = 0;
+        self.cache.progress = None;
+    }
+
+    /// Clears the cache without persisting any state and without counting
+    /// toward the clear count.
+    ///
+    /// This is used for clearing a cache outside of a search, where there is
+    /// no state to save and the clearing isn't a symptom of the cache
+    /// thrashing.
+    fn clear_cache_uncounted(&mut self) {
+        self.cache.state_saver = StateSaver::none();
+        let clear_count = self.cache.clear_count;
+        self.clear_cache();
+        self.cache.clear_count = clear_count;
     }
 
     /// Clear the cache used by this lazy DFA.
@@ -2236,6 +2768,10 @@ impl<'i, 'c> Lazy<'i, 'c> {
         self.cache.states_to_id.clear();
         self.cache.memory_usage_state = 0;
         self.cache.clear_count += 1;
+        self.cache.bytes_searched = 0;
+        if let Some(ref mut p) = self.cache.progress {
+            p.start = p.at;
+        }
         trace!(
             "lazy DFA cache has been cleared (count: {})",
             self.cache.clear_count
@@ -2460,11 +2996,19 @@ impl<'i, 'c> LazyRef<'i, 'c> {
         let index = match pattern_id {
             None => start_index,
             Some(pid) => {
+                assert!(
+                    self.dfa.starts_for_each_pattern,
+                    "attempted to search for a specific pattern \
+                     without enabling starts_for_each_pattern",
+                );
                 let pid = pid.as_usize();
+                let count = self.dfa.pattern_count();
                 assert!(
-                    pid < self.dfa.pattern_count(),
-                    "invalid pattern ID: {:?}",
-                    pid
+                    pid < count,
+                    "invalid pattern ID {}; regex has {} pattern{}",
+                    pid,
+                    count,
+                    if count == 1 { "" } else { "s" },
                 );
                 Start::count() + (Start::count() * pid) + start_index
             }
@@ -2558,6 +3102,126 @@ impl<'i, 'c> LazyRef<'i, 'c> {
     }
 }
 
+/// A wrapper for rendering the contents of a lazy DFA's cache. This is the
+/// implementation of 'DFA::debug_cache'.
+struct DebugCache<'i, 'c>(LazyRef<'i, 'c>);
+
+impl<'i, 'c> core::fmt::Debug for DebugCache<'i, 'c> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let (dfa, cache) = (self.0.dfa, self.0.cache);
+        let stride2 = dfa.stride2();
+        writeln!(f, "hybrid::dfa::Cache(")?;
+        for (index, state) in cache.states.iter().enumerate() {
+            // This unwrap is OK since every state in the cache was given an
+            // ID when it was added.
+            let sid = LazyStateID::new(index << stride2).unwrap();
+            let id = match index {
+                0 => self.0.unknown_id(),
+                1 => self.0.dead_id(),
+                2 => self.0.quit_id(),
+                _ => cache.states_to_id.get(state).copied().unwrap_or(sid),
+            };
+            let kind = if id.is_unknown() {
+                "U "
+            } else if id.is_dead() {
+                "D "
+            } else if id.is_quit() {
+                "Q "
+            } else {
+                match (id.is_start(), id.is_match()) {
+                    (true, true) => ">*",
+                    (true, false) => "> ",
+                    (false, true) => " *",
+                    (false, false) => "  ",
+                }
+            };
+            writeln!(f, "{}{:06?}: {:?}", kind, index, state)?;
+            if self.0.is_sentinel(id) {
+                continue;
+            }
+            let units = (0..=255)
+                .map(alphabet::Unit::u8)
+                .chain(iter::once(dfa.classes.eoi()));
+            let mut cur: Option<(
+                alphabet::Unit,
+                alphabet::Unit,
+                LazyStateID,
+            )> = None;
+            let mut first = true;
+            for unit in units {
+                let class = dfa.classes.get_by_unit(unit);
+                let next = cache.trans[id.as_usize_untagged() + class];
+                if let Some((start, end, prev)) = cur {
+                    if prev == next && !unit.is_eoi() {
+                        cur = Some((start, unit, prev));
+                        continue;
+                    }
+                    fmt_transition(f, &mut first, start, end, prev, stride2)?;
+                }
+                cur = Some((unit, unit, next));
+            }
+            if let Some((start, end, next)) = cur {
+                fmt_transition(f, &mut first, start, end, next, stride2)?;
+            }
+            if !first {
+                writeln!(f, "")?;
+            }
+        }
+        writeln!(f, "")?;
+        for (i, &id) in cache.starts.iter().enumerate() {
+            if i % Start::count() == 0 {
+                match i / Start::count() {
+                    0 => writeln!(f, "START-GROUP(ALL)")?,
+                    n => writeln!(f, "START-GROUP(pattern: {:?})", n - 1)?,
+                }
+            }
+            if id.is_unknown() {
+                continue;
+            }
+            // This unwrap is OK since the index is always less than
+            // 'Start::count()'.
+            let start = Start::from_usize(i % Start::count()).unwrap();
+            writeln!(
+                f,
+                "  {:?} => {:06?}",
+                start,
+                id.as_usize_untagged() >> stride2
+            )?;
+        }
+        writeln!(f, "state count: {:?}", cache.states.len())?;
+        writeln!(f, ")")?;
+        Ok(())
+    }
+}
+
+/// Writes a single range of transitions to the same lazy state, for use in
+/// 'DebugCache'. Transitions to unknown or dead states are skipped. The first
+/// transition written for a state is indented onto its own line.
+fn fmt_transition(
+    f: &mut core::fmt::Formatter<'_>,
+    first: &mut bool,
+    start: alphabet::Unit,
+    end: alphabet::Unit,
+    next: LazyStateID,
+    stride2: usize,
+) -> core::fmt::Result {
+    if next.is_unknown() || next.is_dead() {
+        return Ok(());
+    }
+    if *first {
+        write!(f, "          ")?;
+    } else {
+        write!(f, ", ")?;
+    }
+    *first = false;
+    let index = next.as_usize_untagged() >> stride2;
+    if start == end {
+        write!(f, "{:?} => {:?}", start, index)
+    } else {
+        write!(f, "{:?}-{:?} => {:?}", start, end, index)
+    }
+}
+
 /// A simple type that encapsulates the saving of a state ID through a cache
 /// clearing.
 ///
@@ -2623,10 +3287,13 @@ impl StateSaver {
 /// The default configuration guarantees that a search will _never_ return
 /// a [`MatchError`] for any haystack or pattern. Setting a quit byte with
 /// [`Config::quit`], enabling heuristic support for Unicode word boundaries
-/// with [`Config::unicode_word_boundary`], or setting a minimum cache clear
-/// count with [`Config::minimum_cache_clear_count`] can in turn cause a search
-/// to return an error. See the corresponding configuration options for more
-/// details on when those error conditions arise.
+/// with [`Config::unicode_word_boundary`], setting a minimum cache clear
+/// count with [`Config::minimum_cache_clear_count`], setting a minimum
+/// number of bytes per state with [`Config::minimum_bytes_per_state`] or
+/// setting a maximum total number of states with
+/// [`Config::max_total_states`] can in turn cause a search to return an
+/// error. See the corresponding configuration options for more details on
+/// when those error conditions arise.
 #[derive(Clone, Copy, Debug, Default)]
 pub struct Config {
     // As with other configuration types in this crate, we put all our knobs
@@ -2645,6 +3312,8 @@ pub struct Config {
     cache_capacity: Option<usize>,
     skip_cache_capacity_check: Option<bool>,
     minimum_cache_clear_count: Option<Option<usize>>,
+    minimum_bytes_per_state: Option<Option<usize>>,
+    max_total_states: Option<Option<usize>>,
 }
 
 impl Config {
@@ -3007,7 +3676,8 @@ impl Config {
     /// This is almost equivalent to setting all non-ASCII bytes to be quit
     /// bytes. The only difference is that this will cause non-ASCII bytes to
     /// be quit bytes _only_ when a Unicode word boundary is present in the
-    /// pattern.
+    /// pattern. The quit bytes that a lazy DFA ended up with can be inspected
+    /// with [`DFA::quit_bytes`].
     ///
     /// When enabling this option, callers _must_ be prepared to handle
     /// a [`MatchError`](crate::MatchError) error during search.
@@ -3348,6 +4018,124 @@ impl Config {
         self
     }
 
+    /// Configure a lazy DFA search to quit when the cache is used
+    /// inefficiently, as measured by the number of bytes searched per cached
+    /// state.
+    ///
+    /// When the cache fills up and needs to be cleared, the lazy DFA
+    /// computes the number of bytes searched since the cache was last
+    /// cleared (or created) and divides it by the number of states in the
+    /// cache. If the result is less than the minimum given here, then the
+    /// search gives up instead of clearing the cache. That is, the lazy DFA
+    /// was spending most of its time computing states rather than reusing
+    /// them, and a search on the remainder of the haystack is likely to be
+    /// just as slow. In that case, callers should use a different regex
+    /// engine.
+    ///
+    /// Bytes searched are only counted by the search routines in this crate,
+    /// or by custom search routines that call [`Cache::search_start`],
+    /// [`Cache::search_update`] and [`Cache::search_finish`].
+    ///
+    /// This heuristic is independent of
+    /// [`Config::minimum_cache_clear_count`]. When both are set, a search
+    /// gives up if either one of them says to.
+    ///
+    /// By default, no minimum is configured. Thus, a lazy DFA search will
+    /// never give up due to the number of bytes searched per state.
+    ///
+    /// # Example
+    ///
+    /// This example uses a tiny cache and a regex that needs a new state for
+    /// nearly every byte searched, which causes the cache to thrash. With a
+    /// minimum number of bytes per state set, the search gives up instead.
+    ///
+    /// ```
+    /// use regex_automata::{hybrid::dfa::DFA, MatchError};
+    ///
+    /// let dfa = DFA::builder()
+    ///     .configure(DFA::config()
+    ///         .skip_cache_capacity_check(true)
+    ///         .cache_capacity(0)
+    ///         .minimum_bytes_per_state(Some(10))
+    ///     )
+    ///     .build(r"[a-z]{100}")?;
+    /// let mut cache = dfa.create_cache();
+    ///
+    /// let haystack = "a".repeat(200).into_bytes();
+    /// assert!(matches!(
+    ///     dfa.find_leftmost_fwd(&mut cache, &haystack),
+    ///     Err(MatchError::GaveUp { .. }),
+    /// ));
+    ///
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn minimum_bytes_per_state(mut self, min: Option<usize>) -> Config {
+        self.minimum_bytes_per_state = Some(min);
+        self
+    }
+
+    /// Configure a lazy DFA search to quit once its cache has computed the
+    /// given number of states in total.
+    ///
+    /// Unlike the [cache capacity](Config::cache_capacity), which bounds the
+    /// number of states held in the cache at any one time, this bounds the
+    /// total number of states ever computed by a cache, including states
+    /// that were dropped when the cache was cleared. That is, it puts a hard
+    /// ceiling on the amount of determinization work a cache will do. This
+    /// is useful when searching with untrusted regexes, some of which may
+    /// require a huge number of states. When the ceiling is reached, a search
+    /// gives up instead of computing a new state, and callers can fall back
+    /// to a different regex engine.
+    ///
+    /// Every state added to the cache counts toward this total, including
+    /// start states. Thus, a maximum of `0` causes every search to give up
+    /// before it even begins. Sentinel states are not counted. A state that
+    /// is kept across a cache clearing in order to continue a search is
+    /// counted again, since it is re-added to the cache.
+    ///
+    /// Like the [clear count](Cache::clear_count), the total number of
+    /// states is a property of the cache and is only reset to `0` via
+    /// [`DFA::reset_cache`] (or
+    /// [`Regex::reset_cache`](crate::hybrid::regex::Regex::reset_cache) if
+    /// you're using the `Regex` API).
+    ///
+    /// This heuristic is independent of
+    /// [`Config::minimum_cache_clear_count`] and
+    /// [`Config::minimum_bytes_per_state`]. When more than one is set, a
+    /// search gives up if any one of them says to.
+    ///
+    /// By default, no maximum is configured. Thus, a lazy DFA search will
+    /// never give up due to the total number of states it has computed.
+    ///
+    /// # Example
+    ///
+    /// This example uses a regex whose DFA has an exponential number of
+    /// states. Searching a haystack that contains many different strings of
+    /// bits requires computing many of them, which exceeds the ceiling.
+    ///
+    /// ```
+    /// use regex_automata::{hybrid::dfa::DFA, MatchError};
+    ///
+    /// let dfa = DFA::builder()
+    ///     .configure(DFA::config().max_total_states(Some(50)))
+    ///     .build(r"[01]*1[01]{10}$")?;
+    /// let mut cache = dfa.create_cache();
+    ///
+    /// // All 12 bit strings, concatenated.
+    /// let haystack: String =
+    ///     (0..4096).map(|n| format!("{:012b}", n)).collect();
+    /// assert!(matches!(
+    ///     dfa.find_leftmost_fwd(&mut cache, haystack.as_bytes()),
+    ///     Err(MatchError::GaveUp { .. }),
+    /// ));
+    ///
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn max_total_states(mut self, max: Option<usize>) -> Config {
+        self.max_total_states = Some(max);
+        self
+    }
+
     /// Returns whether this configuration has enabled anchored searches.
     pub fn get_anchored(&self) -> bool {
         self.anchored.unwrap_or(false)
@@ -3404,6 +4192,22 @@ impl Config {
         self.minimum_cache_clear_count.unwrap_or(None)
     }
 
+    /// Returns, if set, the minimum number of bytes that must be searched
+    /// per cached state in order for the cache to be cleared. When no
+    /// minimum is set, then a search will never quit because of the number
+    /// of bytes searched per state.
+    pub fn get_minimum_bytes_per_state(&self) -> Option<usize> {
+        self.minimum_bytes_per_state.unwrap_or(None)
+    }
+
+    /// Returns, if set, the maximum total number of states that a cache may
+    /// compute before a lazy DFA search gives up. When no maximum is set,
+    /// then a search will never quit because of the number of states it has
+    /// computed.
+    pub fn get_max_total_states(&self) -> Option<usize> {
+        self.max_total_states.unwrap_or(None)
+    }
+
     /// Returns the minimum lazy DFA cache capacity required for the given NFA.
     ///
     /// The cache capacity required for a particular NFA may change without
@@ -3510,6 +4314,10 @@ impl Config {
             minimum_cache_clear_count: o
                 .minimum_cache_clear_count
                 .or(self.minimum_cache_clear_count),
+            minimum_bytes_per_state: o
+                .minimum_bytes_per_state
+                .or(self.minimum_bytes_per_state),
+            max_total_states: o.max_total_states.or(self.max_total_states),
         }
     }
 }
@@ -3654,6 +4462,15 @@ impl Builder {
     ) -> Result<DFA, BuildError> {
         let quitset = self.config.quit_set_from_nfa(&nfa)?;
         let classes = self.config.byte_classes_from_nfa(&nfa, &quitset);
+        // Record the quit bytes that were only added to support Unicode word
+        // boundaries heuristically, so that quitting on them can be told
+        // apart from quitting on a byte the caller asked for.
+        let mut unicode_word_quitset = quitset;
+        if let Some(ref userset) = self.config.quitset {
+            for b in userset.iter() {
+                unicode_word_quitset.remove(b);
+            }
+        }
         // Check that we can fit at least a few states into our cache,
         // otherwise it's pretty senseless to use the lazy DFA. This does have
         // a possible failure mode though. This assumes the maximum size of a
@@ -3703,6 +4520,7 @@ impl Builder {
             stride2,
             classes,
             quitset,
+            unicode_word_quitset,
             anchored: self.config.get_anchored(),
             match_kind: self.config.get_match_kind(),
             starts_for_each_pattern: self.config.get_starts_for_each_pattern(),
@@ -3710,6 +4528,8 @@ impl Builder {
             minimum_cache_clear_count: self
                 .config
                 .get_minimum_cache_clear_count(),
+            minimum_bytes_per_state: self.config.get_minimum_bytes_per_state(),
+            max_total_states: self.config.get_max_total_states(),
         })
     }
 
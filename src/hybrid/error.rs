@@ -23,6 +23,34 @@ enum BuildErrorKind {
 }
 
 impl BuildError {
+    /// Returns true if and only if this error occurred because a pattern
+    /// could not be parsed.
+    pub fn is_syntax_error(&self) -> bool {
+        match self.kind() {
+            BuildErrorKind::NFA(ref err) => err.is_syntax_error(),
+            _ => false,
+        }
+    }
+
+    /// Returns true if and only if this error occurred because the heap
+    /// usage of the NFA exceeded the configured size limit.
+    pub fn is_size_limit(&self) -> bool {
+        self.size_limit().is_some()
+    }
+
+    /// Returns the size limit, in bytes, that was exceeded if this error
+    /// occurred because the heap usage of the NFA exceeded it. Otherwise,
+    /// this returns `None`.
+    ///
+    /// Note that an insufficient cache capacity is not considered a size
+    /// limit error.
+    pub fn size_limit(&self) -> Option<usize> {
+        match self.kind() {
+            BuildErrorKind::NFA(ref err) => err.size_limit(),
+            _ => None,
+        }
+    }
+
     fn kind(&self) -> &BuildErrorKind {
         &self.kind
     }
@@ -103,16 +131,36 @@ impl core::fmt::Display for BuildError {
 /// The default configuration of a lazy DFA in this crate is
 /// set such that a `CacheError` will never occur. Instead,
 /// callers must opt into this behavior with settings like
-/// [`dfa::Config::minimum_cache_clear_count`](crate::hybrid::dfa::Config::minimum_cache_clear_count).
+/// [`dfa::Config::minimum_cache_clear_count`](crate::hybrid::dfa::Config::minimum_cache_clear_count),
+/// [`dfa::Config::minimum_bytes_per_state`](crate::hybrid::dfa::Config::minimum_bytes_per_state)
+/// and
+/// [`dfa::Config::max_total_states`](crate::hybrid::dfa::Config::max_total_states).
 ///
 /// When the `std` feature is enabled, this implements the `std::error::Error`
 /// trait.
 #[derive(Clone, Debug)]
-pub struct CacheError(());
+pub struct CacheError {
+    kind: CacheErrorKind,
+}
+
+#[derive(Clone, Debug)]
+enum CacheErrorKind {
+    TooManyCacheClears,
+    TooFewBytesPerState,
+    TooManyStates,
+}
 
 impl CacheError {
     pub(crate) fn too_many_cache_clears() -> CacheError {
-        CacheError(())
+        CacheError { kind: CacheErrorKind::TooManyCacheClears }
+    }
+
+    pub(crate) fn too_few_bytes_per_state() -> CacheError {
+        CacheError { kind: CacheErrorKind::TooFewBytesPerState }
+    }
+
+    pub(crate) fn too_many_states() -> CacheError {
+        CacheError { kind: CacheErrorKind::TooManyStates }
     }
 }
 
@@ -125,6 +173,19 @@ impl std::error::Error for CacheError {
 
 impl core::fmt::Display for CacheError {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-        write!(f, "lazy DFA cache has been cleared too many times")
+        match self.kind {
+            CacheErrorKind::TooManyCacheClears => {
+                write!(f, "lazy DFA cache has been cleared too many times")
+            }
+            CacheErrorKind::TooFewBytesPerState => write!(
+                f,
+                "lazy DFA cache has been cleared after searching too few \
+                 bytes per cached state",
+            ),
+            CacheErrorKind::TooManyStates => write!(
+                f,
+                "lazy DFA cache has computed the maximum number of states",
+            ),
+        }
     }
 }
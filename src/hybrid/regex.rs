@@ -16,16 +16,17 @@ See the [parent module](crate::hybrid) for examples.
 
 use core::borrow::Borrow;
 
-use alloc::boxed::Box;
+use alloc::{boxed::Box, sync::Arc};
 
 use crate::{
     hybrid::{
         dfa::{self, DFA},
         error::BuildError,
-        OverlappingState,
+        search, OverlappingState,
     },
-    nfa::thompson,
+    nfa::thompson::{self, pikevm},
     util::{
+        alphabet::ByteSet,
         matchtypes::{MatchError, MatchKind, MultiMatch},
         prefilter::{self, Prefilter},
     },
@@ -155,7 +156,7 @@ use crate::{
 pub struct Regex {
     /// An optional prefilter that is passed down to the lazy DFA search
     /// routines when present. By default, no prefilter is set.
-    pre: Option<Box<dyn Prefilter>>,
+    pre: Option<Box<dyn Prefilter + Send + Sync>>,
     /// The forward lazy DFA. This can only find the end of a match.
     forward: DFA,
     /// The reverse lazy DFA. This can only find the start of a match.
@@ -172,6 +173,22 @@ pub struct Regex {
     /// Whether iterators on this type should advance by one codepoint or one
     /// byte when an empty match is seen.
     utf8: bool,
+    /// A PikeVM used to resolve Unicode word boundaries on non-ASCII text.
+    ///
+    /// This is only built when the forward lazy DFA gives up on non-ASCII
+    /// bytes because of [`dfa::Config::unicode_word_boundary`] and uses
+    /// leftmost-first match semantics, which is what the PikeVM supports.
+    /// It shares its NFA with the forward lazy DFA.
+    pikevm: Option<pikevm::PikeVM>,
+    /// A process-wide unique identifier for this regex. A [`CachePool`] uses
+    /// this to determine whether a cache it hands out needs to be reset.
+    ///
+    /// This only exists when `std` is enabled because nothing but the pool
+    /// (which requires `std`) uses it, and generating it requires an atomic
+    /// read-modify-write that isn't available on every no-std target. It
+    /// has no effect on searching, so a regex behaves identically either way.
+    #[cfg(feature = "std")]
+    id: usize,
 }
 
 /// Convenience routines for regex and cache construction.
@@ -351,8 +368,7 @@ impl Regex {
     /// # Ok::<(), Box<dyn std::error::Error>>(())
     /// ```
     pub fn reset_cache(&self, cache: &mut Cache) {
-        self.forward().reset_cache(&mut cache.forward);
-        self.reverse().reset_cache(&mut cache.reverse);
+        cache.reset(self);
     }
 }
 
@@ -913,6 +929,54 @@ impl Regex {
         self.try_find_earliest_at(cache, haystack, 0, haystack.len())
     }
 
+    /// Returns the end offset of the earliest match, if one exists.
+    ///
+    /// This stops scanning input in the same circumstances as
+    /// [`try_find_earliest`](Regex::try_find_earliest), but unlike that
+    /// routine, it never runs the reverse DFA to find where the match
+    /// started. It is therefore the cheapest way to learn where a match
+    /// first concludes.
+    ///
+    /// # Errors
+    ///
+    /// This routine only errors if the search could not complete. For
+    /// DFA-based regexes, this only occurs in a non-default configuration
+    /// where quit bytes are used, Unicode word boundaries are heuristically
+    /// enabled or limits are set on the number of times the lazy DFA's cache
+    /// may be cleared.
+    ///
+    /// When a search cannot complete, callers cannot know whether a match
+    /// exists or not.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use regex_automata::hybrid::regex::Regex;
+    ///
+    /// let re = Regex::new(r"foo[0-9]+")?;
+    /// let mut cache = re.create_cache();
+    /// // A leftmost search would report an end offset of 8.
+    /// assert_eq!(Some(4), re.try_shortest_match(&mut cache, b"foo12345")?);
+    ///
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn try_shortest_match(
+        &self,
+        cache: &mut Cache,
+        haystack: &[u8],
+    ) -> Result<Option<usize>, MatchError> {
+        self.forward()
+            .find_earliest_fwd_at(
+                &mut cache.forward,
+                self.scanner().as_mut(),
+                None,
+                haystack,
+                0,
+                haystack.len(),
+            )
+            .map(|m| m.map(|hm| hm.offset()))
+    }
+
     /// Returns the start and end offset of the leftmost match. If no match
     /// exists, then `None` is returned.
     ///
@@ -1019,6 +1083,53 @@ impl Regex {
         TryFindLeftmostMatches::new(self, cache, haystack)
     }
 
+    /// Returns an iterator over all non-overlapping leftmost matches in each
+    /// line of the given haystack.
+    ///
+    /// Each line is searched as if it were its own haystack, so `^` and `$`
+    /// match at the beginning and end of every line and no match ever spans
+    /// more than one line. Lines are terminated by `\n`, which is not
+    /// included in the line searched, and a final line need not end with
+    /// `\n`. The offsets of each match returned are relative to the entire
+    /// haystack.
+    ///
+    /// # Errors
+    ///
+    /// This iterator only yields errors if the search could not complete. For
+    /// DFA-based regexes, this only occurs in a non-default configuration
+    /// where quit bytes are used, Unicode word boundaries are heuristically
+    /// enabled or limits are set on the number of times the lazy DFA's cache
+    /// may be cleared.
+    ///
+    /// When a search cannot complete, callers cannot know whether a match
+    /// exists or not.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use regex_automata::{hybrid::regex::Regex, MultiMatch};
+    ///
+    /// let re = Regex::new(r"^[a-z]+$")?;
+    /// let mut cache = re.create_cache();
+    /// let haystack = b"foo\nbar baz\n\nquux";
+    /// let matches: Vec<MultiMatch> = re
+    ///     .try_find_lines_iter(&mut cache, haystack)
+    ///     .collect::<Result<_, _>>()?;
+    /// assert_eq!(matches, vec![
+    ///     MultiMatch::must(0, 0, 3),
+    ///     MultiMatch::must(0, 13, 17),
+    /// ]);
+    ///
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn try_find_lines_iter<'r, 'c, 't>(
+        &'r self,
+        cache: &'c mut Cache,
+        haystack: &'t [u8],
+    ) -> TryFindLineMatches<'r, 'c, 't> {
+        TryFindLineMatches::new(self, cache, haystack)
+    }
+
     /// Returns an iterator over all overlapping matches in the given haystack.
     ///
     /// This routine is principally useful when searching for multiple patterns
@@ -1362,6 +1473,177 @@ impl Regex {
     }
 }
 
+/// Search routines that resolve Unicode word boundaries on non-ASCII text.
+impl Regex {
+    /// Returns the start and end offset of the leftmost match, correctly
+    /// handling Unicode word boundaries even when the haystack contains
+    /// non-ASCII text. If no match exists, then `None` is returned.
+    ///
+    /// Lazy DFAs cannot implement Unicode word boundaries directly. When
+    /// they are heuristically enabled via
+    /// [`dfa::Config::unicode_word_boundary`], the lazy DFA instead treats
+    /// `\b` as an ASCII word boundary and gives up upon seeing any non-ASCII
+    /// byte. Instead of reporting that as an error, this routine resolves the
+    /// search by simulating the regex's NFA from where the lazy DFA started,
+    /// which determines whether each word boundary matches by decoding the
+    /// codepoints on either side of it. Once the simulation has moved past
+    /// the non-ASCII text without finding a match or any partial match that
+    /// is still in progress, the search resumes with the lazy DFAs. So lazy
+    /// DFAs are used everywhere except around non-ASCII text.
+    ///
+    /// This only resolves the search when the regex contains a Unicode word
+    /// boundary, uses [`MatchKind::LeftmostFirst`] semantics and the lazy DFA
+    /// either quits on a non-ASCII byte because of the heuristic or would
+    /// need to consult a non-ASCII byte just outside of the search range. In
+    /// particular, quitting on a byte given to [`dfa::Config::quit`] is
+    /// still reported as an error, even if that byte is not ASCII.
+    ///
+    /// # Errors
+    ///
+    /// This routine errors in the same circumstances as
+    /// [`Regex::try_find_leftmost`], except for the case described above.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use regex_automata::{
+    ///     hybrid::{dfa, regex::Regex},
+    ///     MatchError, MultiMatch,
+    /// };
+    ///
+    /// let re = Regex::builder()
+    ///     .dfa(dfa::Config::new().unicode_word_boundary(true))
+    ///     .build(r"\bmot\b")?;
+    /// let mut cache = re.create_cache();
+    ///
+    /// let haystack = "café mot".as_bytes();
+    /// // The standard search routines give up on non-ASCII text...
+    /// assert!(matches!(
+    ///     re.try_find_leftmost(&mut cache, haystack),
+    ///     Err(MatchError::Quit { .. }),
+    /// ));
+    /// // ... but this one does not.
+    /// assert_eq!(
+    ///     Some(MultiMatch::must(0, 6, 9)),
+    ///     re.try_find_leftmost_unicode(&mut cache, haystack)?,
+    /// );
+    ///
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn try_find_leftmost_unicode(
+        &self,
+        cache: &mut Cache,
+        haystack: &[u8],
+    ) -> Result<Option<MultiMatch>, MatchError> {
+        self.try_find_leftmost_unicode_at(cache, haystack, 0, haystack.len())
+    }
+
+    /// Returns the start and end offset of the leftmost match within the
+    /// given range of `haystack`, correctly handling Unicode word boundaries
+    /// even when the haystack contains non-ASCII text. If no match exists,
+    /// then `None` is returned.
+    ///
+    /// This is like [`Regex::try_find_leftmost_unicode`], but permits
+    /// searching a substring of `haystack` while still taking the
+    /// surrounding context into account for look-around.
+    ///
+    /// # Errors
+    ///
+    /// This routine errors in the same circumstances as
+    /// [`Regex::try_find_leftmost_at`], except when the search is resolved
+    /// via NFA simulation as described in
+    /// [`Regex::try_find_leftmost_unicode`].
+    pub fn try_find_leftmost_unicode_at(
+        &self,
+        cache: &mut Cache,
+        haystack: &[u8],
+        start: usize,
+        end: usize,
+    ) -> Result<Option<MultiMatch>, MatchError> {
+        let vm = match self.pikevm {
+            None => {
+                return self.try_find_leftmost_at(cache, haystack, start, end)
+            }
+            Some(ref vm) => vm,
+        };
+        // The lazy DFAs compute their start states from the bytes just
+        // outside of the search range without quitting on them. So if the
+        // byte after the range is non-ASCII, then we can't trust the lazy
+        // DFAs to resolve a word boundary at the end of the range, and the
+        // NFA simulation must not hand the search back to them.
+        let end_is_ascii = haystack.get(end).map_or(true, |b| b.is_ascii());
+        // The offset of the last non-ASCII byte that the lazy DFAs couldn't
+        // handle. The NFA simulation only hands the search back to the lazy
+        // DFAs once it's past this offset and the byte preceding the resume
+        // position is ASCII, so that the lazy DFA's start state is correct.
+        let mut non_ascii = None;
+        if let Some(&b) = haystack[..start].last() {
+            if !b.is_ascii() {
+                non_ascii = Some(start - 1);
+            }
+        }
+        let mut at = start;
+        loop {
+            if non_ascii.is_none() && end_is_ascii {
+                match self
+                    .try_find_leftmost_strict_at(cache, haystack, at, end)
+                {
+                    Err(MatchError::Quit { byte, offset })
+                        if self.forward().is_unicode_word_quit(byte) =>
+                    {
+                        non_ascii = Some(offset);
+                    }
+                    result => return result,
+                }
+            }
+            let (vmcache, caps) = cache.pikevm.get_or_insert_with(|| {
+                (vm.create_cache(), vm.create_captures())
+            });
+            let resume = |pos: usize| {
+                end_is_ascii
+                    && non_ascii.map_or(true, |q| pos > q)
+                    && haystack[pos - 1].is_ascii()
+            };
+            match vm.find_leftmost_or_resume_at(
+                vmcache, haystack, at, end, caps, resume,
+            ) {
+                Ok(mm) => return Ok(mm),
+                Err(pos) => {
+                    at = pos;
+                    non_ascii = None;
+                }
+            }
+        }
+    }
+
+    /// Like `try_find_leftmost_at_imp`, except it never uses a prefilter and
+    /// it always reports an error when the forward lazy DFA quits, even if
+    /// it had already found a match. Otherwise, a match found before quitting
+    /// on a non-ASCII byte might be cut short, and a prefilter might skip
+    /// over non-ASCII text without the lazy DFA ever seeing it.
+    fn try_find_leftmost_strict_at(
+        &self,
+        cache: &mut Cache,
+        haystack: &[u8],
+        start: usize,
+        end: usize,
+    ) -> Result<Option<MultiMatch>, MatchError> {
+        let (fdfa, rdfa) = (self.forward(), self.reverse());
+        let (fcache, rcache) = (&mut cache.forward, &mut cache.reverse);
+        let end = match search::find_leftmost_fwd_strict(
+            fdfa, fcache, haystack, start, end,
+        )? {
+            None => return Ok(None),
+            Some(end) => end,
+        };
+        let start = rdfa
+            .find_leftmost_rev_at(rcache, None, haystack, start, end.offset())?
+            .expect("reverse search must match if forward search does");
+        assert!(start.offset() <= end.offset());
+        Ok(Some(MultiMatch::new(end.pattern(), start.offset(), end.offset())))
+    }
+}
+
 /// Non-search APIs for querying information about the regex and setting a
 /// prefilter.
 impl Regex {
@@ -1400,16 +1682,104 @@ impl Regex {
         self.forward().pattern_count()
     }
 
+    /// Returns true if and only if every match of this regex must begin at
+    /// the start of a search.
+    ///
+    /// This is the case when every pattern begins with `^` (outside of
+    /// multi-line mode), or when the regex was built without an unanchored
+    /// prefix.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use regex_automata::hybrid::regex::Regex;
+    ///
+    /// assert!(Regex::new(r"^foo")?.is_always_anchored_start());
+    /// assert!(!Regex::new(r"foo")?.is_always_anchored_start());
+    /// assert!(!Regex::new(r"(?m)^foo")?.is_always_anchored_start());
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn is_always_anchored_start(&self) -> bool {
+        self.forward().nfa().is_always_start_anchored()
+    }
+
+    /// Returns true if and only if this regex can match the empty string.
+    ///
+    /// Look-around assertions are assumed to be satisfiable, so this may
+    /// return true for a regex that can never match at all.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use regex_automata::hybrid::regex::Regex;
+    ///
+    /// assert!(Regex::new(r"a*")?.can_match_empty());
+    /// assert!(Regex::new(r"^$")?.can_match_empty());
+    /// assert!(!Regex::new(r"a+")?.can_match_empty());
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn can_match_empty(&self) -> bool {
+        self.forward().nfa().can_match_empty()
+    }
+
+    /// Returns the set of bytes that may begin a match of any pattern in this
+    /// regex.
+    ///
+    /// This is useful for building a [`Prefilter`] to attach with
+    /// [`Regex::set_prefilter`], either by converting the set into a
+    /// [`prefilter::ByteSet`] or by writing a custom prefilter. If the regex
+    /// can match the empty string, or if whether a byte begins a match
+    /// depends on look-around, then `None` is returned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use regex_automata::{
+    ///     hybrid::regex::Regex, util::prefilter, MultiMatch,
+    /// };
+    ///
+    /// let mut re = Regex::new(r"[abc]\w+")?;
+    /// let set = re.first_byte_set().unwrap();
+    /// assert_eq!(vec![b'a', b'b', b'c'], set.iter().collect::<Vec<u8>>());
+    ///
+    /// re.set_prefilter(Some(Box::new(prefilter::ByteSet::from(set))));
+    /// let mut cache = re.create_cache();
+    /// assert_eq!(
+    ///     Some(MultiMatch::must(0, 4, 7)),
+    ///     re.find_leftmost(&mut cache, b"xyz bcd"),
+    /// );
+    ///
+    /// // A regex that can match the empty string has no such set.
+    /// let re = Regex::new(r"a*")?;
+    /// assert!(re.first_byte_set().is_none());
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn first_byte_set(&self) -> Option<ByteSet> {
+        self.forward().nfa().first_byte_set()
+    }
+
     /// Convenience function for returning this regex's prefilter as a trait
     /// object.
     ///
     /// If this regex doesn't have a prefilter, then `None` is returned.
     pub fn prefilter(&self) -> Option<&dyn Prefilter> {
-        self.pre.as_ref().map(|x| &**x)
+        self.pre.as_ref().map(|x| &**x as &dyn Prefilter)
     }
 
     /// Attach the given prefilter to this regex.
-    pub fn set_prefilter(&mut self, pre: Option<Box<dyn Prefilter>>) {
+    ///
+    /// The prefilter must be `Send` and `Sync`. This is required so that a
+    /// `Regex` is itself `Send` and `Sync`, and can therefore be shared
+    /// between threads, e.g., when searching with caches from a
+    /// [`CachePool`]. Note that this is stricter than what previous releases
+    /// accepted, so a prefilter that isn't thread safe can no longer be
+    /// attached to a `Regex`. Such a prefilter can still be used with a lazy
+    /// DFA directly via a [`prefilter::Scanner`], e.g., with
+    /// [`DFA::find_leftmost_fwd_at`](crate::hybrid::dfa::DFA::find_leftmost_fwd_at).
+    pub fn set_prefilter(
+        &mut self,
+        pre: Option<Box<dyn Prefilter + Send + Sync>>,
+    ) {
         self.pre = pre;
     }
 
@@ -1677,6 +2047,126 @@ impl<'r, 'c, 't> Iterator for TryFindLeftmostMatches<'r, 'c, 't> {
     }
 }
 
+/// An iterator over all non-overlapping leftmost matches in each line of a
+/// haystack for a particular fallible search.
+///
+/// The iterator yields a [`MultiMatch`] value until no more matches could be
+/// found.
+///
+/// The lifetime variables are as follows:
+///
+/// * `'r` is the lifetime of the regular expression itself.
+/// * `'c` is the lifetime of the mutable cache used during search.
+/// * `'t` is the lifetime of the text being searched.
+#[derive(Debug)]
+pub struct TryFindLineMatches<'r, 'c, 't> {
+    re: &'r Regex,
+    cache: &'c mut Cache,
+    scanner: Option<prefilter::Scanner<'r>>,
+    text: &'t [u8],
+    /// The bounds of the line currently being searched.
+    line_start: usize,
+    line_end: usize,
+    /// The search position and last match end, relative to the current line.
+    last_end: usize,
+    last_match: Option<usize>,
+}
+
+impl<'r, 'c, 't> TryFindLineMatches<'r, 'c, 't> {
+    fn new(
+        re: &'r Regex,
+        cache: &'c mut Cache,
+        text: &'t [u8],
+    ) -> TryFindLineMatches<'r, 'c, 't> {
+        let scanner = re.scanner();
+        // An empty haystack has no lines at all.
+        let line_start = if text.is_empty() { 1 } else { 0 };
+        let line_end = memchr::memchr(b'\n', text).unwrap_or(text.len());
+        TryFindLineMatches {
+            re,
+            cache,
+            scanner,
+            text,
+            line_start,
+            line_end,
+            last_end: 0,
+            last_match: None,
+        }
+    }
+
+    /// Move on to the next line, returning false if there are no more lines.
+    /// A `\n` at the very end of the haystack does not begin a new line.
+    fn next_line(&mut self) -> bool {
+        if self.line_end + 1 >= self.text.len() {
+            self.line_start = self.text.len() + 1;
+            return false;
+        }
+        self.line_start = self.line_end + 1;
+        self.line_end = memchr::memchr(b'\n', &self.text[self.line_start..])
+            .map_or(self.text.len(), |i| self.line_start + i);
+        self.last_end = 0;
+        self.last_match = None;
+        true
+    }
+}
+
+impl<'r, 'c, 't> Iterator for TryFindLineMatches<'r, 'c, 't> {
+    type Item = Result<MultiMatch, MatchError>;
+
+    fn next(&mut self) -> Option<Result<MultiMatch, MatchError>> {
+        while self.line_start <= self.text.len() {
+            let line = &self.text[self.line_start..self.line_end];
+            if self.last_end > line.len() {
+                if !self.next_line() {
+                    return None;
+                }
+                continue;
+            }
+            let result = self.re.try_find_leftmost_at_imp(
+                self.scanner.as_mut(),
+                self.cache,
+                line,
+                self.last_end,
+                line.len(),
+            );
+            let m = match result {
+                Err(err) => return Some(Err(err)),
+                Ok(None) => {
+                    if !self.next_line() {
+                        return None;
+                    }
+                    continue;
+                }
+                Ok(Some(m)) => m,
+            };
+            if m.is_empty() {
+                // This is an empty match. To ensure we make progress, start
+                // the next search at the smallest possible starting position
+                // of the next match following this one.
+                self.last_end = if self.re.utf8 {
+                    crate::util::next_utf8(line, m.end())
+                } else {
+                    m.end() + 1
+                };
+                // Don't accept empty matches immediately following a match.
+                // Just move on to the next match.
+                if Some(m.end()) == self.last_match {
+                    continue;
+                }
+            } else {
+                self.last_end = m.end();
+            }
+            self.last_match = Some(m.end());
+            return Some(Ok(MultiMatch::new(
+                m.pattern(),
+                self.line_start + m.start(),
+                self.line_start + m.end(),
+            )));
+        }
+        None
+    }
+}
+
 /// An iterator over all overlapping matches for a particular fallible search.
 ///
 /// The iterator yields a [`MultiMatch`] value until no more matches could be
@@ -1762,6 +2252,9 @@ impl<'r, 'c, 't> Iterator for TryFindOverlappingMatches<'r, 'c, 't> {
 pub struct Cache {
     forward: dfa::Cache,
     reverse: dfa::Cache,
+    /// Scratch space for simulating the NFA when the lazy DFAs can't resolve
+    /// a Unicode word boundary. This is only allocated when first needed.
+    pikevm: Option<(pikevm::Cache, pikevm::Captures)>,
 }
 
 impl Cache {
@@ -1773,7 +2266,7 @@ impl Cache {
     pub fn new(re: &Regex) -> Cache {
         let forward = dfa::Cache::new(re.forward());
         let reverse = dfa::Cache::new(re.reverse());
-        Cache { forward, reverse }
+        Cache { forward, reverse, pikevm: None }
     }
 
     /// Reset this cache such that it can be used for searching with the given
@@ -1819,15 +2312,58 @@ impl Cache {
     pub fn reset(&mut self, re: &Regex) {
         self.forward.reset(re.forward());
         self.reverse.reset(re.reverse());
+        self.pikevm = None;
     }
 
     /// Returns the heap memory usage, in bytes, as a sum of the forward and
-    /// reverse lazy DFA caches.
+    /// reverse lazy DFA caches, along with the scratch space used for
+    /// resolving Unicode word boundaries, if it has been allocated.
     ///
     /// This does **not** include the stack size used up by this cache. To
     /// compute that, use `std::mem::size_of::<Cache>()`.
     pub fn memory_usage(&self) -> usize {
-        self.forward.memory_usage() + self.reverse.memory_usage()
+        let usage = self.memory_usage_breakdown();
+        usage.forward + usage.reverse + usage.pikevm
+    }
+
+    /// Returns the heap memory usage, in bytes, of each part of this cache.
+    ///
+    /// This is useful for determining which part of a cache dominates its
+    /// memory usage. The sum of all fields is always equal to
+    /// [`Cache::memory_usage`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use regex_automata::hybrid::regex::Regex;
+    ///
+    /// let re = Regex::new(r"foo[0-9]+")?;
+    /// let mut cache = re.create_cache();
+    /// re.find_leftmost(&mut cache, b"foo12345");
+    ///
+    /// let usage = cache.memory_usage_breakdown();
+    /// assert!(usage.forward > 0);
+    /// // The NFA simulation is only used for Unicode word boundaries.
+    /// assert_eq!(0, usage.pikevm);
+    /// assert_eq!(
+    ///     cache.memory_usage(),
+    ///     usage.forward + usage.reverse + usage.pikevm,
+    /// );
+    ///
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn memory_usage_breakdown(&self) -> MemoryBreakdown {
+        let pikevm = match self.pikevm {
+            None => 0,
+            Some((ref cache, ref caps)) => {
+                cache.memory_usage() + caps.memory_usage()
+            }
+        };
+        MemoryBreakdown {
+            forward: self.forward.memory_usage(),
+            reverse: self.reverse.memory_usage(),
+            pikevm,
+        }
     }
 
     /// Return references to the forward and reverse caches, respectively.
@@ -1842,6 +2378,157 @@ impl Cache {
     }
 }
 
+/// The heap memory usage, in bytes, of each part of a [`Cache`].
+///
+/// This is returned by [`Cache::memory_usage_breakdown`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct MemoryBreakdown {
+    /// The memory used by the forward lazy DFA's cache.
+    pub forward: usize,
+    /// The memory used by the reverse lazy DFA's cache.
+    pub reverse: usize,
+    /// The memory used by the NFA simulation that resolves Unicode word
+    /// boundaries on non-ASCII text. This is zero until the simulation is
+    /// first needed, and after the cache is reset.
+    pub pikevm: usize,
+}
+
+/// A thread safe pool of caches for searching with one or more [`Regex`]es.
+///
+/// Since a [`Cache`] is mutated by every search, it cannot be shared across
+/// threads. Instead of creating a new cache for every search (or carefully
+/// threading one cache through every call), a pool may be used to hand out
+/// caches on demand. When a cache is no longer needed, it is returned to the
+/// pool so that its memory can be reused by subsequent searches.
+///
+/// A single pool may be used with more than one `Regex`. When a cache is
+/// handed out for a different `Regex` than the one it was last used with, it
+/// is automatically reset via [`Cache::reset`] first. The pool prefers handing
+/// out a cache that was last used with the same `Regex`, when one is
+/// available.
+///
+/// This is only available when the `std` feature is enabled.
+///
+/// # Example
+///
+/// ```
+/// use std::sync::Arc;
+///
+/// use regex_automata::{
+///     hybrid::regex::{CachePool, Regex},
+///     MultiMatch,
+/// };
+///
+/// let re = Arc::new(Regex::new("foo[0-9]+")?);
+/// let pool = Arc::new(CachePool::new());
+///
+/// let mut handles = vec![];
+/// for _ in 0..4 {
+///     let (re, pool) = (Arc::clone(&re), Arc::clone(&pool));
+///     handles.push(std::thread::spawn(move || {
+///         let mut cache = pool.get(&re);
+///         re.find_leftmost(&mut cache, b"zfoo123")
+///     }));
+/// }
+/// for handle in handles {
+///     assert_eq!(Some(MultiMatch::must(0, 1, 7)), handle.join().unwrap());
+/// }
+///
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[cfg(feature = "std")]
+#[derive(Debug, Default)]
+pub struct CachePool {
+    /// Caches that aren't currently in use, each paired with the ID of the
+    /// regex it was last used with.
+    caches: std::sync::Mutex<Vec<(usize, Cache)>>,
+}
+
+#[cfg(feature = "std")]
+impl CachePool {
+    /// Create a new empty pool. Caches are created lazily as they are
+    /// needed.
+    pub fn new() -> CachePool {
+        CachePool::default()
+    }
+
+    /// Get a cache from this pool that is suitable for searching with the
+    /// given `Regex`.
+    ///
+    /// If no cache is available, then a new one is created. The cache is
+    /// returned to this pool when the guard returned is dropped.
+    pub fn get(&self, re: &Regex) -> PooledCache<'_> {
+        let cache = match self.caches.lock() {
+            // If the lock is poisoned, then we just create a new cache. This
+            // mirrors returning a cache to the pool, which drops the cache in
+            // that case.
+            Err(_) => None,
+            Ok(mut caches) => {
+                match caches.iter().rposition(|&(id, _)| id == re.id) {
+                    Some(i) => Some(caches.swap_remove(i).1),
+                    None => caches.pop().map(|(_, mut cache)| {
+                        cache.reset(re);
+                        cache
+                    }),
+                }
+            }
+        };
+        let cache = cache.unwrap_or_else(|| re.create_cache());
+        PooledCache { pool: self, id: re.id, cache: Some(cache) }
+    }
+}
+
+/// A cache borrowed from a [`CachePool`].
+///
+/// This dereferences to a [`Cache`], and returns that cache to the pool it
+/// came from when dropped.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct PooledCache<'p> {
+    pool: &'p CachePool,
+    /// The ID of the regex this cache is being used with.
+    id: usize,
+    /// The cache itself. This is always present until the guard is dropped.
+    cache: Option<Cache>,
+}
+
+#[cfg(feature = "std")]
+impl<'p> core::ops::Deref for PooledCache<'p> {
+    type Target = Cache;
+
+    fn deref(&self) -> &Cache {
+        self.cache.as_ref().unwrap()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'p> core::ops::DerefMut for PooledCache<'p> {
+    fn deref_mut(&mut self) -> &mut Cache {
+        self.cache.as_mut().unwrap()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'p> Drop for PooledCache<'p> {
+    fn drop(&mut self) {
+        if let Some(cache) = self.cache.take() {
+            // If the lock is poisoned, then we just drop the cache.
+            if let Ok(mut caches) = self.pool.caches.lock() {
+                caches.push((self.id, cache));
+            }
+        }
+    }
+}
+
+/// Returns a new process-wide unique identifier for a regex.
+#[cfg(feature = "std")]
+fn next_regex_id() -> usize {
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+    NEXT.fetch_add(1, Ordering::Relaxed)
+}
+
 /// The configuration used for compiling a hybrid NFA/DFA regex.
 ///
 /// A regex configuration is a simple data object that is typically used with
@@ -2059,7 +2746,27 @@ impl Builder {
         // not clear this builder is useful here since lazy DFAs can't be
         // serialized and there is only one type of them.
         let utf8 = self.config.get_utf8();
-        Regex { pre: None, forward, reverse, utf8 }
+        let pikevm = if forward.match_kind() == MatchKind::LeftmostFirst
+            && (0x80..=0xFF).any(|b| forward.is_unicode_word_quit(b))
+        {
+            pikevm::Builder::new()
+                .configure(
+                    pikevm::Config::new().anchored(forward.is_anchored()),
+                )
+                .build_from_nfa(Arc::clone(forward.nfa()))
+                .ok()
+        } else {
+            None
+        };
+        Regex {
+            pre: None,
+            forward,
+            reverse,
+            utf8,
+            pikevm,
+            #[cfg(feature = "std")]
+            id: next_regex_id(),
+        }
     }
 
     /// Apply the given regex configuration options to this builder.
@@ -21,13 +21,16 @@ pub(crate) fn find_earliest_fwd(
     start: usize,
     end: usize,
 ) -> Result<Option<HalfMatch>, MatchError> {
+    cache.search_start(start);
     // Searching with a pattern ID is always anchored, so we should never use
     // a prefilter.
-    if pre.is_some() && pattern_id.is_none() {
-        find_fwd(pre, true, dfa, cache, pattern_id, bytes, start, end)
+    let result = if pre.is_some() && pattern_id.is_none() {
+        find_fwd(pre, true, false, dfa, cache, pattern_id, bytes, start, end)
     } else {
-        find_fwd(None, true, dfa, cache, pattern_id, bytes, start, end)
-    }
+        find_fwd(None, true, false, dfa, cache, pattern_id, bytes, start, end)
+    };
+    cache.search_finish(stopped_at(&result, end));
+    result
 }
 
 #[inline(never)]
@@ -40,19 +43,42 @@ pub(crate) fn find_leftmost_fwd(
     start: usize,
     end: usize,
 ) -> Result<Option<HalfMatch>, MatchError> {
+    cache.search_start(start);
     // Searching with a pattern ID is always anchored, so we should never use
     // a prefilter.
-    if pre.is_some() && pattern_id.is_none() {
-        find_fwd(pre, false, dfa, cache, pattern_id, bytes, start, end)
+    let result = if pre.is_some() && pattern_id.is_none() {
+        find_fwd(pre, false, false, dfa, cache, pattern_id, bytes, start, end)
     } else {
-        find_fwd(None, false, dfa, cache, pattern_id, bytes, start, end)
-    }
+        find_fwd(None, false, false, dfa, cache, pattern_id, bytes, start, end)
+    };
+    cache.search_finish(stopped_at(&result, end));
+    result
+}
+
+/// Like `find_leftmost_fwd`, but entering a quit state always reports an
+/// error, even if a match was already found. That is, a match is only
+/// returned when the search would have found the same match had it been
+/// able to continue.
+#[inline(never)]
+pub(crate) fn find_leftmost_fwd_strict(
+    dfa: &DFA,
+    cache: &mut Cache,
+    bytes: &[u8],
+    start: usize,
+    end: usize,
+) -> Result<Option<HalfMatch>, MatchError> {
+    cache.search_start(start);
+    let result =
+        find_fwd(None, false, true, dfa, cache, None, bytes, start, end);
+    cache.search_finish(stopped_at(&result, end));
+    result
 }
 
 #[inline(always)]
 fn find_fwd(
     mut pre: Option<&mut prefilter::Scanner>,
     earliest: bool,
+    strict: bool,
     dfa: &DFA,
     cache: &mut Cache,
     pattern_id: Option<PatternID>,
@@ -95,6 +121,7 @@ fn find_fwd(
     }
     while at < end {
         if sid.is_tagged() {
+            cache.search_update(at);
             sid = dfa
                 .next_state(cache, sid, bytes[at])
                 .map_err(|_| gave_up(at))?;
@@ -202,6 +229,7 @@ fn find_fwd(
                 }
             }
             if sid.is_unknown() {
+                cache.search_update(at - 1);
                 sid = dfa
                     .next_state(cache, prev_sid, bytes[at - 1])
                     .map_err(|_| gave_up(at - 1))?;
@@ -230,7 +258,7 @@ fn find_fwd(
             } else if sid.is_dead() {
                 return Ok(last_match);
             } else if sid.is_quit() {
-                if last_match.is_some() {
+                if last_match.is_some() && !strict {
                     return Ok(last_match);
                 }
                 let offset = at - 1;
@@ -248,6 +276,7 @@ fn find_fwd(
 
 #[inline(never)]
 pub(crate) fn find_earliest_rev(
+    pre: Option<&mut prefilter::Scanner>,
     dfa: &DFA,
     cache: &mut Cache,
     pattern_id: Option<PatternID>,
@@ -255,11 +284,23 @@ pub(crate) fn find_earliest_rev(
     start: usize,
     end: usize,
 ) -> Result<Option<HalfMatch>, MatchError> {
-    find_rev(true, dfa, cache, pattern_id, bytes, start, end)
+    cache.search_start(end);
+    // Searching with a pattern ID or with an anchored DFA can only find a
+    // match that ends where the search starts, so we should never use a
+    // prefilter. It could otherwise skip past that position.
+    let anchored = pattern_id.is_some() || dfa.is_anchored();
+    let result = if pre.is_some() && !anchored {
+        find_rev(pre, true, dfa, cache, pattern_id, bytes, start, end)
+    } else {
+        find_rev(None, true, dfa, cache, pattern_id, bytes, start, end)
+    };
+    cache.search_finish(stopped_at(&result, start));
+    result
 }
 
 #[inline(never)]
 pub(crate) fn find_leftmost_rev(
+    pre: Option<&mut prefilter::Scanner>,
     dfa: &DFA,
     cache: &mut Cache,
     pattern_id: Option<PatternID>,
@@ -267,11 +308,23 @@ pub(crate) fn find_leftmost_rev(
     start: usize,
     end: usize,
 ) -> Result<Option<HalfMatch>, MatchError> {
-    find_rev(false, dfa, cache, pattern_id, bytes, start, end)
+    cache.search_start(end);
+    // Searching with a pattern ID or with an anchored DFA can only find a
+    // match that ends where the search starts, so we should never use a
+    // prefilter. It could otherwise skip past that position.
+    let anchored = pattern_id.is_some() || dfa.is_anchored();
+    let result = if pre.is_some() && !anchored {
+        find_rev(pre, false, dfa, cache, pattern_id, bytes, start, end)
+    } else {
+        find_rev(None, false, dfa, cache, pattern_id, bytes, start, end)
+    };
+    cache.search_finish(stopped_at(&result, start));
+    result
 }
 
 #[inline(always)]
 fn find_rev(
+    mut pre: Option<&mut prefilter::Scanner>,
     earliest: bool,
     dfa: &DFA,
     cache: &mut Cache,
@@ -294,9 +347,21 @@ fn find_rev(
     let mut sid = init_rev(dfa, cache, pattern_id, haystack, start, end)?;
     let mut last_match = None;
     let mut at = end - start;
+    if let Some(ref mut pre) = pre {
+        if pre.is_effective_rev() {
+            match prev_candidate(pre, haystack, start, end) {
+                None => {}
+                Some(None) => return Ok(None),
+                Some(Some(i)) => {
+                    at = i - start;
+                }
+            }
+        }
+    }
     while at > 0 {
         if sid.is_tagged() {
             at -= 1;
+            cache.search_update(start + at);
             sid = dfa
                 .next_state(cache, sid, bytes[at])
                 .map_err(|_| gave_up(at))?;
@@ -364,6 +429,7 @@ fn find_rev(
                 };
             }
             if sid.is_unknown() {
+                cache.search_update(start + at);
                 sid = dfa
                     .next_state(cache, prev_sid, bytes[at])
                     .map_err(|_| gave_up(at))?;
@@ -371,7 +437,19 @@ fn find_rev(
         }
         if sid.is_tagged() {
             if sid.is_start() {
-                continue;
+                if let Some(ref mut pre) = pre {
+                    if pre.is_effective_rev() {
+                        let cand =
+                            prev_candidate(pre, haystack, start, start + at);
+                        match cand {
+                            None => {}
+                            Some(None) => return Ok(last_match),
+                            Some(Some(i)) => {
+                                at = i - start;
+                            }
+                        }
+                    }
+                }
             } else if sid.is_match() {
                 last_match = Some(HalfMatch {
                     pattern: dfa.match_pattern(cache, sid, 0),
@@ -394,6 +472,22 @@ fn find_rev(
     Ok(eoi_rev(dfa, cache, haystack, start, sid)?.or(last_match))
 }
 
+/// Run the given prefilter in reverse over `bytes[..at]`.
+///
+/// This returns `None` when the prefilter does not support reverse searches.
+/// Otherwise, it returns the position at which the reverse search should
+/// resume, or `Some(None)` when no match can end in `bytes[start..at]`.
+#[inline(always)]
+fn prev_candidate(
+    pre: &mut prefilter::Scanner,
+    bytes: &[u8],
+    start: usize,
+    at: usize,
+) -> Option<Option<usize>> {
+    let end = pre.find_rev(bytes, at)?.into_option_rev();
+    Some(end.filter(|&i| i >= start))
+}
+
 #[inline(never)]
 pub(crate) fn find_overlapping_fwd(
     pre: Option<&mut prefilter::Scanner>,
@@ -405,9 +499,10 @@ pub(crate) fn find_overlapping_fwd(
     end: usize,
     caller_state: &mut OverlappingState,
 ) -> Result<Option<HalfMatch>, MatchError> {
+    cache.search_start(start);
     // Searching with a pattern ID is always anchored, so we should only ever
     // use a prefilter when no pattern ID is given.
-    if pre.is_some() && pattern_id.is_none() {
+    let result = if pre.is_some() && pattern_id.is_none() {
         find_overlapping_fwd_imp(
             pre,
             dfa,
@@ -429,7 +524,9 @@ pub(crate) fn find_overlapping_fwd(
             end,
             caller_state,
         )
-    }
+    };
+    cache.search_finish(stopped_at(&result, end));
+    result
 }
 
 #[inline(always)]
@@ -503,7 +600,20 @@ fn find_overlapping_fwd_imp(
     let mut at = start;
     while at < end {
         let byte = bytes[at];
-        sid = dfa.next_state(cache, sid, byte).map_err(|_| gave_up(at))?;
+        // The cache only needs to know our position when computing the
+        // transition may add a new state, since that's when it may be cleared.
+        let next = if sid.is_tagged() {
+            None
+        } else {
+            Some(dfa.next_state_untagged(cache, sid, byte))
+        };
+        sid = match next {
+            Some(next) if !next.is_unknown() => next,
+            _ => {
+                cache.search_update(at);
+                dfa.next_state(cache, sid, byte).map_err(|_| gave_up(at))?
+            }
+        };
         at += 1;
         if sid.is_tagged() {
             caller_state.set_id(sid);
@@ -656,6 +766,21 @@ fn eoi_rev(
     }
 }
 
+/// Returns the position at which a search that produced the given result
+/// stopped, for the purposes of counting the bytes it searched. When the
+/// search didn't stop early, the given default position is returned.
+fn stopped_at(
+    result: &Result<Option<HalfMatch>, MatchError>,
+    default: usize,
+) -> usize {
+    match *result {
+        Ok(Some(ref m)) => m.offset(),
+        Ok(None) => default,
+        Err(MatchError::Quit { offset, .. }) => offset,
+        Err(MatchError::GaveUp { offset }) => offset,
+    }
+}
+
 /// A convenience routine for constructing a "gave up" match error.
 #[inline(always)]
 fn gave_up(offset: usize) -> MatchError {
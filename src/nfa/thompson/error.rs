@@ -3,12 +3,15 @@ use crate::util::id::{PatternID, StateID};
 /// An error that can occured during the construction of a thompson NFA.
 ///
 /// This error does not provide many introspection capabilities. There are
-/// generally only two things you can do with it:
+/// generally only a few things you can do with it:
 ///
 /// * Obtain a human readable message via its `std::fmt::Display` impl.
 /// * Access an underlying [`regex_syntax::Error`] type from its `source`
 /// method via the `std::error::Error` trait. This error only occurs when using
 /// convenience routines for building an NFA directly from a pattern string.
+/// * Determine whether the error occurred because a pattern could not be
+/// parsed ([`Error::is_syntax_error`]) or because a configured size limit was
+/// exceeded ([`Error::is_size_limit`] and [`Error::size_limit`]).
 ///
 /// Otherwise, errors typically occur when a limit has been breeched. For
 /// example, if the total heap usage of the compiled NFA exceeds the limit
@@ -63,6 +66,31 @@ enum ErrorKind {
 }
 
 impl Error {
+    /// Returns true if and only if this error occurred because a pattern
+    /// could not be parsed.
+    pub fn is_syntax_error(&self) -> bool {
+        match self.kind() {
+            ErrorKind::Syntax(_) => true,
+            _ => false,
+        }
+    }
+
+    /// Returns true if and only if this error occurred because the heap
+    /// usage of the NFA exceeded the configured size limit.
+    pub fn is_size_limit(&self) -> bool {
+        self.size_limit().is_some()
+    }
+
+    /// Returns the size limit, in bytes, that was exceeded if this error
+    /// occurred because the heap usage of the NFA exceeded it. Otherwise,
+    /// this returns `None`.
+    pub fn size_limit(&self) -> Option<usize> {
+        match *self.kind() {
+            ErrorKind::ExceededSizeLimit { limit } => Some(limit),
+            _ => None,
+        }
+    }
+
     fn kind(&self) -> &ErrorKind {
         &self.kind
     }
@@ -351,6 +351,70 @@ impl NFA {
         self.facts.has_word_boundary_ascii()
     }
 
+    /// Returns true if and only if any pattern in this NFA can match the
+    /// empty string.
+    ///
+    /// Look-around assertions are assumed to be satisfiable, so this may
+    /// return true for patterns like `\b\B` that can never match at all.
+    pub fn can_match_empty(&self) -> bool {
+        let mut seen = vec![false; self.states.len()];
+        let mut stack = vec![self.start_anchored()];
+        while let Some(sid) = stack.pop() {
+            if mem::replace(&mut seen[sid], true) {
+                continue;
+            }
+            match *self.state(sid) {
+                State::Range { .. } | State::Sparse(_) | State::Fail => {}
+                State::Union { ref alternates } => {
+                    stack.extend(alternates.iter().copied());
+                }
+                State::Look { next, .. } | State::Capture { next, .. } => {
+                    stack.push(next)
+                }
+                State::Match { .. } => return true,
+            }
+        }
+        false
+    }
+
+    /// Returns the set of bytes that may begin a match of any pattern in this
+    /// NFA.
+    ///
+    /// This returns `None` when no such set can be given. That is, when any
+    /// pattern can match the empty string or when a look-around assertion
+    /// is reachable before the first byte is consumed. (In the latter case,
+    /// whether a byte may begin a match depends on the bytes preceding it.)
+    ///
+    /// Note that the set is computed from the anchored start state, so it
+    /// says nothing about which bytes an unanchored search skips over.
+    pub fn first_byte_set(&self) -> Option<alphabet::ByteSet> {
+        let mut set = alphabet::ByteSet::empty();
+        let mut seen = vec![false; self.states.len()];
+        let mut stack = vec![self.start_anchored()];
+        while let Some(sid) = stack.pop() {
+            if mem::replace(&mut seen[sid], true) {
+                continue;
+            }
+            match *self.state(sid) {
+                State::Range { ref range } => {
+                    set.add_all(range.start, range.end);
+                }
+                State::Sparse(SparseTransitions { ref ranges }) => {
+                    for r in ranges.iter() {
+                        set.add_all(r.start, r.end);
+                    }
+                }
+                State::Union { ref alternates } => {
+                    stack.extend(alternates.iter().copied());
+                }
+                State::Capture { next, .. } => stack.push(next),
+                State::Fail => {}
+                State::Look { .. } | State::Match { .. } => return None,
+            }
+        }
+        Some(set)
+    }
+
     /// Returns the memory usage, in bytes, of this NFA.
     ///
     /// This does **not** include the stack size used up by this NFA. To
@@ -209,6 +209,36 @@ impl PikeVM {
         end: usize,
         caps: &mut Captures,
     ) -> Option<MultiMatch> {
+        match self.find_leftmost_or_resume_at(
+            cache,
+            haystack,
+            start,
+            end,
+            caps,
+            |_| false,
+        ) {
+            Ok(mm) => mm,
+            Err(_) => unreachable!("search never resumes elsewhere"),
+        }
+    }
+
+    /// Like `find_leftmost_at`, but gives up early at the first offset `at`
+    /// past `start` for which `resume(at)` returns true, so long as no match
+    /// has been found and no thread that started before `at` is still alive.
+    /// At that point, the leftmost match (if one exists) must start at or
+    /// after `at`, so `Err(at)` is returned to let the caller resume its
+    /// search at `at`, typically with a faster regex engine.
+    ///
+    /// Anchored searches never give up early.
+    pub(crate) fn find_leftmost_or_resume_at<F: Fn(usize) -> bool>(
+        &self,
+        cache: &mut Cache,
+        haystack: &[u8],
+        start: usize,
+        end: usize,
+        caps: &mut Captures,
+        resume: F,
+    ) -> Result<Option<MultiMatch>, usize> {
         let anchored =
             self.config.get_anchored() || self.nfa.is_always_start_anchored();
         let mut at = start;
@@ -219,6 +249,9 @@ impl PikeVM {
                 if matched_pid.is_some() || (anchored && at > start) {
                     break 'LOOP;
                 }
+                if !anchored && at > start && resume(at) {
+                    return Err(at);
+                }
                 // TODO: prefilter
             }
             if (!anchored && matched_pid.is_none())
@@ -257,7 +290,7 @@ impl PikeVM {
             cache.swap();
             cache.nlist.set.clear();
         }
-        matched_pid.map(|pid| {
+        Ok(matched_pid.map(|pid| {
             let slots = self.nfa.pattern_slots(pid);
             let (start, end) = (slots.start, slots.start + 1);
             MultiMatch::new(
@@ -265,7 +298,7 @@ impl PikeVM {
                 caps.slots[start].unwrap(),
                 caps.slots[end].unwrap(),
             )
-        })
+        }))
     }
 
     #[inline(always)]
@@ -483,6 +516,11 @@ impl Captures {
     pub fn new(nfa: &NFA) -> Captures {
         Captures { slots: vec![None; nfa.capture_slot_len()] }
     }
+
+    /// Returns the heap memory usage, in bytes, of these captures.
+    pub fn memory_usage(&self) -> usize {
+        self.slots.len() * core::mem::size_of::<Slot>()
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -516,6 +554,13 @@ impl Cache {
         }
     }
 
+    /// Returns the heap memory usage, in bytes, of this cache.
+    pub fn memory_usage(&self) -> usize {
+        self.stack.capacity() * core::mem::size_of::<FollowEpsilon>()
+            + self.clist.memory_usage()
+            + self.nlist.memory_usage()
+    }
+
     fn clear(&mut self) {
         self.stack.clear();
         self.clist.set.clear();
@@ -547,6 +592,11 @@ impl Threads {
         self.caps.resize(self.slots_per_thread * nfa.states().len(), None);
     }
 
+    fn memory_usage(&self) -> usize {
+        self.set.memory_usage()
+            + self.caps.capacity() * core::mem::size_of::<Slot>()
+    }
+
     fn caps(&mut self, sid: StateID) -> &mut [Slot] {
         let i = sid.as_usize() * self.slots_per_thread;
         &mut self.caps[i..i + self.slots_per_thread]
@@ -1,4 +1,4 @@
-use core::convert::TryFrom;
+use core::convert::{TryFrom, TryInto};
 
 use crate::util::{
     bytes::{DeserializeError, SerializeError},
@@ -248,6 +248,35 @@ impl ByteClasses {
         self.alphabet_len() == 257
     }
 
+    /// Returns true if and only if every byte maps to an equivalence class
+    /// that is less than the number of byte equivalence classes reported by
+    /// `alphabet_len`.
+    ///
+    /// Byte class maps built by this crate are always valid, but maps built
+    /// by hand via `set` might not be.
+    pub fn is_valid(&self) -> bool {
+        let max = self.get(255);
+        (0..=255).all(|b| self.get(b) <= max)
+    }
+
+    /// Returns true if and only if any two bytes in the same equivalence
+    /// class in this map are also in the same equivalence class in `other`.
+    ///
+    /// In other words, this returns true when this map distinguishes every
+    /// pair of bytes that `other` distinguishes.
+    pub fn is_refinement_of(&self, other: &ByteClasses) -> bool {
+        let mut seen: [Option<u8>; 256] = [None; 256];
+        for b in 0..=255 {
+            let class = usize::from(self.get(b));
+            match seen[class] {
+                None => seen[class] = Some(other.get(b)),
+                Some(oclass) if oclass != other.get(b) => return false,
+                Some(_) => {}
+            }
+        }
+        true
+    }
+
     /// Returns an iterator over all equivalence classes in this set.
     pub fn iter(&self) -> ByteClassIter<'_> {
         ByteClassIter { classes: self, i: 0 }
@@ -262,9 +291,18 @@ impl ByteClasses {
     /// hasn't been converted to equivalence classes yet. Picking an arbitrary
     /// byte from each equivalence class then permits a full exploration of
     /// the NFA instead of using every possible byte value.
+    ///
+    /// The last item yielded is always the EOI sentinel, which is the sole
+    /// member of its own equivalence class. Every other item is the smallest
+    /// byte in its equivalence class, even when the bytes in a class are not
+    /// contiguous.
     #[cfg(feature = "alloc")]
     pub fn representatives(&self) -> ByteClassRepresentatives<'_> {
-        ByteClassRepresentatives { classes: self, byte: 0, last_class: None }
+        ByteClassRepresentatives {
+            classes: self,
+            byte: 0,
+            seen: ByteSet::empty(),
+        }
     }
 
     /// Returns an iterator of the bytes in the given equivalence class.
@@ -336,7 +374,7 @@ impl<'a> Iterator for ByteClassIter<'a> {
 pub struct ByteClassRepresentatives<'a> {
     classes: &'a ByteClasses,
     byte: usize,
-    last_class: Option<u8>,
+    seen: ByteSet,
 }
 
 #[cfg(feature = "alloc")]
@@ -349,8 +387,8 @@ impl<'a> Iterator for ByteClassRepresentatives<'a> {
             let class = self.classes.get(byte);
             self.byte += 1;
 
-            if self.last_class != Some(class) {
-                self.last_class = Some(class);
+            if !self.seen.contains(class) {
+                self.seen.add(class);
                 return Some(Unit::u8(byte));
             }
         }
@@ -589,20 +627,85 @@ impl ByteSet {
     pub fn is_empty(&self) -> bool {
         self.bits.0 == [0, 0]
     }
+
+    /// Deserializes a byte set from the given slice. If the slice is too
+    /// small, then an error is returned. Upon success, the number of bytes
+    /// read along with the set are returned. The number of bytes read is
+    /// always a multiple of 8.
+    pub(crate) fn from_bytes(
+        slice: &[u8],
+    ) -> Result<(ByteSet, usize), DeserializeError> {
+        if slice.len() < 32 {
+            return Err(DeserializeError::buffer_too_small("byte set"));
+        }
+        // The set is always written in little endian regardless of the
+        // endianness requested by the caller, since it is just a bag of
+        // bits. The `unwrap`s are OK since each slice has length 16.
+        let lo = u128::from_le_bytes(slice[..16].try_into().unwrap());
+        let hi = u128::from_le_bytes(slice[16..32].try_into().unwrap());
+        Ok((ByteSet { bits: BitSet([lo, hi]) }, 32))
+    }
+
+    /// Writes this byte set to the given byte buffer. If the given buffer is
+    /// too small, then an error is returned. Upon success, the total number
+    /// of bytes written is returned. The number of bytes written is
+    /// guaranteed to be a multiple of 8.
+    pub(crate) fn write_to(
+        &self,
+        dst: &mut [u8],
+    ) -> Result<usize, SerializeError> {
+        let nwrite = self.write_to_len();
+        if dst.len() < nwrite {
+            return Err(SerializeError::buffer_too_small("byte set"));
+        }
+        dst[..16].copy_from_slice(&self.bits.0[0].to_le_bytes());
+        dst[16..32].copy_from_slice(&self.bits.0[1].to_le_bytes());
+        Ok(nwrite)
+    }
+
+    /// Returns the total number of bytes written by `write_to`.
+    pub(crate) fn write_to_len(&self) -> usize {
+        32
+    }
 }
 
 impl core::fmt::Debug for BitSet {
     fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        // Contiguous runs of bytes are collapsed into ranges, which keeps
+        // large sets (like all non-ASCII bytes) readable.
+        let set = ByteSet { bits: *self };
         let mut fmtd = f.debug_set();
-        for b in (0..256).map(|b| b as u8) {
-            if (ByteSet { bits: *self }).contains(b) {
-                fmtd.entry(&b);
+        let mut b = 0;
+        while b <= 255 {
+            if !set.contains(b as u8) {
+                b += 1;
+                continue;
             }
+            let start = b as u8;
+            while b < 255 && set.contains(b as u8 + 1) {
+                b += 1;
+            }
+            fmtd.entry(&DebugByteRange(start, b as u8));
+            b += 1;
         }
         fmtd.finish()
     }
 }
 
+/// A helper for formatting an inclusive range of bytes, escaping them as
+/// needed. A range containing a single byte is written as just that byte.
+struct DebugByteRange(u8, u8);
+
+impl core::fmt::Debug for DebugByteRange {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        if self.0 == self.1 {
+            write!(f, "{:?}", DebugByte(self.0))
+        } else {
+            write!(f, "{:?}-{:?}", DebugByte(self.0), DebugByte(self.1))
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct ByteSetIter<'a> {
     set: &'a ByteSet,
@@ -655,7 +758,7 @@ impl<'a> Iterator for ByteSetRangeIter<'a> {
 #[cfg(test)]
 #[cfg(feature = "alloc")]
 mod tests {
-    use alloc::{vec, vec::Vec};
+    use alloc::{format, vec, vec::Vec};
 
     use super::*;
 
@@ -774,6 +877,30 @@ mod tests {
         assert_eq!(elements, vec![Unit::eoi(256)]);
     }
 
+    #[test]
+    fn representatives_non_contiguous() {
+        let mut classes = ByteClasses::empty();
+        classes.set(b'a', 1);
+        classes.set(b'c', 1);
+        classes.set(b'e', 2);
+        classes.set(b'g', 1);
+        classes.set(255, 2);
+        assert!(classes.is_valid());
+        assert_eq!(classes.alphabet_len(), 4);
+
+        let reps = classes.representatives().collect::<Vec<_>>();
+        assert_eq!(
+            reps,
+            vec![Unit::u8(0), Unit::u8(b'a'), Unit::u8(b'e'), Unit::eoi(3)],
+        );
+
+        let elements = classes.elements(Unit::u8(1)).collect::<Vec<_>>();
+        assert_eq!(
+            elements,
+            vec![Unit::u8(b'a'), Unit::u8(b'c'), Unit::u8(b'g')],
+        );
+    }
+
     #[test]
     fn elements_empty() {
         let classes = ByteClasses::empty();
@@ -787,4 +914,38 @@ mod tests {
         let elements = classes.elements(Unit::eoi(1)).collect::<Vec<_>>();
         assert_eq!(elements, vec![Unit::eoi(256)]);
     }
+
+    #[test]
+    fn byte_set_debug_ranges() {
+        let mut set = ByteSet::empty();
+        set.add_all(0x80, 0xFF);
+        assert_eq!(format!("{:?}", set), r"ByteSet { bits: {\x80-\xFF} }");
+
+        let mut set = ByteSet::empty();
+        set.add_all(b'a', b'z');
+        set.add(b'\n');
+        set.add(0);
+        assert_eq!(format!("{:?}", set), r"ByteSet { bits: {\x00, \n, a-z} }",);
+
+        assert_eq!(format!("{:?}", ByteSet::empty()), "ByteSet { bits: {} }",);
+    }
+
+    #[test]
+    fn byte_set_roundtrip() {
+        let mut set = ByteSet::empty();
+        set.add(0);
+        set.add(b'\n');
+        set.add(0x7F);
+        set.add(0x80);
+        set.add(0xFF);
+
+        let mut buf = vec![0; set.write_to_len()];
+        assert_eq!(32, set.write_to(&mut buf).unwrap());
+        let (got, nread) = ByteSet::from_bytes(&buf).unwrap();
+        assert_eq!(32, nread);
+        assert_eq!(set, got);
+
+        assert!(set.write_to(&mut buf[..31]).is_err());
+        assert!(ByteSet::from_bytes(&buf[..31]).is_err());
+    }
 }
@@ -152,6 +152,35 @@ impl PatternID {
         self.as_usize().checked_add(1).unwrap()
     }
 
+    /// Returns the byte offset of the `index`-th pattern ID in a table of
+    /// pattern IDs. That is, this returns `index * PatternID::SIZE`.
+    ///
+    /// If the multiplication overflows a `usize`, then this returns `None`.
+    #[inline]
+    pub fn offset(index: usize) -> Option<usize> {
+        index.checked_mul(PatternID::SIZE)
+    }
+
+    /// Returns the pattern ID whose index in a table of pattern IDs corresponds
+    /// to the given byte offset. This is the inverse of [`PatternID::offset`].
+    ///
+    /// If the index corresponding to the offset exceeds [`PatternID::MAX`], then
+    /// this returns an error.
+    ///
+    /// # Panics
+    ///
+    /// This panics if the given offset is not a multiple of
+    /// [`PatternID::SIZE`].
+    #[inline]
+    pub fn from_offset(offset: usize) -> Result<PatternID, PatternIDError> {
+        assert_eq!(
+            0,
+            offset % PatternID::SIZE,
+            "offset must be a multiple of PatternID::SIZE",
+        );
+        PatternID::new(offset / PatternID::SIZE)
+    }
+
     /// Decode this pattern ID from the bytes given using the native endian
     /// byte order for the current target.
     ///
@@ -193,6 +222,27 @@ impl PatternID {
     }
 }
 
+#[cfg(feature = "serde")]
+impl serde::Serialize for PatternID {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u32(self.as_u32())
+    }
+}
+
+/// Deserializing a pattern ID fails if its value exceeds [`PatternID::MAX`].
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for PatternID {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<PatternID, D::Error> {
+        let id = u32::deserialize(deserializer)?;
+        PatternID::try_from(id).map_err(serde::de::Error::custom)
+    }
+}
+
 /// This error occurs when a pattern ID could not be constructed.
 ///
 /// This occurs when given an integer exceeding the maximum pattern ID value.
@@ -331,6 +381,35 @@ impl StateID {
         self.as_usize().checked_add(1).unwrap()
     }
 
+    /// Returns the byte offset of the `index`-th state ID in a table of
+    /// state IDs. That is, this returns `index * StateID::SIZE`.
+    ///
+    /// If the multiplication overflows a `usize`, then this returns `None`.
+    #[inline]
+    pub fn offset(index: usize) -> Option<usize> {
+        index.checked_mul(StateID::SIZE)
+    }
+
+    /// Returns the state ID whose index in a table of state IDs corresponds
+    /// to the given byte offset. This is the inverse of [`StateID::offset`].
+    ///
+    /// If the index corresponding to the offset exceeds [`StateID::MAX`], then
+    /// this returns an error.
+    ///
+    /// # Panics
+    ///
+    /// This panics if the given offset is not a multiple of
+    /// [`StateID::SIZE`].
+    #[inline]
+    pub fn from_offset(offset: usize) -> Result<StateID, StateIDError> {
+        assert_eq!(
+            0,
+            offset % StateID::SIZE,
+            "offset must be a multiple of StateID::SIZE",
+        );
+        StateID::new(offset / StateID::SIZE)
+    }
+
     /// Decode this state ID from the bytes given using the native endian byte
     /// order for the current target.
     ///
@@ -606,3 +685,56 @@ macro_rules! iditer {
 iditer!(PatternID, PatternIDIter, WithPatternIDIter);
 #[cfg(feature = "alloc")]
 iditer!(StateID, StateIDIter, WithStateIDIter);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn offset_roundtrip() {
+        assert_eq!(Some(0), StateID::offset(0));
+        assert_eq!(Some(StateID::SIZE * 5), StateID::offset(5));
+        assert_eq!(
+            Ok(StateID::must(5)),
+            StateID::from_offset(5 * StateID::SIZE)
+        );
+        assert_eq!(Some(0), PatternID::offset(0));
+        assert_eq!(Some(PatternID::SIZE * 5), PatternID::offset(5));
+        assert_eq!(
+            Ok(PatternID::must(5)),
+            PatternID::from_offset(5 * PatternID::SIZE)
+        );
+
+        let max = StateID::offset(StateID::MAX.as_usize()).unwrap();
+        assert_eq!(Ok(StateID::MAX), StateID::from_offset(max));
+        let max = PatternID::offset(PatternID::MAX.as_usize()).unwrap();
+        assert_eq!(Ok(PatternID::MAX), PatternID::from_offset(max));
+    }
+
+    #[test]
+    fn offset_overflow() {
+        let limit = core::usize::MAX / StateID::SIZE;
+        assert_eq!(Some(limit * StateID::SIZE), StateID::offset(limit));
+        assert_eq!(None, StateID::offset(limit + 1));
+        assert_eq!(None, StateID::offset(core::usize::MAX));
+
+        let limit = core::usize::MAX / PatternID::SIZE;
+        assert_eq!(Some(limit * PatternID::SIZE), PatternID::offset(limit));
+        assert_eq!(None, PatternID::offset(limit + 1));
+        assert_eq!(None, PatternID::offset(core::usize::MAX));
+    }
+
+    #[test]
+    fn from_offset_too_big() {
+        let big = StateID::offset(StateID::LIMIT).unwrap();
+        assert!(StateID::from_offset(big).is_err());
+        let big = PatternID::offset(PatternID::LIMIT).unwrap();
+        assert!(PatternID::from_offset(big).is_err());
+    }
+
+    #[test]
+    #[should_panic]
+    fn from_offset_unaligned() {
+        let _ = StateID::from_offset(StateID::SIZE + 1);
+    }
+}
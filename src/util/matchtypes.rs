@@ -52,8 +52,18 @@ impl Default for MatchKind {
 ///
 /// A match records the start and end offsets of the match in the haystack.
 ///
-/// Every match guarantees that `start <= end`.
+/// Every match guarantees that `start <= end`. When the `serde` feature is
+/// enabled, deserializing a match that violates this invariant returns an
+/// error.
+///
+/// Matches are ordered by their start offset, with ties broken by their end
+/// offset. So for example, `0..5` sorts before `1..2`, which sorts before
+/// `1..3`.
+///
+/// The `Display` impl renders a match as its range of offsets, e.g., `5..10`.
+/// Unlike the `Debug` impl, this format is stable.
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Match {
     /// The start offset of the match, inclusive.
     start: usize,
@@ -100,6 +110,73 @@ impl Match {
     pub fn is_empty(&self) -> bool {
         self.start == self.end
     }
+
+    /// Returns true if and only if this match is empty and starts at the
+    /// given position. That is, when `start() == end() == pos`.
+    ///
+    /// This is useful when advancing past empty matches, where one needs to
+    /// know whether a match is empty at the current search position.
+    #[inline]
+    pub fn is_empty_at(&self, pos: usize) -> bool {
+        self.is_empty() && self.start == pos
+    }
+
+    /// Returns this match with both of its offsets moved by `delta`, which
+    /// may be negative.
+    ///
+    /// This returns `None` if moving either offset would overflow or
+    /// underflow a `usize`. The length of the match is always preserved.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use regex_automata::Match;
+    ///
+    /// assert_eq!(Some(Match::new(8, 10)), Match::new(5, 7).shift(3));
+    /// assert_eq!(Some(Match::new(0, 2)), Match::new(5, 7).shift(-5));
+    /// assert_eq!(None, Match::new(5, 7).shift(-6));
+    /// ```
+    #[inline]
+    pub fn shift(&self, delta: isize) -> Option<Match> {
+        let shift = |offset: usize| {
+            if delta < 0 {
+                // This is the magnitude of 'delta', computed via two's
+                // complement negation. This is correct even for 'isize::MIN',
+                // whose magnitude isn't representable as an 'isize'.
+                offset.checked_sub((!(delta as usize)).wrapping_add(1))
+            } else {
+                offset.checked_add(delta as usize)
+            }
+        };
+        Some(Match { start: shift(self.start)?, end: shift(self.end)? })
+    }
+
+    /// Returns this match with `delta` added to both of its offsets.
+    ///
+    /// This is useful for translating a match found by searching a slice of
+    /// a larger buffer back into the coordinates of the larger buffer, where
+    /// `delta` is the offset at which the slice starts.
+    ///
+    /// # Panics
+    ///
+    /// This panics if adding `delta` to the end of this match overflows a
+    /// `usize`. Use [`Match::shift`] to handle overflow instead.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use regex_automata::Match;
+    ///
+    /// let haystack = "foo bar baz";
+    /// let m = Match::new(0, 3); // found by searching &haystack[8..]
+    /// let m = m.offset(8);
+    /// assert_eq!("baz", &haystack[m.range()]);
+    /// ```
+    #[inline]
+    pub fn offset(self, delta: usize) -> Match {
+        let end = self.end.checked_add(delta).expect("match offset overflow");
+        Match { start: self.start + delta, end }
+    }
 }
 
 /// A representation of a match reported by a DFA.
@@ -114,7 +191,15 @@ impl Match {
 /// relative to other patterns used to construct the corresponding DFA. If only
 /// a single pattern is provided to the DFA, then all matches are guaranteed to
 /// have a pattern ID of `0`.
+///
+/// Half matches are ordered by their offset, with ties broken by their
+/// pattern ID.
+///
+/// The `Display` impl renders a half match as its pattern ID followed by its
+/// offset, e.g., `pattern 1 @ 7`. Unlike the `Debug` impl, this format is
+/// stable.
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct HalfMatch {
     /// The pattern ID.
     pub(crate) pattern: PatternID,
@@ -174,8 +259,19 @@ impl HalfMatch {
 /// corresponding regex engine. If only a single pattern is provided, then all
 /// multi matches are guaranteed to have a pattern ID of `0`.
 ///
-/// Every multi match guarantees that `start <= end`.
+/// Every multi match guarantees that `start <= end`. When the `serde` feature
+/// is enabled, deserializing a multi match that violates this invariant
+/// returns an error.
+///
+/// Multi matches are ordered by their start offset, then by their end offset
+/// and finally by their pattern ID. That is, they are ordered first in the
+/// same way as a [`Match`] with the same offsets.
+///
+/// The `Display` impl renders a multi match as its pattern ID followed by its
+/// range of offsets, e.g., `pattern 0: 5..10`. Unlike the `Debug` impl, this
+/// format is stable.
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct MultiMatch {
     /// The pattern ID.
     pattern: PatternID,
@@ -211,6 +307,40 @@ impl MultiMatch {
         MultiMatch::new(PatternID::new(pattern).unwrap(), start, end)
     }
 
+    /// Create a new multi match from a pair of half matches.
+    ///
+    /// This codifies the common pattern of running a forward search to find
+    /// the end of a match and then a reverse search to find its start. The
+    /// `start` half match should come from the reverse search and the `end`
+    /// half match should come from the forward search.
+    ///
+    /// This returns `None` if the pattern IDs of the two half matches differ,
+    /// or if `start.offset() > end.offset()`.
+    #[inline]
+    pub fn from_half_matches(
+        start: HalfMatch,
+        end: HalfMatch,
+    ) -> Option<MultiMatch> {
+        if start.pattern() != end.pattern() || start.offset() > end.offset() {
+            return None;
+        }
+        Some(MultiMatch::new(end.pattern(), start.offset(), end.offset()))
+    }
+
+    /// Split this multi match into a pair of half matches.
+    ///
+    /// The first half match corresponds to the start of this match (as would
+    /// be reported by a reverse search) and the second corresponds to the end
+    /// of this match (as would be reported by a forward search). Both half
+    /// matches have the same pattern ID as this match.
+    #[inline]
+    pub fn as_half_matches(&self) -> (HalfMatch, HalfMatch) {
+        (
+            HalfMatch::new(self.pattern, self.start),
+            HalfMatch::new(self.pattern, self.end),
+        )
+    }
+
     /// Returns the ID of the pattern that matched.
     ///
     /// The ID of a pattern is derived from the position in which it was
@@ -251,6 +381,119 @@ impl MultiMatch {
     }
 }
 
+impl Ord for Match {
+    fn cmp(&self, other: &Match) -> core::cmp::Ordering {
+        (self.start, self.end).cmp(&(other.start, other.end))
+    }
+}
+
+impl PartialOrd for Match {
+    fn partial_cmp(&self, other: &Match) -> Option<core::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for HalfMatch {
+    fn cmp(&self, other: &HalfMatch) -> core::cmp::Ordering {
+        (self.offset, self.pattern).cmp(&(other.offset, other.pattern))
+    }
+}
+
+impl PartialOrd for HalfMatch {
+    fn partial_cmp(&self, other: &HalfMatch) -> Option<core::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for MultiMatch {
+    fn cmp(&self, other: &MultiMatch) -> core::cmp::Ordering {
+        (self.start, self.end, self.pattern).cmp(&(
+            other.start,
+            other.end,
+            other.pattern,
+        ))
+    }
+}
+
+impl PartialOrd for MultiMatch {
+    fn partial_cmp(&self, other: &MultiMatch) -> Option<core::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl core::fmt::Display for Match {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "{}..{}", self.start, self.end)
+    }
+}
+
+impl core::fmt::Display for HalfMatch {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "pattern {} @ {}", self.pattern.as_usize(), self.offset)
+    }
+}
+
+impl core::fmt::Display for MultiMatch {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(
+            f,
+            "pattern {}: {}..{}",
+            self.pattern.as_usize(),
+            self.start,
+            self.end,
+        )
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Match {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Match, D::Error> {
+        #[derive(serde::Deserialize)]
+        #[serde(rename = "Match")]
+        struct Raw {
+            start: usize,
+            end: usize,
+        }
+
+        let Raw { start, end } = Raw::deserialize(deserializer)?;
+        check_span::<D::Error>(start, end)?;
+        Ok(Match::new(start, end))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for MultiMatch {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<MultiMatch, D::Error> {
+        #[derive(serde::Deserialize)]
+        #[serde(rename = "MultiMatch")]
+        struct Raw {
+            pattern: PatternID,
+            start: usize,
+            end: usize,
+        }
+
+        let Raw { pattern, start, end } = Raw::deserialize(deserializer)?;
+        check_span::<D::Error>(start, end)?;
+        Ok(MultiMatch::new(pattern, start, end))
+    }
+}
+
+/// Returns an error if the given offsets do not satisfy `start <= end`.
+#[cfg(feature = "serde")]
+fn check_span<E: serde::de::Error>(start: usize, end: usize) -> Result<(), E> {
+    if end < start {
+        return Err(E::custom(format_args!(
+            "invalid match span: end ({}) is less than start ({})",
+            end, start,
+        )));
+    }
+    Ok(())
+}
+
 /// An error type indicating that a search stopped prematurely without finding
 /// a match.
 ///
@@ -354,3 +597,207 @@ impl core::fmt::Display for MatchError {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn multi_match_from_half_matches() {
+        let m = MultiMatch::from_half_matches(
+            HalfMatch::must(1, 3),
+            HalfMatch::must(1, 7),
+        );
+        assert_eq!(m, Some(MultiMatch::must(1, 3, 7)));
+
+        let m = MultiMatch::from_half_matches(
+            HalfMatch::must(2, 5),
+            HalfMatch::must(2, 5),
+        );
+        assert_eq!(m, Some(MultiMatch::must(2, 5, 5)));
+    }
+
+    #[test]
+    fn multi_match_from_half_matches_mismatched_patterns() {
+        let m = MultiMatch::from_half_matches(
+            HalfMatch::must(0, 3),
+            HalfMatch::must(1, 7),
+        );
+        assert_eq!(m, None);
+    }
+
+    #[test]
+    fn multi_match_from_half_matches_inverted_offsets() {
+        let m = MultiMatch::from_half_matches(
+            HalfMatch::must(0, 7),
+            HalfMatch::must(0, 3),
+        );
+        assert_eq!(m, None);
+    }
+
+    #[test]
+    fn multi_match_as_half_matches() {
+        let m = MultiMatch::must(4, 2, 9);
+        let (start, end) = m.as_half_matches();
+        assert_eq!(start, HalfMatch::must(4, 2));
+        assert_eq!(end, HalfMatch::must(4, 9));
+        assert_eq!(MultiMatch::from_half_matches(start, end), Some(m));
+    }
+
+    #[test]
+    fn match_ordering() {
+        let mut got = [
+            Match::new(1, 3),
+            Match::new(0, 5),
+            Match::new(1, 2),
+            Match::new(1, 1),
+        ];
+        got.sort_unstable();
+        assert_eq!(
+            got,
+            [
+                Match::new(0, 5),
+                Match::new(1, 1),
+                Match::new(1, 2),
+                Match::new(1, 3),
+            ]
+        );
+    }
+
+    #[test]
+    fn half_match_ordering() {
+        // The offset takes priority over the pattern ID.
+        assert!(HalfMatch::must(5, 1) < HalfMatch::must(0, 2));
+        assert!(HalfMatch::must(0, 2) < HalfMatch::must(1, 2));
+        assert_eq!(
+            HalfMatch::must(1, 2).cmp(&HalfMatch::must(1, 2)),
+            core::cmp::Ordering::Equal,
+        );
+    }
+
+    #[test]
+    fn multi_match_ordering() {
+        let mut got = [
+            MultiMatch::must(0, 1, 3),
+            MultiMatch::must(2, 1, 2),
+            MultiMatch::must(1, 1, 2),
+            MultiMatch::must(3, 0, 9),
+        ];
+        got.sort_unstable();
+        assert_eq!(
+            got,
+            [
+                MultiMatch::must(3, 0, 9),
+                MultiMatch::must(1, 1, 2),
+                MultiMatch::must(2, 1, 2),
+                MultiMatch::must(0, 1, 3),
+            ]
+        );
+    }
+
+    #[test]
+    fn match_is_empty_at() {
+        assert!(Match::new(5, 5).is_empty_at(5));
+        assert!(!Match::new(5, 5).is_empty_at(4));
+        assert!(!Match::new(5, 6).is_empty_at(5));
+    }
+
+    #[test]
+    fn match_shift() {
+        let m = Match::new(5, 10);
+        assert_eq!(m.shift(0), Some(m.clone()));
+        assert_eq!(m.shift(2), Some(Match::new(7, 12)));
+        assert_eq!(m.shift(-5), Some(Match::new(0, 5)));
+        assert_eq!(m.shift(-6), None);
+
+        let max = isize::MAX as usize;
+        assert_eq!(
+            Match::new(0, max).shift(isize::MAX),
+            Some(Match::new(max, 2 * max)),
+        );
+        assert_eq!(Match::new(0, usize::MAX).shift(1), None);
+        assert_eq!(Match::new(usize::MAX, usize::MAX).shift(1), None);
+        // The magnitude of isize::MIN is one more than isize::MAX.
+        let min_magnitude = (isize::MAX as usize) + 1;
+        assert_eq!(
+            Match::new(usize::MAX, usize::MAX).shift(isize::MIN),
+            Some(Match::new(
+                usize::MAX - min_magnitude,
+                usize::MAX - min_magnitude,
+            )),
+        );
+        assert_eq!(Match::new(0, 5).shift(isize::MIN), None);
+    }
+
+    #[test]
+    fn match_offset() {
+        assert_eq!(Match::new(0, 3).offset(8), Match::new(8, 11));
+        assert_eq!(Match::new(4, 4).offset(0), Match::new(4, 4));
+        assert_eq!(
+            Match::new(0, 1).offset(usize::MAX - 1),
+            Match::new(usize::MAX - 1, usize::MAX),
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn match_offset_overflow() {
+        Match::new(0, 1).offset(usize::MAX);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn display() {
+        use alloc::string::ToString;
+
+        assert_eq!(Match::new(5, 10).to_string(), "5..10");
+        assert_eq!(Match::new(3, 3).to_string(), "3..3");
+        assert_eq!(HalfMatch::must(1, 7).to_string(), "pattern 1 @ 7");
+        assert_eq!(MultiMatch::must(0, 5, 10).to_string(), "pattern 0: 5..10");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_roundtrip() {
+        let m = Match::new(2, 9);
+        let json = serde_json::to_string(&m).unwrap();
+        assert_eq!(json, r#"{"start":2,"end":9}"#);
+        assert_eq!(serde_json::from_str::<Match>(&json).unwrap(), m);
+
+        let hm = HalfMatch::must(3, 5);
+        let json = serde_json::to_string(&hm).unwrap();
+        assert_eq!(json, r#"{"pattern":3,"offset":5}"#);
+        assert_eq!(serde_json::from_str::<HalfMatch>(&json).unwrap(), hm);
+
+        let mm = MultiMatch::must(1, 4, 4);
+        let json = serde_json::to_string(&mm).unwrap();
+        assert_eq!(json, r#"{"pattern":1,"start":4,"end":4}"#);
+        assert_eq!(serde_json::from_str::<MultiMatch>(&json).unwrap(), mm);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_rejects_invalid_span() {
+        let json = r#"{"start":9,"end":2}"#;
+        assert!(serde_json::from_str::<Match>(json).is_err());
+
+        let json = r#"{"pattern":0,"start":9,"end":2}"#;
+        assert!(serde_json::from_str::<MultiMatch>(json).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_rejects_invalid_pattern_id() {
+        let json = format!("{}", PatternID::MAX.as_u32());
+        assert_eq!(
+            serde_json::from_str::<PatternID>(&json).unwrap(),
+            PatternID::MAX,
+        );
+
+        let json = format!("{}", PatternID::LIMIT);
+        assert!(serde_json::from_str::<PatternID>(&json).is_err());
+
+        let json = format!(r#"{{"pattern":{},"offset":0}}"#, PatternID::LIMIT);
+        assert!(serde_json::from_str::<HalfMatch>(&json).is_err());
+    }
+}
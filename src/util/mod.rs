@@ -4,6 +4,9 @@ TODO
 
 use core::{ascii, fmt, str};
 
+#[cfg(feature = "alloc")]
+use crate::util::matchtypes::Match;
+
 #[cfg(feature = "alloc")]
 use alloc::vec::Vec;
 
@@ -18,7 +21,7 @@ pub(crate) mod matchtypes;
 pub mod prefilter;
 #[cfg(feature = "alloc")]
 pub(crate) mod sparse_set;
-pub(crate) mod start;
+pub mod start;
 #[cfg(feature = "alloc")]
 pub(crate) mod syntax;
 
@@ -83,6 +86,211 @@ pub(crate) fn next_utf8(text: &[u8], i: usize) -> usize {
     i.checked_add(inc).unwrap()
 }
 
+/// Returns an iterator over every UTF-8 character boundary in the given
+/// haystack, in ascending order.
+///
+/// The iterator always yields `0` first and `haystack.len()` last. (For an
+/// empty haystack, only `0` is yielded.) In between, it yields the offset
+/// immediately following each encoded codepoint.
+///
+/// When the haystack contains invalid UTF-8, each byte that is not part of a
+/// valid encoding of a codepoint is treated as its own "character." That is,
+/// a boundary is reported both before and after each such byte.
+pub fn char_boundaries(haystack: &[u8]) -> CharBoundaries<'_> {
+    CharBoundaries { haystack, at: Some(0) }
+}
+
+/// An iterator over the UTF-8 character boundaries in a haystack.
+///
+/// This is created by [`char_boundaries`].
+#[derive(Clone, Debug)]
+pub struct CharBoundaries<'h> {
+    haystack: &'h [u8],
+    at: Option<usize>,
+}
+
+impl<'h> Iterator for CharBoundaries<'h> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        let at = self.at?;
+        self.at = match decode_utf8(&self.haystack[at..]) {
+            None => None,
+            Some(Ok(ch)) => Some(at + ch.len_utf8()),
+            Some(Err(_)) => Some(at + 1),
+        };
+        Some(at)
+    }
+}
+
+impl<'h> core::iter::FusedIterator for CharBoundaries<'h> {}
+
+/// Returns an iterator over every UTF-8 encoded codepoint in the given
+/// haystack, along with the byte offset at which it starts.
+///
+/// Each item is either the decoded `char` or, when no valid encoding of a
+/// codepoint starts at that offset, the invalid byte at that offset. In the
+/// latter case, the iterator advances by exactly one byte. This is consistent
+/// with how [`char_boundaries`] treats invalid UTF-8.
+///
+/// # Example
+///
+/// ```
+/// use regex_automata::util::decode_utf8_iter;
+///
+/// let got: Vec<(usize, Result<char, u8>)> =
+///     decode_utf8_iter(b"a\xE2\x98\x83\xFFb").collect();
+/// assert_eq!(got, vec![
+///     (0, Ok('a')),
+///     (1, Ok('☃')),
+///     (4, Err(0xFF)),
+///     (5, Ok('b')),
+/// ]);
+/// ```
+pub fn decode_utf8_iter(haystack: &[u8]) -> DecodeUtf8<'_> {
+    DecodeUtf8 { haystack, at: 0 }
+}
+
+/// An iterator over the UTF-8 encoded codepoints in a haystack.
+///
+/// This is created by [`decode_utf8_iter`].
+#[derive(Clone, Debug)]
+pub struct DecodeUtf8<'h> {
+    haystack: &'h [u8],
+    at: usize,
+}
+
+impl<'h> Iterator for DecodeUtf8<'h> {
+    type Item = (usize, Result<char, u8>);
+
+    fn next(&mut self) -> Option<(usize, Result<char, u8>)> {
+        let at = self.at;
+        let result = decode_utf8(self.haystack.get(at..)?)?;
+        self.at = match result {
+            Ok(_) => next_utf8(self.haystack, at),
+            Err(_) => at + 1,
+        };
+        Some((at, result))
+    }
+}
+
+impl<'h> core::iter::FusedIterator for DecodeUtf8<'h> {}
+
+/// Returns an iterator over every UTF-8 encoded codepoint in the given
+/// haystack in reverse, along with the byte offset at which it starts.
+///
+/// This is the reverse of [`decode_utf8_iter`]: it yields the same items,
+/// but from the end of the haystack to its beginning. In particular, each
+/// byte that is not part of a valid encoding of a codepoint is yielded as its
+/// own item, and the iterator moves back by exactly one byte.
+///
+/// # Example
+///
+/// ```
+/// use regex_automata::util::decode_utf8_rev_iter;
+///
+/// let got: Vec<(usize, Result<char, u8>)> =
+///     decode_utf8_rev_iter(b"a\xE2\x98\x83b\xFF").collect();
+/// assert_eq!(got, vec![
+///     (5, Err(0xFF)),
+///     (4, Ok('b')),
+///     (1, Ok('☃')),
+///     (0, Ok('a')),
+/// ]);
+/// ```
+pub fn decode_utf8_rev_iter(haystack: &[u8]) -> DecodeUtf8Rev<'_> {
+    DecodeUtf8Rev { haystack, end: haystack.len() }
+}
+
+/// An iterator over the UTF-8 encoded codepoints in a haystack, in reverse.
+///
+/// This is created by [`decode_utf8_rev_iter`].
+#[derive(Clone, Debug)]
+pub struct DecodeUtf8Rev<'h> {
+    haystack: &'h [u8],
+    end: usize,
+}
+
+impl<'h> Iterator for DecodeUtf8Rev<'h> {
+    type Item = (usize, Result<char, u8>);
+
+    fn next(&mut self) -> Option<(usize, Result<char, u8>)> {
+        let bytes = &self.haystack[..self.end];
+        let result = match decode_last_utf8(bytes)? {
+            // 'decode_last_utf8' only looks for the nearest leading byte, so
+            // the codepoint it decodes might end before the end of 'bytes'
+            // (e.g., when a valid codepoint is followed by a stray
+            // continuation byte). In that case, only the last byte is
+            // yielded, which is consistent with decoding forwards.
+            Ok(ch) => {
+                let start = bytes.len() - ch.len_utf8();
+                if decode_utf8(&bytes[start..]) == Some(Ok(ch)) {
+                    Ok(ch)
+                } else {
+                    Err(bytes[bytes.len() - 1])
+                }
+            }
+            Err(byte) => Err(byte),
+        };
+        self.end -= match result {
+            Ok(ch) => ch.len_utf8(),
+            Err(_) => 1,
+        };
+        Some((self.end, result))
+    }
+}
+
+impl<'h> core::iter::FusedIterator for DecodeUtf8Rev<'h> {}
+
+/// Coalesce the given matches into the smallest sequence of matches that
+/// covers the same regions of a haystack.
+///
+/// After this returns, `matches` is sorted in ascending order, and no two
+/// matches in it overlap or touch. That is, for each pair of consecutive
+/// matches `a` and `b`, it is guaranteed that `a.end() < b.start()`. Matches
+/// that overlap, as well as matches where one ends exactly where the other
+/// starts, are merged into a single match spanning both.
+///
+/// Empty matches (i.e., where [`Match::is_empty`] returns true) cover no
+/// part of the haystack, and are therefore always removed.
+///
+/// # Example
+///
+/// ```
+/// use regex_automata::{util::merge_matches, Match};
+///
+/// let mut matches = vec![
+///     Match::new(8, 10),
+///     Match::new(0, 3),
+///     Match::new(2, 5),
+///     Match::new(5, 6),
+///     Match::new(7, 7),
+/// ];
+/// merge_matches(&mut matches);
+/// assert_eq!(matches, vec![Match::new(0, 6), Match::new(8, 10)]);
+/// ```
+#[cfg(feature = "alloc")]
+pub fn merge_matches(matches: &mut Vec<Match>) {
+    matches.retain(|m| !m.is_empty());
+    matches.sort();
+    // Merge in place, where 'len' is the number of merged matches written to
+    // the front of 'matches' so far.
+    let mut len = 0;
+    for i in 0..matches.len() {
+        let m = matches[i].clone();
+        if len > 0 && m.start() <= matches[len - 1].end() {
+            let last = &mut matches[len - 1];
+            if m.end() > last.end() {
+                *last = Match::new(last.start(), m.end());
+            }
+        } else {
+            matches[len] = m;
+            len += 1;
+        }
+    }
+    matches.truncate(len);
+}
+
 /// Returns true if and only if the given byte is considered a word character.
 /// This only applies to ASCII.
 ///
@@ -273,3 +481,150 @@ pub(crate) fn is_word_char_rev(bytes: &[u8], mut at: usize) -> bool {
     }
     dfa.is_match_state(dfa.next_eoi_state(sid))
 }
+
+#[cfg(test)]
+#[cfg(feature = "alloc")]
+mod tests {
+    use alloc::{vec, vec::Vec};
+
+    use super::*;
+
+    #[test]
+    fn char_boundaries_valid() {
+        let got: Vec<usize> = char_boundaries("a☃b".as_bytes()).collect();
+        assert_eq!(got, vec![0, 1, 4, 5]);
+
+        let got: Vec<usize> = char_boundaries(b"").collect();
+        assert_eq!(got, vec![0]);
+    }
+
+    #[test]
+    fn char_boundaries_invalid() {
+        // A truncated snowman followed by an invalid byte. Each byte that
+        // isn't part of a valid encoding is its own character.
+        let got: Vec<usize> = char_boundaries(b"a\xE2\x98\xFFb").collect();
+        assert_eq!(got, vec![0, 1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn decode_utf8_iter_valid() {
+        let got: Vec<(usize, Result<char, u8>)> =
+            decode_utf8_iter("a☃𝛃".as_bytes()).collect();
+        assert_eq!(got, vec![(0, Ok('a')), (1, Ok('☃')), (4, Ok('𝛃'))]);
+
+        assert_eq!(decode_utf8_iter(b"").next(), None);
+    }
+
+    #[test]
+    fn decode_utf8_iter_invalid() {
+        // A truncated snowman is reported one byte at a time, as are bytes
+        // that can never appear in valid UTF-8 and stray continuation bytes.
+        let got: Vec<(usize, Result<char, u8>)> =
+            decode_utf8_iter(b"a\xE2\x98b\xFF\x80\xE2\x98\x83").collect();
+        assert_eq!(
+            got,
+            vec![
+                (0, Ok('a')),
+                (1, Err(0xE2)),
+                (2, Err(0x98)),
+                (3, Ok('b')),
+                (4, Err(0xFF)),
+                (5, Err(0x80)),
+                (6, Ok('☃')),
+            ]
+        );
+
+        // A truncated sequence at the very end of the haystack.
+        let got: Vec<(usize, Result<char, u8>)> =
+            decode_utf8_iter(b"\xF0\x9D").collect();
+        assert_eq!(got, vec![(0, Err(0xF0)), (1, Err(0x9D))]);
+    }
+
+    #[test]
+    fn decode_utf8_rev_iter_valid() {
+        let got: Vec<(usize, Result<char, u8>)> =
+            decode_utf8_rev_iter("a☃b".as_bytes()).collect();
+        assert_eq!(got, vec![(4, Ok('b')), (1, Ok('☃')), (0, Ok('a'))]);
+
+        assert_eq!(decode_utf8_rev_iter(b"").next(), None);
+    }
+
+    #[test]
+    fn decode_utf8_rev_iter_invalid() {
+        let got: Vec<(usize, Result<char, u8>)> =
+            decode_utf8_rev_iter(b"a\xE2\x98\x83b\xFF").collect();
+        assert_eq!(
+            got,
+            vec![(5, Err(0xFF)), (4, Ok('b')), (1, Ok('☃')), (0, Ok('a'))]
+        );
+
+        // Every haystack should decode to the same items in either
+        // direction, including truncated sequences and a valid codepoint
+        // followed by a stray continuation byte.
+        let haystacks: &[&[u8]] = &[
+            b"a\xE2\x98b\xFF\x80\xE2\x98\x83",
+            b"\xE2\x98\x83\x80",
+            b"\xF0\x9D",
+            b"\x80\x80\x80\x80\x80",
+        ];
+        for &haystack in haystacks {
+            let mut fwd: Vec<(usize, Result<char, u8>)> =
+                decode_utf8_iter(haystack).collect();
+            fwd.reverse();
+            let rev: Vec<(usize, Result<char, u8>)> =
+                decode_utf8_rev_iter(haystack).collect();
+            assert_eq!(fwd, rev, "haystack: {:?}", haystack);
+        }
+    }
+
+    #[test]
+    fn merge_matches_disjoint() {
+        let mut got =
+            vec![Match::new(5, 7), Match::new(0, 2), Match::new(3, 4)];
+        merge_matches(&mut got);
+        assert_eq!(
+            got,
+            vec![Match::new(0, 2), Match::new(3, 4), Match::new(5, 7)]
+        );
+    }
+
+    #[test]
+    fn merge_matches_overlapping() {
+        let mut got = vec![
+            Match::new(2, 6),
+            Match::new(0, 3),
+            Match::new(3, 4),
+            Match::new(10, 20),
+            Match::new(12, 15),
+        ];
+        merge_matches(&mut got);
+        assert_eq!(got, vec![Match::new(0, 6), Match::new(10, 20)]);
+    }
+
+    #[test]
+    fn merge_matches_touching() {
+        let mut got =
+            vec![Match::new(3, 5), Match::new(0, 3), Match::new(5, 6)];
+        merge_matches(&mut got);
+        assert_eq!(got, vec![Match::new(0, 6)]);
+    }
+
+    #[test]
+    fn merge_matches_empty() {
+        // Empty matches are always dropped, even when they sit between two
+        // matches that would otherwise touch.
+        let mut got =
+            vec![Match::new(1, 1), Match::new(4, 4), Match::new(4, 6)];
+        merge_matches(&mut got);
+        assert_eq!(got, vec![Match::new(4, 6)]);
+
+        let mut got =
+            vec![Match::new(0, 2), Match::new(2, 2), Match::new(3, 5)];
+        merge_matches(&mut got);
+        assert_eq!(got, vec![Match::new(0, 2), Match::new(3, 5)]);
+
+        let mut got: Vec<Match> = vec![];
+        merge_matches(&mut got);
+        assert_eq!(got, vec![]);
+    }
+}
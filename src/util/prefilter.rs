@@ -1,4 +1,7 @@
-use crate::Match;
+use crate::{
+    util::{alphabet, DebugByte},
+    Match,
+};
 
 /// A candidate is the result of running a prefilter on a haystack at a
 /// particular position. The result is one of no match, a confirmed match or
@@ -43,6 +46,20 @@ impl Candidate {
             Candidate::PossibleStartOfMatch(start) => Some(start),
         }
     }
+
+    /// Convert a candidate reported by [`Prefilter::find_rev`] into an
+    /// option.
+    ///
+    /// This is like [`Candidate::into_option`], except the byte offset
+    /// returned corresponds to the ending position of the possible match,
+    /// which is where a reverse search starts.
+    pub fn into_option_rev(self) -> Option<usize> {
+        match self {
+            Candidate::None => None,
+            Candidate::Match(ref m) => Some(m.end()),
+            Candidate::PossibleStartOfMatch(end) => Some(end),
+        }
+    }
 }
 
 /// A prefilter describes the behavior of fast literal scanners for quickly
@@ -65,6 +82,34 @@ pub trait Prefilter: core::fmt::Debug {
     /// units of bytes.
     fn heap_bytes(&self) -> usize;
 
+    /// Returns the previous possible match candidate when searching in
+    /// reverse, or `None` if this prefilter does not support reverse
+    /// searches.
+    ///
+    /// A reverse search starts at the end of a match, so the candidate
+    /// reported corresponds to a position at which a match may *end*. That
+    /// is, `Candidate::PossibleStartOfMatch(i)` reports that a reverse search
+    /// may start matching at `i`, and `Candidate::Match` reports a confirmed
+    /// match whose end is where the reverse search would begin. The position
+    /// reported must be less than or equal to `at`, and only the bytes in
+    /// `haystack[..at]` may be part of the match.
+    ///
+    /// Like [`Prefilter::next_candidate`], this may yield false positives but
+    /// must never produce false negatives. That is, this must, at minimum,
+    /// return the ending position of the last match in `haystack` that ends
+    /// at or before `at`.
+    ///
+    /// By default, this returns `None`, which causes reverse searches to run
+    /// without a prefilter.
+    fn find_rev(
+        &self,
+        _state: &mut State,
+        _haystack: &[u8],
+        _at: usize,
+    ) -> Option<Candidate> {
+        None
+    }
+
     /// Returns true if and only if this prefilter may return false positives
     /// via the `Candidate::PossibleStartOfMatch` variant. This is most useful
     /// when false positives are not posssible (in which case, implementations
@@ -94,6 +139,16 @@ impl<'a, P: Prefilter + ?Sized> Prefilter for &'a P {
         (**self).heap_bytes()
     }
 
+    #[inline]
+    fn find_rev(
+        &self,
+        state: &mut State,
+        haystack: &[u8],
+        at: usize,
+    ) -> Option<Candidate> {
+        (**self).find_rev(state, haystack, at)
+    }
+
     fn reports_false_positives(&self) -> bool {
         (**self).reports_false_positives()
     }
@@ -137,6 +192,30 @@ impl<'p> Scanner<'p> {
         }
         cand
     }
+
+    pub(crate) fn is_effective_rev(&mut self) -> bool {
+        self.state.is_effective_rev()
+    }
+
+    pub(crate) fn find_rev(
+        &mut self,
+        bytes: &[u8],
+        at: usize,
+    ) -> Option<Candidate> {
+        let cand = self.prefilter.find_rev(&mut self.state, bytes, at)?;
+        match cand {
+            Candidate::None => {
+                self.state.update_skipped_bytes(at);
+            }
+            Candidate::Match(ref m) => {
+                self.state.update_skipped_bytes(at - m.end());
+            }
+            Candidate::PossibleStartOfMatch(i) => {
+                self.state.update_skipped_bytes(at - i);
+            }
+        }
+        Some(cand)
+    }
 }
 
 impl<'p> core::fmt::Debug for Scanner<'p> {
@@ -234,6 +313,17 @@ impl State {
         if at < self.last_scan_at {
             return false;
         }
+        self.is_effective_rev()
+    }
+
+    /// Like `is_effective`, but for reverse searches.
+    ///
+    /// The last scan position only tracks forward scans, so it is ignored
+    /// here. Only the average number of bytes skipped is considered.
+    fn is_effective_rev(&mut self) -> bool {
+        if self.inert {
+            return false;
+        }
         if self.skips < State::MIN_SKIPS {
             return true;
         }
@@ -279,3 +369,145 @@ impl Prefilter for None {
         0
     }
 }
+
+/// A `Prefilter` implementation that reports the position of the next byte
+/// in a fixed set of bytes.
+///
+/// This is useful when one knows the set of bytes that every match must
+/// start with. For example, every match of `[aeiou][0-9]+` must start with
+/// one of `a`, `e`, `i`, `o` or `u`. When the set has at most 3 bytes, this
+/// uses `memchr`. Otherwise, it falls back to checking each byte against the
+/// set.
+///
+/// This prefilter never confirms a match on its own. It only advances the
+/// position at which a search starts, and so callers must still run a regex
+/// engine to confirm each candidate.
+///
+/// Note that this prefilter is only correct when *every* match starts with a
+/// byte in the set. In particular, it must not be used with a regex that can
+/// match the empty string, since an empty match may occur at a position
+/// whose byte is not in the set (or at the end of the haystack). An empty set
+/// reports that no match is possible.
+///
+/// This prefilter also supports reverse searches. In that case, the set must
+/// contain every byte that a match may *end* with, and each candidate is
+/// reported just past the last such byte before the search position.
+///
+/// # Example
+///
+/// ```
+/// use regex_automata::{dfa::regex::Regex, util::prefilter, MultiMatch};
+///
+/// let re = Regex::new(r"[aeiou][0-9]+")?
+///     .with_prefilter(prefilter::ByteSet::new(b"aeiou"));
+/// let matches: Vec<MultiMatch> =
+///     re.find_leftmost_iter(b"xyz a1 bcd u23").collect();
+/// assert_eq!(matches, vec![
+///     MultiMatch::must(0, 4, 6),
+///     MultiMatch::must(0, 11, 14),
+/// ]);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Clone)]
+pub struct ByteSet {
+    /// A membership table indexed by byte.
+    table: [bool; 256],
+    /// The number of distinct bytes in this set.
+    len: usize,
+    /// The first (at most) 3 distinct bytes in this set, which are used with
+    /// `memchr` when `len <= 3`.
+    needles: [u8; 3],
+}
+
+impl ByteSet {
+    /// Create a new prefilter that reports the position of any of the given
+    /// bytes. Duplicate bytes are ignored.
+    pub fn new(bytes: &[u8]) -> ByteSet {
+        let mut set = ByteSet { table: [false; 256], len: 0, needles: [0; 3] };
+        for &b in bytes {
+            set.add(b);
+        }
+        set
+    }
+
+    /// Add the given byte to this set. If it's already in the set, then this
+    /// is a no-op.
+    fn add(&mut self, b: u8) {
+        if self.table[usize::from(b)] {
+            return;
+        }
+        self.table[usize::from(b)] = true;
+        if self.len < self.needles.len() {
+            self.needles[self.len] = b;
+        }
+        self.len += 1;
+    }
+}
+
+/// Build a prefilter from a set of bytes, such as the one returned by
+/// [`NFA::first_byte_set`](crate::nfa::thompson::NFA::first_byte_set).
+impl From<alphabet::ByteSet> for ByteSet {
+    fn from(bytes: alphabet::ByteSet) -> ByteSet {
+        let mut set = ByteSet::new(&[]);
+        for b in (0..=255).filter(|&b| bytes.contains(b)) {
+            set.add(b);
+        }
+        set
+    }
+}
+
+impl Prefilter for ByteSet {
+    fn next_candidate(
+        &self,
+        _: &mut State,
+        haystack: &[u8],
+        at: usize,
+    ) -> Candidate {
+        let (hay, bs) = (&haystack[at..], &self.needles);
+        let i = match self.len {
+            0 => return Candidate::None,
+            1 => memchr::memchr(bs[0], hay),
+            2 => memchr::memchr2(bs[0], bs[1], hay),
+            3 => memchr::memchr3(bs[0], bs[1], bs[2], hay),
+            _ => hay.iter().position(|&b| self.table[usize::from(b)]),
+        };
+        match i {
+            None => Candidate::None,
+            Some(i) => Candidate::PossibleStartOfMatch(at + i),
+        }
+    }
+
+    fn heap_bytes(&self) -> usize {
+        0
+    }
+
+    fn find_rev(
+        &self,
+        _: &mut State,
+        haystack: &[u8],
+        at: usize,
+    ) -> Option<Candidate> {
+        let (hay, bs) = (&haystack[..at], &self.needles);
+        let i = match self.len {
+            0 => return Some(Candidate::None),
+            1 => memchr::memrchr(bs[0], hay),
+            2 => memchr::memrchr2(bs[0], bs[1], hay),
+            3 => memchr::memrchr3(bs[0], bs[1], bs[2], hay),
+            _ => hay.iter().rposition(|&b| self.table[usize::from(b)]),
+        };
+        Some(match i {
+            None => Candidate::None,
+            Some(i) => Candidate::PossibleStartOfMatch(i + 1),
+        })
+    }
+}
+
+impl core::fmt::Debug for ByteSet {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        let mut fmtd = f.debug_set();
+        for b in (0..=255u8).filter(|&b| self.table[usize::from(b)]) {
+            fmtd.entry(&DebugByte(b));
+        }
+        fmtd.finish()
+    }
+}
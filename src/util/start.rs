@@ -16,8 +16,38 @@
 /// might point to the same state if the states would otherwise be equivalent.
 /// (This is guaranteed by DFA minimization and may even be accomplished by
 /// normal determinization, since it attempts to reuse equivalent states too.)
+///
+/// # Kinds
+///
+/// For a forward search beginning at offset `at`, the kinds are checked in
+/// the following order, and the first that applies is chosen:
+///
+/// * [`Start::Text`] when `at == 0`.
+/// * [`Start::Line`] when the byte at `at - 1` is `\n`.
+/// * [`Start::WordByte`] when the byte at `at - 1` is an ASCII word byte.
+/// * [`Start::NonWordByte`] otherwise.
+///
+/// A reverse search ending at offset `at` is classified the same way, except
+/// that `Start::Text` applies when `at` is the length of the haystack, and the
+/// byte at `at` is examined instead of the byte at `at - 1`.
+///
+/// New starting configurations may be added in a semver compatible release,
+/// so this enum is marked as non-exhaustive.
+///
+/// # Example
+///
+/// ```
+/// use regex_automata::util::start::Start;
+///
+/// let haystack = b"ab\n-";
+/// assert_eq!(Start::Text, Start::from_bytes_at(haystack, 0));
+/// assert_eq!(Start::WordByte, Start::from_bytes_at(haystack, 1));
+/// assert_eq!(Start::Line, Start::from_bytes_at(haystack, 3));
+/// assert_eq!(Start::NonWordByte, Start::from_bytes_at(haystack, 4));
+/// ```
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
-pub(crate) enum Start {
+#[non_exhaustive]
+pub enum Start {
     /// This occurs when the starting position is not any of the ones below.
     NonWordByte = 0,
     /// This occurs when the byte immediately preceding the start of the search
@@ -44,6 +74,28 @@ impl Start {
         }
     }
 
+    /// Returns the starting configuration of a forward search that begins
+    /// at the given offset in `bytes`.
+    ///
+    /// This is the configuration used to choose a DFA's start state, and is
+    /// useful for understanding why a particular start state was chosen near
+    /// an assertion like `^` or `\b`. If `at` is greater than the length of
+    /// `bytes`, then this panics.
+    pub fn from_bytes_at(bytes: &[u8], at: usize) -> Start {
+        Start::from_position_fwd(bytes, at, bytes.len())
+    }
+
+    /// Returns a short name for this starting configuration, which is the
+    /// same as its variant name.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Start::NonWordByte => "NonWordByte",
+            Start::WordByte => "WordByte",
+            Start::Text => "Text",
+            Start::Line => "Line",
+        }
+    }
+
     /// Returns the total number of starting state configurations.
     pub(crate) fn count() -> usize {
         4
@@ -107,3 +159,42 @@ impl Start {
         *self as usize
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_bytes_at() {
+        let haystack = b"foo\nbar baz";
+        // start of text
+        assert_eq!(Start::Text, Start::from_bytes_at(haystack, 0));
+        assert_eq!(Start::Text, Start::from_bytes_at(b"", 0));
+        // after a line terminator
+        assert_eq!(Start::Line, Start::from_bytes_at(haystack, 4));
+        // after a word byte
+        assert_eq!(Start::WordByte, Start::from_bytes_at(haystack, 2));
+        assert_eq!(Start::WordByte, Start::from_bytes_at(haystack, 3));
+        // after a non-word byte
+        assert_eq!(Start::NonWordByte, Start::from_bytes_at(haystack, 8));
+        // end of text, which depends only on the preceding byte
+        assert_eq!(Start::WordByte, Start::from_bytes_at(haystack, 11));
+        assert_eq!(Start::Line, Start::from_bytes_at(b"a\n", 2));
+        assert_eq!(Start::NonWordByte, Start::from_bytes_at(b"a ", 2));
+    }
+
+    #[test]
+    #[should_panic]
+    fn from_bytes_at_out_of_bounds() {
+        Start::from_bytes_at(b"abc", 4);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn as_str() {
+        for i in 0..Start::count() {
+            let start = Start::from_usize(i).unwrap();
+            assert_eq!(format!("{:?}", start), start.as_str());
+        }
+    }
+}
@@ -1,9 +1,16 @@
 use std::error::Error;
 
 use regex_automata::{
-    dfa::{dense, regex::Regex, Automaton, OverlappingState},
+    dfa::{
+        dense, regex::Regex, sparse, Automaton, FullMatches, OverlappingState,
+        StreamSearcher,
+    },
     nfa::thompson,
-    HalfMatch, MatchError, MatchKind, MultiMatch,
+    util::{
+        alphabet::{ByteSet, Unit},
+        prefilter,
+    },
+    HalfMatch, MatchError, MatchKind, MultiMatch, PatternID, SyntaxConfig,
 };
 
 use crate::util::{BunkPrefilter, SubstringPrefilter};
@@ -131,3 +138,798 @@ fn prefilter_is_active() -> Result<(), Box<dyn Error>> {
     assert_eq!(re.find_leftmost(b"a123"), None);
     Ok(())
 }
+
+// Tests that a search stops as soon as it enters a dead state instead of
+// scanning the rest of the haystack. We detect a full scan by putting a quit
+// byte at the very end of the haystack: if the search reaches it, then it
+// reports an error instead of no match.
+#[test]
+fn dead_state_stops_search() -> Result<(), Box<dyn Error>> {
+    let dense = dense::Builder::new()
+        .configure(dense::Config::new().anchored(true).quit(b'!', true))
+        .build("xyz")?;
+    let sparse = dense.to_sparse()?;
+
+    let mut haystack = vec![b'a'; 1 << 20];
+    haystack.push(b'!');
+
+    assert_eq!(dense.find_earliest_fwd(&haystack), Ok(None));
+    assert_eq!(dense.find_leftmost_fwd(&haystack), Ok(None));
+    assert_eq!(sparse.find_earliest_fwd(&haystack), Ok(None));
+    assert_eq!(sparse.find_leftmost_fwd(&haystack), Ok(None));
+
+    Ok(())
+}
+
+// Tests that searching for a pattern ID that doesn't exist panics with a
+// message that names the invalid pattern.
+#[test]
+#[should_panic(expected = "invalid pattern ID 5; regex has 1 pattern")]
+fn invalid_pattern_id_panics() {
+    let dfa = dense::Builder::new()
+        .configure(dense::Config::new().starts_for_each_pattern(true))
+        .build("a")
+        .unwrap();
+    let pid = PatternID::must(5);
+    let _ = dfa.find_leftmost_fwd_at(None, Some(pid), b"a", 0, 1);
+}
+
+// Tests that quit bytes added at search time to a sparse DFA cause the search
+// to stop at the quit byte, and that searches are otherwise unaffected.
+#[test]
+fn sparse_with_quit_bytes() -> Result<(), Box<dyn Error>> {
+    let dfa = sparse::DFA::new("[a-z]+[0-9]")?;
+    let mut quit = ByteSet::empty();
+    quit.add(b'x');
+    let qdfa = dfa.with_quit_bytes(quit);
+
+    assert_eq!(
+        qdfa.find_leftmost_fwd(b"123xyz9"),
+        Err(MatchError::Quit { byte: b'x', offset: 3 })
+    );
+    assert_eq!(
+        qdfa.find_earliest_fwd(b"123xyz9"),
+        Err(MatchError::Quit { byte: b'x', offset: 3 })
+    );
+
+    for haystack in &[&b"abc1"[..], b"  abc1 def2", b"", b"123", b"zzz"] {
+        assert_eq!(
+            dfa.find_leftmost_fwd(haystack),
+            qdfa.find_leftmost_fwd(haystack)
+        );
+        assert_eq!(
+            dfa.find_earliest_fwd(haystack),
+            qdfa.find_earliest_fwd(haystack)
+        );
+    }
+    Ok(())
+}
+
+// Tests that two DFAs built with the same forced byte class map decode input
+// bytes identically, and that a map which can't distinguish the bytes needed
+// by a DFA is rejected.
+#[test]
+fn byte_classes_from() -> Result<(), Box<dyn Error>> {
+    let dfa1 = dense::DFA::new("[a-z]+[0-9]")?;
+    let classes = *dfa1.byte_classes();
+    let dfa2 = dense::Builder::new()
+        .configure(dense::Config::new().byte_classes_from(classes))
+        .build("[0-9]+")?;
+    for b in 0..=255 {
+        assert_eq!(dfa1.byte_classes().get(b), dfa2.byte_classes().get(b));
+    }
+    assert_eq!(
+        dfa2.find_leftmost_fwd(b"ab123"),
+        Ok(Some(HalfMatch::must(0, 5)))
+    );
+
+    let result = dense::Builder::new()
+        .configure(dense::Config::new().byte_classes_from(classes))
+        .build("[A-Z]");
+    assert!(result.is_err());
+    Ok(())
+}
+
+// Tests that a streaming search finds the same match as a single-shot search,
+// regardless of where the haystack is split into chunks.
+#[test]
+fn stream_searcher_chunks() -> Result<(), Box<dyn Error>> {
+    let dfa = dense::DFA::new("foo[0-9]+")?;
+    let haystack = b"foo12345";
+    let expected = dfa.find_leftmost_fwd(haystack)?;
+    assert_eq!(expected, Some(HalfMatch::must(0, 8)));
+
+    for i in 0..=haystack.len() {
+        for j in i..=haystack.len() {
+            let chunks = [&haystack[..i], &haystack[i..j], &haystack[j..]];
+            let mut searcher = StreamSearcher::new(&dfa);
+            let mut got = None;
+            for chunk in chunks.iter() {
+                if let Some(m) = searcher.feed(&dfa, chunk)? {
+                    got = Some(m);
+                }
+            }
+            if let Some(m) = searcher.finish(&dfa)? {
+                got = Some(m);
+            }
+            assert_eq!(expected, got, "split at {} and {}", i, j);
+        }
+    }
+    Ok(())
+}
+
+// Tests that pairing a forward and reverse DFA finds the same matches as a
+// regex, including empty matches and when mixing dense and sparse DFAs.
+#[test]
+fn full_matches() -> Result<(), Box<dyn Error>> {
+    let haystacks: &[&[u8]] =
+        &[b"xfoo123 foo9 foo", b"", b"abc", "\u{2603}a\u{2603}".as_bytes()];
+    for pattern in &[r"foo\d+", r"a*", r"", r"(?-u:\b)"] {
+        let re = Regex::new(pattern)?;
+        let rev = re.reverse().to_sparse()?;
+        for &haystack in haystacks {
+            let expected: Vec<MultiMatch> =
+                re.find_leftmost_iter(haystack).collect();
+            let got = FullMatches::new(re.forward(), &rev, haystack)
+                .collect::<Result<Vec<MultiMatch>, _>>()?;
+            assert_eq!(expected, got, "pattern: {:?}", pattern);
+        }
+    }
+
+    // Without UTF-8 mode, an empty match may occur at every byte offset.
+    let re = Regex::new("")?;
+    let got =
+        FullMatches::new(re.forward(), re.reverse(), "\u{2603}".as_bytes())
+            .utf8(false)
+            .collect::<Result<Vec<MultiMatch>, _>>()?;
+    assert_eq!(
+        got,
+        vec![
+            MultiMatch::must(0, 0, 0),
+            MultiMatch::must(0, 1, 1),
+            MultiMatch::must(0, 2, 2),
+            MultiMatch::must(0, 3, 3),
+        ]
+    );
+
+    // Errors from the forward search are reported.
+    let fwd = dense::Builder::new()
+        .configure(dense::Config::new().quit(b'!', true))
+        .build("[a-z]+")?;
+    let mut it = FullMatches::new(&fwd, re.reverse(), b"!abc");
+    assert_eq!(
+        Some(Err(MatchError::Quit { byte: b'!', offset: 0 })),
+        it.next()
+    );
+    Ok(())
+}
+
+// Tests that iterating over half matches with an automaton reports the same
+// match ends as a regex, including for empty matches.
+#[test]
+fn find_leftmost_fwd_iter() -> Result<(), Box<dyn Error>> {
+    let haystacks: &[&[u8]] =
+        &[b"a1b22c", b"", b"abc", "\u{2603}a\u{2603}".as_bytes()];
+    for pattern in &[r"[0-9]+", r"a*", r"", r"(?-u:\b)"] {
+        let re = Regex::new(pattern)?;
+        let sparse = re.forward().to_sparse()?;
+        for &haystack in haystacks {
+            let expected: Vec<usize> =
+                re.find_leftmost_iter(haystack).map(|m| m.end()).collect();
+            let got = re
+                .forward()
+                .find_leftmost_fwd_iter(haystack)
+                .map(|r| r.map(|m| m.offset()))
+                .collect::<Result<Vec<usize>, _>>()?;
+            assert_eq!(expected, got, "pattern: {:?}", pattern);
+            let got = sparse
+                .find_leftmost_fwd_iter(haystack)
+                .map(|r| r.map(|m| m.offset()))
+                .collect::<Result<Vec<usize>, _>>()?;
+            assert_eq!(expected, got, "pattern: {:?}", pattern);
+        }
+    }
+
+    // Without UTF-8 mode, an empty match may occur at every byte offset.
+    let dfa = dense::DFA::new("")?;
+    let got = dfa
+        .find_leftmost_fwd_iter("\u{2603}".as_bytes())
+        .utf8(false)
+        .collect::<Result<Vec<HalfMatch>, _>>()?;
+    let expected: Vec<HalfMatch> =
+        (0..=3).map(|i| HalfMatch::must(0, i)).collect();
+    assert_eq!(expected, got);
+
+    // Errors are reported.
+    let dfa = dense::Builder::new()
+        .configure(dense::Config::new().quit(b'!', true))
+        .build("[a-z]+")?;
+    let mut it = dfa.find_leftmost_fwd_iter(b"!abc");
+    assert_eq!(
+        Some(Err(MatchError::Quit { byte: b'!', offset: 0 })),
+        it.next()
+    );
+    Ok(())
+}
+
+// Tests that repeated overlapping reverse searches with a sparse DFA
+// enumerate every distinct match start, including multiple patterns matching
+// at the same position.
+#[test]
+fn sparse_overlapping_rev() -> Result<(), Box<dyn Error>> {
+    let dfa = dense::Builder::new()
+        .configure(dense::Config::new().match_kind(MatchKind::All))
+        .thompson(thompson::Config::new().reverse(true))
+        .build_many(&[r"\w+", r"\S+"])?
+        .to_sparse()?;
+    let haystack = b"@foo";
+    let mut state = OverlappingState::start();
+    let mut end = haystack.len();
+    let mut got = vec![];
+    while let Some(m) =
+        dfa.find_overlapping_rev_at(None, haystack, 0, end, &mut state)?
+    {
+        got.push(m);
+        end = m.offset();
+    }
+    assert_eq!(
+        got,
+        vec![
+            HalfMatch::must(0, 3),
+            HalfMatch::must(1, 3),
+            HalfMatch::must(0, 2),
+            HalfMatch::must(1, 2),
+            HalfMatch::must(0, 1),
+            HalfMatch::must(1, 1),
+            HalfMatch::must(1, 0),
+        ]
+    );
+    Ok(())
+}
+
+// Tests that a compacted DFA is whichever of the dense DFA it came from and
+// the corresponding sparse DFA uses less memory, preferring the dense DFA on
+// a tie, and that it reports the same matches.
+#[test]
+fn compact() -> Result<(), Box<dyn Error>> {
+    let haystack = "xyz αβγδεζηθικ foo123".as_bytes();
+    let mut dfas =
+        vec![dense::DFA::always_match()?, dense::DFA::never_match()?];
+    for pattern in &[r"\w{10}", "foo[0-9]+", "[a-z]+", "a"] {
+        dfas.push(dense::DFA::new(pattern)?);
+    }
+    dfas.push(
+        dense::Builder::new()
+            .configure(dense::Config::new().starts_for_each_pattern(true))
+            .build_many(&["foo", "[0-9]+"])?,
+    );
+    for dense in &dfas {
+        let sparse_size = dense.to_sparse()?.memory_usage();
+        let dfa = dense.compact()?;
+        assert_eq!(sparse_size < dense.memory_usage(), dfa.is_sparse());
+        assert_eq!(sparse_size.min(dense.memory_usage()), dfa.memory_usage());
+        assert_eq!(
+            dense.find_leftmost_fwd(haystack)?,
+            dfa.find_leftmost_fwd(haystack)?,
+        );
+    }
+    assert!(dense::DFA::new(r"\w{10}")?.compact()?.is_sparse());
+    Ok(())
+}
+
+// Tests that an end-anchored search only reports matches that end exactly
+// at the end of the search, for both dense and sparse DFAs.
+#[test]
+fn find_fwd_anchored_end() -> Result<(), Box<dyn Error>> {
+    let dense = dense::DFA::new("foo")?;
+    let sparse = dense.to_sparse()?;
+
+    assert_eq!(None, dense.find_fwd_anchored_end(b"foobar")?);
+    assert_eq!(None, sparse.find_fwd_anchored_end(b"foobar")?);
+    assert_eq!(
+        Some(HalfMatch::must(0, 3)),
+        dense.find_fwd_anchored_end(b"foo")?
+    );
+    assert_eq!(
+        Some(HalfMatch::must(0, 3)),
+        sparse.find_fwd_anchored_end(b"foo")?
+    );
+    assert_eq!(
+        Some(HalfMatch::must(0, 3)),
+        dense.find_fwd_anchored_end_at(None, b"foobar", 0, 3)?
+    );
+
+    // A match is only reported when the DFA is in a match state after the
+    // EOI transition. Consecutive matches covering the haystack don't count,
+    // since the DFA has already stopped after the first one.
+    let anchored = dense::Builder::new()
+        .configure(dense::Config::new().anchored(true))
+        .build("foo")?;
+    for dfa in &[&dense, &anchored] {
+        assert_eq!(None, dfa.find_fwd_anchored_end(b"foofoo")?);
+        assert_eq!(None, dfa.find_fwd_anchored_end(b"foo foo")?);
+    }
+    assert_eq!(None, sparse.find_fwd_anchored_end(b"foofoo")?);
+    assert_eq!(None, anchored.find_fwd_anchored_end(b"barfoo")?);
+    assert_eq!(
+        Some(HalfMatch::must(0, 6)),
+        dense.find_fwd_anchored_end(b"barfoo")?
+    );
+    Ok(())
+}
+
+// Tests that dropping accelerators when converting to a sparse DFA makes it
+// smaller without changing any search results.
+#[test]
+fn sparse_without_accelerators() -> Result<(), Box<dyn Error>> {
+    let dense = dense::Builder::new()
+        .syntax(SyntaxConfig::new().unicode(false).utf8(false))
+        .build("[^abc]+a")?;
+    let with = dense.to_sparse()?;
+    let without = dense
+        .to_sparse_with_config(sparse::Config::new().accelerate(false))?;
+    assert!(without.write_to_len() < with.write_to_len());
+
+    let haystacks: &[&[u8]] = &[b"xyzxyzxyza", b"zzza", b"abc", b"", b"xyz"];
+    for &haystack in haystacks {
+        assert_eq!(
+            with.find_leftmost_fwd(haystack)?,
+            without.find_leftmost_fwd(haystack)?,
+        );
+        assert_eq!(
+            with.find_earliest_fwd(haystack)?,
+            without.find_earliest_fwd(haystack)?,
+        );
+    }
+    Ok(())
+}
+
+// Tests that rebuilding a dense DFA from a sparse DFA yields a DFA with the
+// same search results as the original, including for accelerated states,
+// quit bytes and anchored searches for specific patterns.
+#[test]
+fn dense_from_sparse_roundtrip() -> Result<(), Box<dyn Error>> {
+    let dense = dense::Builder::new()
+        .configure(
+            dense::Config::new()
+                .starts_for_each_pattern(true)
+                .quit(b'!', true),
+        )
+        .syntax(SyntaxConfig::new().unicode(false).utf8(false))
+        .build_many(&["[^abc]+a", "[0-9]+", r"\bfoo\b"])?;
+    let sparse = dense.to_sparse()?;
+    let bytes = sparse.to_bytes_native_endian();
+    let sparse: sparse::DFA<&[u8]> = sparse::DFA::from_bytes(&bytes)?.0;
+    let rebuilt = dense::DFA::from_sparse(&sparse)?;
+    assert_eq!(dense.memory_usage(), rebuilt.memory_usage());
+
+    let haystacks: &[&[u8]] = &[
+        b"xyzxyzxyza",
+        b"zzza",
+        b"abc",
+        b"",
+        b"foo 123",
+        b"a foo!",
+        b"foobar 9a",
+    ];
+    for &haystack in haystacks {
+        assert_eq!(
+            dense.find_leftmost_fwd(haystack),
+            rebuilt.find_leftmost_fwd(haystack),
+        );
+        assert_eq!(
+            dense.find_earliest_fwd(haystack),
+            rebuilt.find_earliest_fwd(haystack),
+        );
+        for pid in 0..3 {
+            let pid = Some(PatternID::must(pid));
+            let end = haystack.len();
+            assert_eq!(
+                dense.find_leftmost_fwd_at(None, pid, haystack, 0, end),
+                rebuilt.find_leftmost_fwd_at(None, pid, haystack, 0, end),
+            );
+        }
+    }
+
+    // Rebuilding from a sparse DFA without accelerators works too.
+    let sparse = dense
+        .to_sparse_with_config(sparse::Config::new().accelerate(false))?;
+    let rebuilt = dense::DFA::from_sparse(&sparse)?;
+    for &haystack in haystacks {
+        assert_eq!(
+            dense.find_leftmost_fwd(haystack),
+            rebuilt.find_leftmost_fwd(haystack),
+        );
+    }
+    Ok(())
+}
+
+// Tests that the quit set configured on a dense DFA survives conversion to a
+// sparse DFA, serialization of both and conversion back to a dense DFA.
+#[test]
+fn quit_bytes_roundtrip() -> Result<(), Box<dyn Error>> {
+    let mut expected = ByteSet::empty();
+    expected.add(b'\n');
+    expected.add(b'!');
+    // Unicode word boundaries add every non-ASCII byte to the quit set.
+    expected.add_all(0x80, 0xFF);
+
+    let dense = dense::Builder::new()
+        .configure(
+            dense::Config::new()
+                .quit(b'\n', true)
+                .quit(b'!', true)
+                .unicode_word_boundary(true),
+        )
+        .build(r"\bfoo\b")?;
+    assert_eq!(expected, dense.quit_bytes());
+
+    let (bytes, pad) = dense.to_bytes_native_endian();
+    let got: dense::DFA<&[u32]> = dense::DFA::from_bytes(&bytes[pad..])?.0;
+    assert_eq!(expected, got.quit_bytes());
+
+    let sparse = dense.to_sparse()?;
+    assert_eq!(expected, sparse.quit_bytes());
+    let bytes = sparse.to_bytes_native_endian();
+    let sparse: sparse::DFA<&[u8]> = sparse::DFA::from_bytes(&bytes)?.0;
+    assert_eq!(expected, sparse.quit_bytes());
+    assert_eq!(
+        Err(MatchError::Quit { byte: b'!', offset: 3 }),
+        sparse.find_leftmost_fwd(b"foo!"),
+    );
+    assert_eq!(expected, dense::DFA::from_sparse(&sparse)?.quit_bytes());
+
+    // A DFA without any quit bytes has an empty quit set.
+    let sparse = sparse::DFA::new("foo")?;
+    let bytes = sparse.to_bytes_native_endian();
+    let sparse: sparse::DFA<&[u8]> = sparse::DFA::from_bytes(&bytes)?.0;
+    assert!(sparse.quit_bytes().is_empty());
+    Ok(())
+}
+
+/// Returns the offset at which the states of the given serialized sparse DFA
+/// begin, along with their total length in bytes.
+///
+/// Every sparse DFA begins with a dead state and a quit state, both of which
+/// have a single EOI transition to the dead state and no accelerator. So we
+/// look for them to find where the states begin. The length of the states
+/// is a u32 that immediately precedes them.
+fn find_sparse_states(bytes: &[u8]) -> (usize, usize) {
+    let mut empty = vec![];
+    empty.extend_from_slice(&1u16.to_ne_bytes());
+    empty.extend_from_slice(&[0, 0]);
+    empty.extend_from_slice(&0u32.to_ne_bytes());
+    empty.push(0);
+    let dead_and_quit = [&empty[..], &empty[..]].concat();
+    let start = bytes
+        .windows(dead_and_quit.len())
+        .position(|w| w == &*dead_and_quit)
+        .unwrap();
+    let mut len = [0; 4];
+    len.copy_from_slice(&bytes[start - 4..start]);
+    (start, u32::from_ne_bytes(len) as usize)
+}
+
+// Tests that deserializing a sparse DFA rejects a transition that doesn't
+// point to the beginning of a state.
+#[test]
+fn sparse_validate_corrupt_transition() -> Result<(), Box<dyn Error>> {
+    let bytes = sparse::DFA::new("foo[0-9]+")?.to_bytes_native_endian();
+    assert!(sparse::DFA::from_bytes(&bytes).is_ok());
+
+    let (sparse_start, sparse_len) = find_sparse_states(&bytes);
+
+    // The state following the quit state always has at least one transition
+    // besides EOI. Corrupt its first transition to point at an offset in the
+    // middle of the dead state, then at the offset immediately following the
+    // last state and finally at an offset well past the end of all states.
+    // The dead and quit states are each 9 bytes.
+    let state = sparse_start + 18;
+    let ntrans = u16::from_ne_bytes([bytes[state], bytes[state + 1]]);
+    let ntrans = usize::from(ntrans & !(1 << 15));
+    let next = state + 2 + ntrans * 2;
+    for &bad in &[1u32, sparse_len as u32, 0xFFFF] {
+        let mut corrupt = bytes.clone();
+        corrupt[next..next + 4].copy_from_slice(&bad.to_ne_bytes());
+        assert!(sparse::DFA::from_bytes(&corrupt).is_err());
+    }
+    Ok(())
+}
+
+// Tests that deserializing a truncated sparse DFA always returns an error,
+// regardless of which field the truncation happens in, and never panics.
+#[test]
+fn sparse_truncated_errors() -> Result<(), Box<dyn Error>> {
+    let dfa = dense::Builder::new()
+        .configure(dense::Config::new().starts_for_each_pattern(true))
+        .build_many(&["foo[0-9]+", "[a-z]+bar"])?
+        .to_sparse()?;
+    let bytes = dfa.to_bytes_native_endian();
+    assert!(sparse::DFA::from_bytes(&bytes).is_ok());
+    for len in 0..bytes.len() {
+        assert!(
+            sparse::DFA::from_bytes(&bytes[..len]).is_err(),
+            "expected error when truncated to {} bytes",
+            len,
+        );
+    }
+
+    // Truncating the whole buffer is caught by the length prefix on the
+    // states. So also truncate the states themselves, at every offset, while
+    // keeping the length prefix consistent. This cuts every field of every
+    // state short at some point.
+    let (sparse_start, sparse_len) = find_sparse_states(&bytes);
+    let len_start = sparse_start - 4;
+    let rest = &bytes[sparse_start + sparse_len..];
+    for len in 0..sparse_len {
+        let mut corrupt = bytes[..len_start].to_vec();
+        corrupt.extend_from_slice(&(len as u32).to_ne_bytes());
+        corrupt.extend_from_slice(&bytes[sparse_start..sparse_start + len]);
+        corrupt.extend_from_slice(rest);
+        assert!(
+            sparse::DFA::from_bytes(&corrupt).is_err(),
+            "expected error when states are truncated to {} bytes",
+            len,
+        );
+    }
+    Ok(())
+}
+
+// Tests that a byte set prefilter finds the same matches as no prefilter,
+// for sets small enough to use memchr and for larger sets.
+#[test]
+fn prefilter_byte_set() -> Result<(), Box<dyn Error>> {
+    let text = b"xyz a1 bcd u23 e o9 iu0 AEIOU5 a";
+    for &(pattern, bytes) in &[
+        (r"[aeiou][0-9]+", &b"aeiou"[..]),
+        (r"[ae][0-9]+", &b"ae"[..]),
+        (r"[aeu][0-9]+", &b"uea"[..]),
+        (r"a[0-9]+", &b"aa"[..]),
+    ] {
+        let re = Regex::new(pattern)?;
+        let expected: Vec<MultiMatch> = re.find_leftmost_iter(text).collect();
+        let re = re.with_prefilter(prefilter::ByteSet::new(bytes));
+        let got: Vec<MultiMatch> = re.find_leftmost_iter(text).collect();
+        assert!(!expected.is_empty());
+        assert_eq!(expected, got);
+    }
+    Ok(())
+}
+
+// Tests that a byte set prefilter used in a reverse search finds the same
+// match starts as no prefilter, where the set contains the bytes that every
+// match ends with.
+#[test]
+fn prefilter_byte_set_rev() -> Result<(), Box<dyn Error>> {
+    let text: &[u8] = b"xyz 1a bcd 23u e 9o 0iu 5AEIOU a";
+    for &(pattern, bytes) in &[
+        (r"[0-9]+[aeiou]", &b"aeiou"[..]),
+        (r"[0-9]+[ae]", &b"ae"[..]),
+        (r"[0-9]+[aeu]", &b"uea"[..]),
+        (r"[0-9]+a", &b"aa"[..]),
+    ] {
+        let dfa = dense::Builder::new()
+            .thompson(thompson::Config::new().reverse(true))
+            .build(pattern)?;
+        let pre = prefilter::ByteSet::new(bytes);
+        for end in 0..=text.len() {
+            let expected = dfa.find_leftmost_rev_at(None, text, 0, end)?;
+            let mut scanner = prefilter::Scanner::new(&pre);
+            let got = dfa.find_leftmost_rev_at_with_prefilter(
+                Some(&mut scanner),
+                None,
+                text,
+                0,
+                end,
+            )?;
+            assert_eq!(expected, got, "pattern: {:?}, end: {}", pattern, end);
+        }
+    }
+    Ok(())
+}
+
+// Tests that a reverse search with a reverse prefilter finds the same match
+// starts as a reverse search without one.
+#[test]
+fn prefilter_rev() -> Result<(), Box<dyn Error>> {
+    let dfa = dense::Builder::new()
+        .thompson(thompson::Config::new().reverse(true))
+        .build(r"\w+rare")?;
+    let pre = SubstringPrefilter::new("rare");
+    let haystacks: &[&[u8]] = &[
+        b"foo barrare quux",
+        b"a rare bird",
+        b"rare",
+        b"xrare rare zzz",
+        b"nothing here",
+        b"",
+    ];
+    for &haystack in haystacks {
+        for end in 0..=haystack.len() {
+            let expected = dfa.find_leftmost_rev_at(None, haystack, 0, end)?;
+            let mut scanner = prefilter::Scanner::new(&pre);
+            let got = dfa.find_leftmost_rev_at_with_prefilter(
+                Some(&mut scanner),
+                None,
+                haystack,
+                0,
+                end,
+            )?;
+            assert_eq!(
+                expected, got,
+                "haystack: {:?}, end: {}",
+                haystack, end
+            );
+
+            let expected = dfa.find_earliest_rev_at(None, haystack, 0, end)?;
+            let mut scanner = prefilter::Scanner::new(&pre);
+            let got = dfa.find_earliest_rev_at_with_prefilter(
+                Some(&mut scanner),
+                None,
+                haystack,
+                0,
+                end,
+            )?;
+            assert_eq!(
+                expected, got,
+                "haystack: {:?}, end: {}",
+                haystack, end
+            );
+        }
+    }
+    assert_eq!(
+        Some(HalfMatch::must(0, 4)),
+        dfa.find_leftmost_rev(b"foo barrare quux")?,
+    );
+
+    // Confirm that the prefilter is actually used by giving it one that
+    // reports false negatives.
+    let bunk = BunkPrefilter::new();
+    let mut scanner = prefilter::Scanner::new(&bunk);
+    let haystack = b"foo barrare quux";
+    let got = dfa.find_leftmost_rev_at_with_prefilter(
+        Some(&mut scanner),
+        None,
+        haystack,
+        0,
+        haystack.len(),
+    )?;
+    assert_eq!(None, got);
+
+    // An anchored DFA can only match at the end of the search, so the
+    // prefilter must not be used to skip to an earlier candidate.
+    let anchored = dense::Builder::new()
+        .configure(dense::Config::new().anchored(true))
+        .thompson(thompson::Config::new().reverse(true))
+        .build(r"\w+rare")?;
+    let sparse = anchored.to_sparse()?;
+    let (bytes, pad) = anchored.to_bytes_native_endian();
+    let deserialized = dense::DFA::from_bytes(&bytes[pad..])?.0;
+    assert!(anchored.is_anchored());
+    assert!(sparse.is_anchored());
+    assert!(deserialized.is_anchored());
+    assert!(!dfa.is_anchored());
+    let haystack = b"foo barrare quux";
+    let mut scanner = prefilter::Scanner::new(&pre);
+    let got = anchored.find_leftmost_rev_at_with_prefilter(
+        Some(&mut scanner),
+        None,
+        haystack,
+        0,
+        haystack.len(),
+    )?;
+    assert_eq!(None, got);
+    let mut scanner = prefilter::Scanner::new(&pre);
+    let got = sparse.find_earliest_rev_at_with_prefilter(
+        Some(&mut scanner),
+        None,
+        haystack,
+        0,
+        haystack.len(),
+    )?;
+    assert_eq!(None, got);
+    let mut scanner = prefilter::Scanner::new(&pre);
+    let got = deserialized.find_leftmost_rev_at_with_prefilter(
+        Some(&mut scanner),
+        None,
+        haystack,
+        0,
+        haystack.len(),
+    )?;
+    assert_eq!(None, got);
+    let mut scanner = prefilter::Scanner::new(&pre);
+    let got = anchored.find_leftmost_rev_at_with_prefilter(
+        Some(&mut scanner),
+        None,
+        haystack,
+        0,
+        11,
+    )?;
+    assert_eq!(Some(HalfMatch::must(0, 4)), got);
+    Ok(())
+}
+
+// Tests that the representatives of a DFA's byte classes cover every class
+// exactly once, including EOI, and that the elements of all classes cover
+// every byte plus EOI.
+#[test]
+fn byte_classes_representatives() -> Result<(), Box<dyn Error>> {
+    // Disabling UTF-8 mode keeps the unanchored prefix from splitting the
+    // non-ASCII bytes into their own classes.
+    let dfa = dense::Builder::new()
+        .thompson(thompson::Config::new().utf8(false))
+        .build("[a-z]")?;
+    let classes = dfa.byte_classes();
+    // [\x00-`], [a-z], [{-\xFF] and EOI.
+    assert_eq!(classes.alphabet_len(), 4);
+
+    let reps: Vec<Unit> = classes.representatives().collect();
+    assert_eq!(
+        reps,
+        vec![Unit::u8(0), Unit::u8(b'a'), Unit::u8(b'{'), classes.eoi()],
+    );
+    for (class, rep) in classes.iter().zip(reps) {
+        assert_eq!(class.as_usize(), classes.get_by_unit(rep));
+    }
+
+    let elements: Vec<Unit> =
+        classes.iter().flat_map(|class| classes.elements(class)).collect();
+    assert_eq!(elements.len(), 257);
+    assert_eq!(elements.iter().filter(|unit| unit.is_eoi()).count(), 1);
+    assert!(classes.elements(classes.eoi()).all(|unit| unit.is_eoi()));
+    Ok(())
+}
+
+// Tests that build errors report which limit, if any, caused them.
+#[test]
+fn build_error_introspection() -> Result<(), Box<dyn Error>> {
+    let err = dense::DFA::new("(").unwrap_err();
+    assert!(err.is_syntax_error());
+    assert!(!err.is_size_limit());
+    assert_eq!(None, err.size_limit());
+
+    let err = dense::Builder::new()
+        .thompson(thompson::Config::new().nfa_size_limit(Some(10)))
+        .build(r"\w{10}")
+        .unwrap_err();
+    assert!(!err.is_syntax_error());
+    assert!(err.is_size_limit());
+    assert_eq!(Some(10), err.size_limit());
+
+    let err = dense::Builder::new()
+        .configure(dense::Config::new().dfa_size_limit(Some(500)))
+        .build(r"\w{10}")
+        .unwrap_err();
+    assert!(!err.is_syntax_error());
+    assert_eq!(Some(500), err.size_limit());
+
+    let err = dense::Builder::new()
+        .configure(dense::Config::new().determinize_size_limit(Some(500)))
+        .build(r"\w{10}")
+        .unwrap_err();
+    assert_eq!(Some(500), err.size_limit());
+
+    let err = dense::Builder::new()
+        .configure(dense::Config::new().unicode_word_boundary(false))
+        .build(r"\b")
+        .unwrap_err();
+    assert!(!err.is_syntax_error());
+    assert!(!err.is_size_limit());
+    Ok(())
+}
+
+// Tests that a sparse DFA's reported serialized size is exactly the number of
+// bytes written, for every endianness.
+#[test]
+fn sparse_write_to_len() -> Result<(), Box<dyn Error>> {
+    let patterns = &["", "a", r"foo[0-9]+", r"(?-u:\w)+\s+bar", r"\pL{3}"];
+    for pattern in patterns {
+        let dfa = sparse::DFA::new(pattern)?;
+        let len = dfa.write_to_len();
+        let mut buf = vec![0; len];
+        assert_eq!(len, dfa.write_to_little_endian(&mut buf)?);
+        assert_eq!(len, dfa.write_to_big_endian(&mut buf)?);
+        assert_eq!(len, dfa.write_to_native_endian(&mut buf)?);
+    }
+    Ok(())
+}
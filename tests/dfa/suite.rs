@@ -139,6 +139,28 @@ fn sparse_serialization_unminimized_default() -> Result<()> {
     Ok(())
 }
 
+/// Runs the test suite on dense DFAs that were rebuilt from sparse DFAs. This
+/// checks that converting a dense DFA to a sparse DFA and back again doesn't
+/// change its matches.
+#[test]
+fn sparse_to_dense_unminimized_default() -> Result<()> {
+    let builder = Regex::builder();
+    let my_compiler = |builder| {
+        compiler(builder, |builder, re| {
+            let fwd = dense::DFA::from_sparse(&re.forward().to_sparse()?)?;
+            let rev = dense::DFA::from_sparse(&re.reverse().to_sparse()?)?;
+            let re = builder.build_from_dfas(fwd, rev);
+            Ok(CompiledRegex::compiled(move |test| -> Vec<TestResult> {
+                run_test(&re, test)
+            }))
+        })
+    };
+    TestRunner::new()?
+        .test_iter(suite()?.iter(), my_compiler(builder))
+        .assert();
+    Ok(())
+}
+
 fn dense_compiler(
     builder: dfa::regex::Builder,
 ) -> impl FnMut(&RegexTest, &[BString]) -> Result<CompiledRegex> {
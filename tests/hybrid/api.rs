@@ -7,7 +7,8 @@ use regex_automata::{
         OverlappingState,
     },
     nfa::thompson,
-    HalfMatch, MatchError, MatchKind, MultiMatch,
+    util::prefilter,
+    HalfMatch, MatchError, MatchKind, MultiMatch, PatternID,
 };
 
 use crate::util::{BunkPrefilter, SubstringPrefilter};
@@ -193,3 +194,708 @@ fn prefilter_is_active() -> Result<(), Box<dyn Error>> {
     assert_eq!(re.find_leftmost(&mut cache, b"a123"), None);
     Ok(())
 }
+
+// Tests that a reverse search with a reverse prefilter finds the same match
+// starts as a reverse search without one.
+#[test]
+fn prefilter_rev() -> Result<(), Box<dyn Error>> {
+    let dfa = DFA::builder()
+        .thompson(thompson::Config::new().reverse(true))
+        .build(r"\w+rare")?;
+    let mut cache = dfa.create_cache();
+    let pre = SubstringPrefilter::new("rare");
+    let haystacks: &[&[u8]] = &[
+        b"foo barrare quux",
+        b"a rare bird",
+        b"rare",
+        b"xrare rare zzz",
+        b"nothing here",
+        b"",
+    ];
+    for &haystack in haystacks {
+        for end in 0..=haystack.len() {
+            let expected =
+                dfa.find_leftmost_rev_at(&mut cache, None, haystack, 0, end)?;
+            let mut scanner = prefilter::Scanner::new(&pre);
+            let got = dfa.find_leftmost_rev_at_with_prefilter(
+                &mut cache,
+                Some(&mut scanner),
+                None,
+                haystack,
+                0,
+                end,
+            )?;
+            assert_eq!(
+                expected, got,
+                "haystack: {:?}, end: {}",
+                haystack, end
+            );
+
+            let expected =
+                dfa.find_earliest_rev_at(&mut cache, None, haystack, 0, end)?;
+            let mut scanner = prefilter::Scanner::new(&pre);
+            let got = dfa.find_earliest_rev_at_with_prefilter(
+                &mut cache,
+                Some(&mut scanner),
+                None,
+                haystack,
+                0,
+                end,
+            )?;
+            assert_eq!(
+                expected, got,
+                "haystack: {:?}, end: {}",
+                haystack, end
+            );
+        }
+    }
+
+    // Confirm that the prefilter is actually used by giving it one that
+    // reports false negatives.
+    let bunk = BunkPrefilter::new();
+    let mut scanner = prefilter::Scanner::new(&bunk);
+    let haystack = b"foo barrare quux";
+    let got = dfa.find_leftmost_rev_at_with_prefilter(
+        &mut cache,
+        Some(&mut scanner),
+        None,
+        haystack,
+        0,
+        haystack.len(),
+    )?;
+    assert_eq!(None, got);
+
+    // An anchored DFA can only match at the end of the search, so the
+    // prefilter must not be used to skip to an earlier candidate.
+    let anchored = DFA::builder()
+        .configure(DFA::config().anchored(true))
+        .thompson(thompson::Config::new().reverse(true))
+        .build(r"\w+rare")?;
+    let mut cache = anchored.create_cache();
+    for &earliest in &[false, true] {
+        let mut scanner = prefilter::Scanner::new(&pre);
+        let got = if earliest {
+            anchored.find_earliest_rev_at_with_prefilter(
+                &mut cache,
+                Some(&mut scanner),
+                None,
+                haystack,
+                0,
+                haystack.len(),
+            )?
+        } else {
+            anchored.find_leftmost_rev_at_with_prefilter(
+                &mut cache,
+                Some(&mut scanner),
+                None,
+                haystack,
+                0,
+                haystack.len(),
+            )?
+        };
+        assert_eq!(None, got);
+    }
+    let mut scanner = prefilter::Scanner::new(&pre);
+    let got = anchored.find_leftmost_rev_at_with_prefilter(
+        &mut cache,
+        Some(&mut scanner),
+        None,
+        haystack,
+        0,
+        11,
+    )?;
+    assert_eq!(Some(HalfMatch::must(0, 4)), got);
+    Ok(())
+}
+
+// Tests that searching for a pattern ID that doesn't exist panics with a
+// message that names the invalid pattern, instead of an opaque out of bounds
+// panic.
+#[test]
+#[should_panic(expected = "invalid pattern ID 5; regex has 1 pattern")]
+fn invalid_pattern_id_panics() {
+    let dfa = DFA::builder()
+        .configure(DFA::config().starts_for_each_pattern(true))
+        .build("a")
+        .unwrap();
+    let mut cache = dfa.create_cache();
+    let pid = PatternID::must(5);
+    let _ = dfa.find_leftmost_fwd_at(&mut cache, None, Some(pid), b"a", 0, 1);
+}
+
+// Tests that explicitly clearing a cache keeps it usable with the same DFA,
+// and that doing so doesn't count toward the minimum cache clear count.
+#[test]
+fn cache_clear() -> Result<(), Box<dyn Error>> {
+    let dfa = DFA::builder()
+        .configure(DFA::config().minimum_cache_clear_count(Some(0)))
+        .build_many(&[r"[a-z]+[0-9]", r"[0-9]+[a-z]"])?;
+    let mut cache = dfa.create_cache();
+    let haystack = b"foo bar1 123z";
+
+    let expected = Some(HalfMatch::must(0, 8));
+    assert_eq!(expected, dfa.find_leftmost_fwd(&mut cache, haystack)?);
+    let before = cache.memory_usage();
+
+    cache.clear(&dfa);
+    assert_eq!(0, cache.clear_count());
+    assert!(cache.memory_usage() <= before);
+    assert_eq!(expected, dfa.find_leftmost_fwd(&mut cache, haystack)?);
+    assert_eq!(
+        Some(HalfMatch::must(1, 13)),
+        dfa.find_leftmost_fwd_at(
+            &mut cache,
+            None,
+            None,
+            haystack,
+            8,
+            haystack.len()
+        )?,
+    );
+
+    cache.clear(&dfa);
+    cache.clear(&dfa);
+    assert_eq!(0, cache.clear_count());
+    assert_eq!(expected, dfa.find_leftmost_fwd(&mut cache, haystack)?);
+    Ok(())
+}
+
+// Tests that a cache that is too small for its regex gets cleared repeatedly,
+// and that the cache reports itself as ineffective as a result.
+#[test]
+fn cache_clear_count() -> Result<(), Box<dyn Error>> {
+    let dfa = DFA::builder()
+        .configure(
+            DFA::config().skip_cache_capacity_check(true).cache_capacity(0),
+        )
+        .build(r"[a-z]{50}")?;
+    let mut cache = dfa.create_cache();
+    assert_eq!(0, cache.clear_count());
+    assert!(cache.was_effective());
+
+    let haystack = "a".repeat(100).into_bytes();
+    let expected = Some(HalfMatch::must(0, 50));
+    assert_eq!(expected, dfa.find_leftmost_fwd(&mut cache, &haystack)?);
+    let count1 = cache.clear_count();
+    assert!(count1 > 1);
+    assert!(!cache.was_effective());
+
+    assert_eq!(expected, dfa.find_leftmost_fwd(&mut cache, &haystack)?);
+    assert!(cache.clear_count() > count1);
+
+    dfa.reset_cache(&mut cache);
+    assert_eq!(0, cache.clear_count());
+    assert!(cache.was_effective());
+
+    // A cache with the default capacity never needs to be cleared here.
+    let dfa = DFA::new(r"[a-z]{50}")?;
+    let mut cache = dfa.create_cache();
+    assert_eq!(expected, dfa.find_leftmost_fwd(&mut cache, &haystack)?);
+    assert_eq!(0, cache.clear_count());
+    assert!(cache.was_effective());
+    Ok(())
+}
+
+// Tests that a lazy DFA gives up when it searches too few bytes per cached
+// state, instead of thrashing its cache.
+#[test]
+fn too_few_bytes_per_state_cause_quit() -> Result<(), Box<dyn Error>> {
+    // This regex has an exponential number of DFA states in the size of the
+    // bounded repetition, and a pseudo-random haystack of 0s and 1s visits
+    // a new one at nearly every byte.
+    let pattern = r"(?-u)[01]*1[01]{20}";
+    let mut haystack = vec![];
+    let mut x: u32 = 12345;
+    for _ in 0..10_000 {
+        x = x.wrapping_mul(1103515245).wrapping_add(12345);
+        haystack.push(if (x >> 16) & 1 == 0 { b'0' } else { b'1' });
+    }
+
+    let config = DFA::config().cache_capacity(1 << 16);
+    let dfa = DFA::builder().configure(config).build(pattern)?;
+    let mut cache = dfa.create_cache();
+    assert!(dfa.find_leftmost_fwd(&mut cache, &haystack)?.is_some());
+    assert!(cache.clear_count() > 0);
+
+    let dfa = DFA::builder()
+        .configure(config.minimum_bytes_per_state(Some(10)))
+        .build(pattern)?;
+    let mut cache = dfa.create_cache();
+    match dfa.find_leftmost_fwd(&mut cache, &haystack) {
+        Err(MatchError::GaveUp { offset }) => assert!(offset > 0),
+        result => panic!("expected search to give up, but got {:?}", result),
+    }
+
+    // A regex with few states easily meets the minimum, even with a small
+    // cache.
+    let dfa = DFA::builder()
+        .configure(config.minimum_bytes_per_state(Some(10)))
+        .build(r"(?-u)[01]*1[01]{2}")?;
+    let mut cache = dfa.create_cache();
+    assert!(dfa.find_leftmost_fwd(&mut cache, &haystack)?.is_some());
+    Ok(())
+}
+
+// Tests that the universal start state agrees with the position dependent
+// start states when the regex has no look-around assertions.
+#[test]
+fn universal_start_state() -> Result<(), Box<dyn Error>> {
+    let haystack = "a b\nc☃ d".as_bytes();
+
+    let dfa = DFA::new(r"\w")?;
+    let mut cache = dfa.create_cache();
+    assert!(dfa.has_universal_start_state());
+    let sid = dfa.universal_start_state(&mut cache)?;
+    for start in 0..=haystack.len() {
+        let got = dfa.start_state_forward(
+            &mut cache,
+            None,
+            haystack,
+            start,
+            haystack.len(),
+        )?;
+        assert_eq!(sid, got, "mismatch at {}", start);
+    }
+
+    let dfa = DFA::builder()
+        .thompson(thompson::Config::new().reverse(true))
+        .build(r"\w")?;
+    let mut cache = dfa.create_cache();
+    let sid = dfa.universal_start_state(&mut cache)?;
+    for end in 0..=haystack.len() {
+        let got =
+            dfa.start_state_reverse(&mut cache, None, haystack, 0, end)?;
+        assert_eq!(sid, got, "mismatch at {}", end);
+    }
+
+    assert!(!DFA::new(r"(?-u:\b)\w")?.has_universal_start_state());
+    assert!(!DFA::new(r"^\w")?.has_universal_start_state());
+    Ok(())
+}
+
+#[test]
+#[should_panic]
+fn universal_start_state_panics() {
+    let dfa = DFA::new(r"^\w").unwrap();
+    let mut cache = dfa.create_cache();
+    let _ = dfa.universal_start_state(&mut cache);
+}
+
+// Tests that Unicode word boundaries are resolved correctly on non-ASCII text
+// instead of causing the search to quit.
+#[test]
+fn unicode_word_boundary_resolved() -> Result<(), Box<dyn Error>> {
+    let re = Regex::builder()
+        .dfa(dfa::Config::new().unicode_word_boundary(true))
+        .build(r"\bmot\b")?;
+    let mut cache = re.create_cache();
+
+    let haystack = "café mot".as_bytes();
+    assert!(re.try_find_leftmost(&mut cache, haystack).is_err());
+    assert_eq!(
+        Some(MultiMatch::must(0, 6, 9)),
+        re.try_find_leftmost_unicode(&mut cache, haystack)?,
+    );
+    // An ASCII word boundary would match here, but since 'é' is a word
+    // character, a Unicode word boundary does not.
+    assert_eq!(
+        None,
+        re.try_find_leftmost_unicode(&mut cache, "émot".as_bytes())?
+    );
+    assert_eq!(
+        Some(MultiMatch::must(0, 4, 7)),
+        re.try_find_leftmost_unicode(&mut cache, "é  mot".as_bytes())?,
+    );
+    // Purely ASCII haystacks never need to leave the lazy DFA.
+    assert_eq!(
+        Some(MultiMatch::must(0, 5, 8)),
+        re.try_find_leftmost_unicode(&mut cache, b"cafe mot")?,
+    );
+    // Searching a range still takes the surrounding context into account.
+    assert_eq!(
+        None,
+        re.try_find_leftmost_unicode_at(&mut cache, "émot".as_bytes(), 2, 5)?,
+    );
+
+    let re = Regex::builder()
+        .dfa(dfa::Config::new().unicode_word_boundary(true))
+        .build(r"\b\w+\b")?;
+    let mut cache = re.create_cache();
+    assert_eq!(
+        Some(MultiMatch::must(0, 6, 11)),
+        re.try_find_leftmost_unicode(&mut cache, "  → café".as_bytes())?,
+    );
+
+    // The lazy DFA finds a match ending before the 'é' here, but it must not
+    // be reported, since the match continues through the 'é'.
+    let re = Regex::builder()
+        .dfa(dfa::Config::new().unicode_word_boundary(true))
+        .build(r"\B\w+")?;
+    let mut cache = re.create_cache();
+    assert_eq!(
+        Some(MultiMatch::must(0, 1, 5)),
+        re.try_find_leftmost_unicode(&mut cache, "café".as_bytes())?,
+    );
+    Ok(())
+}
+
+// Tests that only quitting because of the Unicode word boundary heuristic is
+// resolved, and that the lazy DFA resumes the search once the non-ASCII text
+// has been resolved.
+#[test]
+fn unicode_word_boundary_resolved_quit() -> Result<(), Box<dyn Error>> {
+    // Quitting on a non-ASCII byte the caller asked for is still an error.
+    let re = Regex::builder()
+        .dfa(dfa::Config::new().unicode_word_boundary(true).quit(0xC3, true))
+        .build(r"\bmot\b")?;
+    let mut cache = re.create_cache();
+    assert_eq!(
+        Err(MatchError::Quit { byte: 0xC3, offset: 3 }),
+        re.try_find_leftmost_unicode(&mut cache, "café mot".as_bytes()),
+    );
+
+    // After resolving the word boundaries around 'é', the lazy DFA resumes
+    // the search and quits on '!', which only it does. Without '!', the
+    // lazy DFA finds the match.
+    let re = Regex::builder()
+        .dfa(dfa::Config::new().unicode_word_boundary(true).quit(b'!', true))
+        .build(r"\bmot\b")?;
+    let mut cache = re.create_cache();
+    assert_eq!(
+        Err(MatchError::Quit { byte: b'!', offset: 6 }),
+        re.try_find_leftmost_unicode(&mut cache, "é mot!".as_bytes()),
+    );
+    assert_eq!(
+        Some(MultiMatch::must(0, 3, 6)),
+        re.try_find_leftmost_unicode(&mut cache, "é mot".as_bytes())?,
+    );
+    Ok(())
+}
+
+// Tests that the memory usage breakdown of a cache always sums to its total
+// memory usage, both before and after the NFA simulation is used.
+#[test]
+fn cache_memory_usage_breakdown() -> Result<(), Box<dyn Error>> {
+    let re = Regex::builder()
+        .dfa(dfa::Config::new().unicode_word_boundary(true))
+        .build(r"\bmot\b")?;
+    let mut cache = re.create_cache();
+
+    let usage = cache.memory_usage_breakdown();
+    assert_eq!(0, usage.pikevm);
+    assert_eq!(
+        cache.memory_usage(),
+        usage.forward + usage.reverse + usage.pikevm
+    );
+
+    re.try_find_leftmost_unicode(&mut cache, "café mot".as_bytes())?;
+    let usage = cache.memory_usage_breakdown();
+    assert!(usage.forward > 0);
+    assert!(usage.pikevm > 0);
+    assert_eq!(
+        cache.memory_usage(),
+        usage.forward + usage.reverse + usage.pikevm
+    );
+
+    // Resetting the cache discards the NFA simulation's scratch space.
+    cache.reset(&re);
+    assert_eq!(0, cache.memory_usage_breakdown().pikevm);
+    Ok(())
+}
+
+// Tests that build errors report which limit, if any, caused them.
+#[test]
+fn build_error_introspection() -> Result<(), Box<dyn Error>> {
+    let err = DFA::new("(").unwrap_err();
+    assert!(err.is_syntax_error());
+    assert!(!err.is_size_limit());
+    assert_eq!(None, err.size_limit());
+
+    let err = DFA::builder()
+        .thompson(thompson::Config::new().nfa_size_limit(Some(10)))
+        .build(r"\w{10}")
+        .unwrap_err();
+    assert!(!err.is_syntax_error());
+    assert!(err.is_size_limit());
+    assert_eq!(Some(10), err.size_limit());
+
+    let err = DFA::builder()
+        .configure(DFA::config().cache_capacity(0))
+        .build(r"\w")
+        .unwrap_err();
+    assert!(!err.is_syntax_error());
+    assert!(!err.is_size_limit());
+    Ok(())
+}
+
+// Tests that a cache pool can be used concurrently from many threads, and
+// with more than one regex.
+#[test]
+fn cache_pool_concurrent() -> Result<(), Box<dyn Error>> {
+    use std::sync::Arc;
+
+    use regex_automata::hybrid::regex::CachePool;
+
+    let re1 = Arc::new(Regex::new(r"[0-9]+")?);
+    let re2 = Arc::new(Regex::new(r"[a-z]+")?);
+    let pool = Arc::new(CachePool::new());
+
+    let mut handles = vec![];
+    for i in 0..8 {
+        let (re1, re2) = (Arc::clone(&re1), Arc::clone(&re2));
+        let pool = Arc::clone(&pool);
+        handles.push(std::thread::spawn(move || {
+            for j in 0..100 {
+                let (re, expected) = if (i + j) % 2 == 0 {
+                    (&re1, MultiMatch::must(0, 4, 7))
+                } else {
+                    (&re2, MultiMatch::must(0, 0, 3))
+                };
+                let mut cache = pool.get(re);
+                assert_eq!(
+                    Some(expected),
+                    re.find_leftmost(&mut cache, b"abc 123"),
+                );
+            }
+        }));
+    }
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    Ok(())
+}
+
+// Tests that a shortest match search reports the earliest end offset and
+// doesn't extend the match to its leftmost-first end.
+#[test]
+fn shortest_match_earliest_end() -> Result<(), Box<dyn Error>> {
+    let re = Regex::new(r"foo\d+")?;
+    let mut cache = re.create_cache();
+
+    assert_eq!(Some(4), re.try_shortest_match(&mut cache, b"foo12345")?);
+    assert_eq!(Some(7), re.try_shortest_match(&mut cache, b"xyzfoo1")?);
+    assert_eq!(None, re.try_shortest_match(&mut cache, b"foo")?);
+    Ok(())
+}
+
+// Tests that the set of bytes that may begin a match is computed from the
+// NFA, and that it is unavailable when the regex can match the empty string
+// or begins with an assertion.
+#[test]
+fn first_byte_set() -> Result<(), Box<dyn Error>> {
+    let re = Regex::new(r"[abc]\w+")?;
+    let set = re.first_byte_set().unwrap();
+    assert_eq!(vec![b'a', b'b', b'c'], set.iter().collect::<Vec<u8>>());
+
+    let re = Regex::new_many(&["foo", "[0-9]bar"])?;
+    let set = re.first_byte_set().unwrap();
+    assert_eq!(11, set.len());
+    assert!(set.contains(b'f'));
+    assert!(set.contains_range(b'0', b'9'));
+
+    assert!(Regex::new(r"a*")?.first_byte_set().is_none());
+    assert!(Regex::new(r"^abc")?.first_byte_set().is_none());
+    assert!(Regex::new(r"(?-u:\b)abc")?.first_byte_set().is_none());
+    Ok(())
+}
+
+// Tests that searching line by line anchors '^' to the start of each line and
+// reports offsets relative to the whole haystack, including for empty lines
+// and a final line without a trailing newline.
+#[test]
+fn find_lines_iter() -> Result<(), Box<dyn Error>> {
+    let re = Regex::new(r"^\w+")?;
+    let mut cache = re.create_cache();
+    let haystack = b"foo bar\nbaz\n\nquux 1";
+    let matches = re
+        .try_find_lines_iter(&mut cache, haystack)
+        .collect::<Result<Vec<MultiMatch>, _>>()?;
+    assert_eq!(
+        matches,
+        vec![
+            MultiMatch::must(0, 0, 3),
+            MultiMatch::must(0, 8, 11),
+            MultiMatch::must(0, 13, 17),
+        ]
+    );
+
+    // An empty pattern matches at every offset within each line, including
+    // the end of the line just before its '\n', since each line is searched
+    // as its own haystack. A trailing newline doesn't begin a new line, so
+    // there is no match at the very end of the haystack.
+    let re = Regex::new(r"")?;
+    let mut cache = re.create_cache();
+    let matches = re
+        .try_find_lines_iter(&mut cache, b"ab\n\nc\n")
+        .collect::<Result<Vec<MultiMatch>, _>>()?;
+    assert_eq!(
+        matches,
+        vec![
+            MultiMatch::must(0, 0, 0),
+            MultiMatch::must(0, 1, 1),
+            MultiMatch::must(0, 2, 2),
+            MultiMatch::must(0, 3, 3),
+            MultiMatch::must(0, 4, 4),
+            MultiMatch::must(0, 5, 5),
+        ]
+    );
+    assert_eq!(0, re.try_find_lines_iter(&mut cache, b"").count());
+    Ok(())
+}
+
+// Tests introspection of whether a regex is anchored at the start and whether
+// it can match the empty string, for each combination of the two.
+#[test]
+fn anchored_and_empty_introspection() -> Result<(), Box<dyn Error>> {
+    let re = Regex::new(r"^foo")?;
+    assert!(re.is_always_anchored_start());
+    assert!(!re.can_match_empty());
+
+    let re = Regex::new(r"foo")?;
+    assert!(!re.is_always_anchored_start());
+    assert!(!re.can_match_empty());
+
+    let re = Regex::new(r"a*")?;
+    assert!(!re.is_always_anchored_start());
+    assert!(re.can_match_empty());
+
+    let re = Regex::new(r"^a*")?;
+    assert!(re.is_always_anchored_start());
+    assert!(re.can_match_empty());
+
+    // A regex is only anchored when every pattern is anchored.
+    let re = Regex::new_many(&["^foo", "bar"])?;
+    assert!(!re.is_always_anchored_start());
+    Ok(())
+}
+
+// Tests that a lazy DFA gives up once its cache has computed the maximum total
+// number of states, even when the cache never fills up, and that resetting
+// the cache starts the count over.
+#[test]
+fn max_total_states_cause_quit() -> Result<(), Box<dyn Error>> {
+    // The DFA for this regex needs a state for every 11 bit suffix, and a
+    // haystack containing every 12 bit string forces it to compute them.
+    let pattern = r"[01]*1[01]{10}$";
+    let haystack: String = (0..4096).map(|n| format!("{:012b}", n)).collect();
+    let expected = Some(HalfMatch::must(0, haystack.len()));
+
+    let dfa = DFA::new(pattern)?;
+    let mut cache = dfa.create_cache();
+    assert_eq!(
+        expected,
+        dfa.find_leftmost_fwd(&mut cache, haystack.as_bytes())?
+    );
+    assert_eq!(0, cache.clear_count());
+
+    let dfa = DFA::builder()
+        .configure(DFA::config().max_total_states(Some(100)))
+        .build(pattern)?;
+    let mut cache = dfa.create_cache();
+    assert!(matches!(
+        dfa.find_leftmost_fwd(&mut cache, haystack.as_bytes()),
+        Err(MatchError::GaveUp { .. }),
+    ));
+    // The ceiling persists across searches until the cache is reset.
+    assert!(matches!(
+        dfa.find_leftmost_fwd(&mut cache, haystack.as_bytes()),
+        Err(MatchError::GaveUp { .. }),
+    ));
+    cache.reset(&dfa);
+    assert_eq!(
+        Some(HalfMatch::must(0, 11)),
+        dfa.find_leftmost_fwd(&mut cache, b"10000000000")?,
+    );
+
+    // Start states count toward the ceiling too, so a search can give up
+    // before it examines a single byte.
+    let dfa = DFA::builder()
+        .configure(DFA::config().max_total_states(Some(0)))
+        .build(pattern)?;
+    let mut cache = dfa.create_cache();
+    assert_eq!(
+        Err(MatchError::GaveUp { offset: 0 }),
+        dfa.find_leftmost_fwd(&mut cache, b"10000000000"),
+    );
+
+    // A ceiling above the number of states needed never gets in the way.
+    let dfa = DFA::builder()
+        .configure(DFA::config().max_total_states(Some(100_000)))
+        .build(pattern)?;
+    let mut cache = dfa.create_cache();
+    assert_eq!(
+        expected,
+        dfa.find_leftmost_fwd(&mut cache, haystack.as_bytes())?
+    );
+    Ok(())
+}
+
+// Tests that the memory usage of a lazy DFA reflects the size of its NFA and
+// doesn't change as its cache is used.
+#[test]
+fn dfa_memory_usage() -> Result<(), Box<dyn Error>> {
+    let small = DFA::new(r"a")?;
+    let big = DFA::new(r"\w{50}")?;
+    assert!(small.memory_usage() < big.memory_usage());
+
+    let before = big.memory_usage();
+    let mut cache = big.create_cache();
+    big.find_leftmost_fwd(&mut cache, "β".repeat(60).as_bytes())?;
+    assert_eq!(before, big.memory_usage());
+    Ok(())
+}
+
+// Tests that dumping a lazy DFA's cache after a search shows the start and
+// match states it computed, and that clearing the cache drops them.
+#[test]
+fn debug_cache() -> Result<(), Box<dyn Error>> {
+    let dfa = DFA::new("foo")?;
+    let mut cache = dfa.create_cache();
+    assert!(!dfa.debug_cache(&cache).contains("=> "));
+
+    assert_eq!(
+        Some(HalfMatch::must(0, 4)),
+        dfa.find_leftmost_fwd(&mut cache, b"xfoo")?,
+    );
+    let dump = dfa.debug_cache(&cache);
+    assert!(dump.contains("START-GROUP(ALL)"));
+    assert!(dump.lines().any(|line| line.starts_with("> ")), "{}", dump);
+    assert!(dump.lines().any(|line| line.starts_with(" *")), "{}", dump);
+    assert!(dump.contains("f => "), "{}", dump);
+
+    cache.clear(&dfa);
+    let dump = dfa.debug_cache(&cache);
+    assert!(!dump.lines().any(|line| line.starts_with(" *")), "{}", dump);
+    Ok(())
+}
+
+// Tests that a lazy DFA's quit bytes include explicitly configured quit bytes,
+// and all non-ASCII bytes when heuristic Unicode word boundaries are needed.
+#[test]
+fn quit_bytes() -> Result<(), Box<dyn Error>> {
+    let dfa = DFA::new(r"\w+")?;
+    assert!(dfa.quit_bytes().is_empty());
+
+    let dfa = DFA::builder()
+        .configure(DFA::config().quit(b'\n', true))
+        .build(r"\w+")?;
+    let quit = dfa.quit_bytes();
+    assert!(quit.contains(b'\n'));
+    assert!(!quit.contains(0x80));
+
+    let dfa = DFA::builder()
+        .configure(DFA::config().unicode_word_boundary(true))
+        .build(r"\b\w+\b")?;
+    let quit = dfa.quit_bytes();
+    assert!(quit.contains(0x80));
+    assert!(quit.contains_range(0x80, 0xFF));
+    assert!(!quit.contains(b'a'));
+
+    // Any quit error reported by a search names a byte in the quit set.
+    let mut cache = dfa.create_cache();
+    match dfa.find_leftmost_fwd(&mut cache, "β abc".as_bytes()) {
+        Err(MatchError::Quit { byte, .. }) => assert!(quit.contains(byte)),
+        result => panic!("expected quit error, but got {:?}", result),
+    }
+    Ok(())
+}
@@ -23,6 +23,21 @@ impl Prefilter for SubstringPrefilter {
             .unwrap_or(Candidate::None)
     }
 
+    #[inline]
+    fn find_rev(
+        &self,
+        _state: &mut prefilter::State,
+        haystack: &[u8],
+        at: usize,
+    ) -> Option<Candidate> {
+        let needle_len = self.0.needle().len();
+        Some(
+            bstr::ByteSlice::rfind(&haystack[..at], self.0.needle())
+                .map(|i| Candidate::PossibleStartOfMatch(i + needle_len))
+                .unwrap_or(Candidate::None),
+        )
+    }
+
     fn heap_bytes(&self) -> usize {
         self.0.needle().len()
     }
@@ -51,6 +66,16 @@ impl Prefilter for BunkPrefilter {
         Candidate::None
     }
 
+    #[inline]
+    fn find_rev(
+        &self,
+        _state: &mut prefilter::State,
+        _haystack: &[u8],
+        _at: usize,
+    ) -> Option<Candidate> {
+        Some(Candidate::None)
+    }
+
     fn heap_bytes(&self) -> usize {
         0
     }